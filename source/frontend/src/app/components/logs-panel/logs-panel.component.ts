@@ -319,6 +319,10 @@ export class LogsPanelComponent implements OnInit, OnDestroy, AfterViewChecked {
     
     this.websocket.onopen = () => {
       // Silent connection - no console logging to prevent loops
+      const token = localStorage.getItem('access_token');
+      if (token) {
+        this.websocket?.send(JSON.stringify({ type: 'auth', token }));
+      }
       // Subscribe to Log Display Agent UI state
       this.websocket?.send(JSON.stringify({
         type: 'subscribe',
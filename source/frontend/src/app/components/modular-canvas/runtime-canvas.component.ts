@@ -95,6 +95,7 @@ export class RuntimeCanvasComponent implements OnInit, AfterViewInit, OnDestroy,
 
   private historySubscription?: Subscription;
   private realtimeDeltaSubscription?: Subscription;
+  private realtimeResyncSubscription?: Subscription;
   private configSubscriptions: Subscription[] = [];
   private eventHubSubscription?: Subscription;
   private runtimeConfigQueue: Promise<void> = Promise.resolve();
@@ -144,6 +145,7 @@ export class RuntimeCanvasComponent implements OnInit, AfterViewInit, OnDestroy,
     // No automatic saving - layout persistence handled by explicit Save button
     // Disconnect from realtime service
     this.realtimeDeltaSubscription?.unsubscribe();
+    this.realtimeResyncSubscription?.unsubscribe();
     this.neo4jRealtimeService.disconnect();
 
     // Unregister from control service
@@ -1010,6 +1012,7 @@ private compareRawGraphWithLayout(rawData: { entities: any[]; relationships: any
   private setupRealtimeSubscription(): void {
     // Unsubscribe from any previous subscription
     this.realtimeDeltaSubscription?.unsubscribe();
+    this.realtimeResyncSubscription?.unsubscribe();
 
     // Only subscribe if we have a ViewNode with an ID
     if (!this.selectedViewNode?.id || !this.engine) {
@@ -1034,6 +1037,13 @@ private compareRawGraphWithLayout(rawData: { entities: any[]; relationships: any
         console.error('[RuntimeCanvas] Error applying graph delta:', error);
       }
     });
+
+    // Deltas were missed while disconnected - reload the whole view
+    this.realtimeResyncSubscription = this.neo4jRealtimeService.getResync$().subscribe(viewNodeId => {
+      if (viewNodeId === this.selectedViewNode?.id) {
+        this.loadViewNodeData(viewNodeId);
+      }
+    });
   }
 
   private resizeCanvas(): void {
@@ -197,7 +197,8 @@ export class Neo4jDataService {
         this.http.post<RuntimeGraphResponse>('/runtime/canvas/data', {
           query: queryToExecute,
           parameters: {},
-          include_raw_rows: true
+          include_raw_rows: true,
+          view_node_id: viewNode.GUID ?? viewNode.id
         })
       );
 
@@ -1,6 +1,12 @@
 import { Injectable } from '@angular/core';
 import { BehaviorSubject, Observable, Subject } from 'rxjs';
 
+/** Version of the /ws message schema this client speaks */
+const PROTOCOL_VERSION = 1;
+
+/** Close code of sockets without a valid session */
+const UNAUTHORIZED_CLOSE_CODE = 4401;
+
 /**
  * Graph delta interface aligned with backend GraphDelta
  */
@@ -13,6 +19,8 @@ export interface GraphDelta {
   nodesDeleted?: Array<string>;
   relationshipsCreated?: Array<any>;
   relationshipsDeleted?: Array<string>;
+  /** Redis stream entry ID, sent back on resubscribe to replay missed deltas */
+  streamId?: string;
 }
 
 /**
@@ -43,9 +51,12 @@ export class Neo4jRealtimeService {
   private maxReconnectAttempts = 10;
   private baseReconnectDelay = 1000; // 1 second
   private reconnectTimer: any = null;
+  // Last delta stream ID received per ViewNode, used to resume after a reconnect
+  private lastStreamIds = new Map<string, string>();
 
   // Observables
   private delta$ = new Subject<GraphDelta>();
+  private resync$ = new Subject<string>();
   private status$ = new BehaviorSubject<RealtimeConnectionStatus>(
     RealtimeConnectionStatus.Disconnected
   );
@@ -60,6 +71,13 @@ export class Neo4jRealtimeService {
     return this.delta$.asObservable();
   }
 
+  /**
+   * Get observable emitting the ViewNode ID when deltas were missed and the view must be reloaded
+   */
+  public getResync$(): Observable<string> {
+    return this.resync$.asObservable();
+  }
+
   /**
    * Get observable for connection status
    */
@@ -118,6 +136,13 @@ export class Neo4jRealtimeService {
         this.status$.next(RealtimeConnectionStatus.Connected);
         this.reconnectAttempts = 0;
 
+        // Browsers cannot set headers on the handshake, so the first frame authenticates
+        const token = localStorage.getItem('access_token');
+        if (token) {
+          this.websocket?.send(JSON.stringify({ type: 'auth', token }));
+        }
+        this.websocket?.send(JSON.stringify({ type: 'hello', version: PROTOCOL_VERSION }));
+
         // Subscribe to graph changes if we have a view node ID
         if (this.currentViewNodeId) {
           this.subscribe(this.currentViewNodeId);
@@ -136,6 +161,12 @@ export class Neo4jRealtimeService {
       this.websocket.onclose = (event) => {
         this.websocket = null;
 
+        if (event.code === UNAUTHORIZED_CLOSE_CODE) {
+          console.warn('[Neo4jRealtimeService] Session rejected:', event.reason);
+          this.status$.next(RealtimeConnectionStatus.Error);
+          return;
+        }
+
         if (this.currentViewNodeId) {
           this.scheduleReconnect();
         } else {
@@ -164,11 +195,20 @@ export class Neo4jRealtimeService {
       return;
     }
 
+    // Only the current ViewNode is followed
+    if (this.currentViewNodeId && this.currentViewNodeId !== viewNodeId) {
+      this.websocket.send(JSON.stringify({
+        type: 'unsubscribe_graph_changes',
+        viewNodeId: this.currentViewNodeId
+      }));
+    }
+
     this.currentViewNodeId = viewNodeId;
 
     const subscriptionMessage = {
       type: 'subscribe_graph_changes',
-      viewNodeId: viewNodeId
+      viewNodeId: viewNodeId,
+      lastStreamId: this.lastStreamIds.get(viewNodeId)
     };
 
     this.websocket.send(JSON.stringify(subscriptionMessage));
@@ -183,16 +223,34 @@ export class Neo4jRealtimeService {
     }
 
     switch (data.type) {
+      case 'welcome':
+        console.log('[Neo4jRealtimeService] Protocol version:', data.version);
+        break;
+
       case 'graph_subscription_ack':
         console.log('[Neo4jRealtimeService] Subscription acknowledged:', data.viewNodeId);
         this.status$.next(RealtimeConnectionStatus.Subscribed);
         break;
 
+      case 'graph_resync_required':
+        console.warn('[Neo4jRealtimeService] Missed deltas, resync required:', data.viewNodeId);
+        this.lastStreamIds.delete(data.viewNodeId);
+        this.resync$.next(data.viewNodeId);
+        break;
+
       case 'graph_subscription_error':
         console.error('[Neo4jRealtimeService] Subscription error:', data.error);
         this.status$.next(RealtimeConnectionStatus.Error);
         break;
 
+      case 'graph_unsubscribed':
+        console.log('[Neo4jRealtimeService] Unsubscribed:', data.viewNodeId);
+        break;
+
+      case 'error':
+        console.error(`[Neo4jRealtimeService] Server rejected message (${data.code}):`, data.message);
+        break;
+
       case 'graph_delta':
         // [TIMING T4] Delta received via WebSocket
         const delta = data as GraphDelta;
@@ -206,6 +264,9 @@ export class Neo4jRealtimeService {
           console.log(`[TIMING:${traceId}:T4:${t4}] Delta received via WebSocket`);
         }
         console.log('[Neo4jRealtimeService] Received graph delta:', data);
+        if (delta.streamId) {
+          this.lastStreamIds.set(delta.viewNodeId, delta.streamId);
+        }
         this.delta$.next(delta);
         break;
 
@@ -1,6 +1,9 @@
 import { Injectable } from '@angular/core';
 import { BehaviorSubject, Observable, Subject } from 'rxjs';
 
+/** Close code of sockets without a valid session */
+const UNAUTHORIZED_CLOSE_CODE = 4401;
+
 export interface AgentRequest {
   request_id: string;
   agent_type: string;
@@ -108,6 +111,11 @@ export class RedisSpaService {
     this.websocket = new WebSocket(wsUrl);
     
     this.websocket.onopen = () => {
+      // Browsers cannot set headers on the handshake, so the first frame authenticates
+      const token = localStorage.getItem('access_token');
+      if (token) {
+        this.websocket?.send(JSON.stringify({ type: 'auth', token }));
+      }
       console.log('🔌 Redis SPA connection established');
       this.connectionSubject.next(true);
     };
@@ -128,9 +136,14 @@ export class RedisSpaService {
       }
     };
     
-    this.websocket.onclose = () => {
+    this.websocket.onclose = (event) => {
       console.log('🔌 Redis SPA connection closed');
       this.connectionSubject.next(false);
+
+      // Not signed in, or the session ended
+      if (event.code === UNAUTHORIZED_CLOSE_CODE) {
+        return;
+      }
       
       // Auto-reconnect
       setTimeout(() => this.initializeRedisConnection(), 5000);
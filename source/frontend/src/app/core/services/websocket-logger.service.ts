@@ -60,6 +60,12 @@ export class WebSocketLoggerService {
       this.websocket = new WebSocket(wsUrl);
       
       this.websocket.onopen = () => {
+        // The first frame authenticates the socket
+        const token = localStorage.getItem('access_token');
+        if (token) {
+          this.websocket!.send(JSON.stringify({ type: 'auth', token }));
+        }
+
         // Send session start marker
         const sessionStart = {
           type: 'session_start',
@@ -56,12 +56,24 @@ impl<'a> HelloBuilder<'a> {
             routing = ServerRouting::No;
         }
 
+        // From 5.1 on the credentials are sent with LOGON instead
+        let (scheme, principal, credentials) = if version.has_logon() {
+            (None, None, None)
+        } else {
+            (Some(scheme), Some(principal), Some(credentials))
+        };
+
+        let bolt_agent = (version >= Version::V5_3).then_some(BoltAgent {
+            product: crate::messages::BOLT_AGENT_PRODUCT,
+        });
+
         let metadata = Meta {
             user_agent,
             scheme,
             principal,
             credentials,
             routing,
+            bolt_agent,
         };
         Hello { metadata }
     }
@@ -75,12 +87,22 @@ impl<'a> Hello<'a> {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 struct Meta<'a> {
-    scheme: &'a str,
-    principal: &'a str,
-    credentials: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheme: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    principal: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credentials: Option<&'a str>,
     user_agent: &'a str,
     #[serde(skip_serializing_if = "ServerRouting::is_none")]
     routing: ServerRouting<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bolt_agent: Option<BoltAgent<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct BoltAgent<'a> {
+    product: &'a str,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -247,6 +269,21 @@ mod tests {
         assert_eq!(bytes, expected);
     }
 
+    #[test]
+    fn serialize_without_credentials_for_logon_versions() {
+        let hello = Hello::builder("user", "pass").build(Version::V5_1);
+        let bytes = hello.to_bytes().unwrap();
+
+        let expected = bolt()
+            .structure(1, 0x01)
+            .tiny_map(1)
+            .tiny_string("user_agent")
+            .tiny_string("neo4rs")
+            .build();
+
+        assert_eq!(bytes, expected);
+    }
+
     #[test]
     fn parse() {
         let data = bolt()
@@ -0,0 +1,64 @@
+use crate::bolt::{ExpectedResponse, Summary};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Logon<'a> {
+    auth: Auth<'a>,
+}
+
+impl<'a> Logon<'a> {
+    pub fn basic(principal: &'a str, credentials: &'a str) -> Self {
+        Self {
+            auth: Auth {
+                scheme: "basic",
+                principal,
+                credentials,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Auth<'a> {
+    scheme: &'a str,
+    principal: &'a str,
+    credentials: &'a str,
+}
+
+impl ExpectedResponse for Logon<'_> {
+    type Response = Summary<()>;
+}
+
+impl Serialize for Logon<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_variant("Request", 0x6A, "LOGON", &self.auth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bolt::Message as _, packstream::bolt};
+
+    #[test]
+    fn serialize() {
+        let logon = Logon::basic("user", "pass");
+        let bytes = logon.to_bytes().unwrap();
+
+        let expected = bolt()
+            .structure(1, 0x6A)
+            .tiny_map(3)
+            .tiny_string("scheme")
+            .tiny_string("basic")
+            .tiny_string("principal")
+            .tiny_string("user")
+            .tiny_string("credentials")
+            .tiny_string("pass")
+            .build();
+
+        assert_eq!(bytes, expected);
+    }
+}
@@ -295,12 +295,22 @@ impl From<Bolt> for crate::BoltType {
         fn conv_unrel(rel: urel::UnboundRelationship) -> BoltUnboundedRelation {
             let id = BoltInteger::new(rel.id().try_into().unwrap());
             let typ = BoltString::from(rel.typ());
+            let element_id = rel.element_id().map(BoltString::from);
             let properties = rel.into::<Bolt>().unwrap();
             let properties = BoltType::from(properties);
             let BoltType::Map(properties) = properties else {
                 panic!("properties should be a map");
             };
-            BoltUnboundedRelation::new(id, typ, properties)
+            BoltUnboundedRelation {
+                element_id,
+                ..BoltUnboundedRelation::new(id, typ, properties)
+            }
+        }
+
+        // BoltDateTime and BoltDateTimeZoneId keep wall-clock seconds, while
+        // the Bolt 5 structs carry seconds since the epoch in UTC.
+        fn utc_to_local_seconds(seconds: i64, tz_offset_seconds: i32) -> i64 {
+            seconds + i64::from(tz_offset_seconds)
         }
 
         match value {
@@ -320,6 +330,7 @@ impl From<Bolt> for crate::BoltType {
             ),
             Bolt::Node(v) => {
                 let id = v.id();
+                let element_id = v.element_id().map(BoltString::from);
                 let labels = v
                     .labels()
                     .iter()
@@ -331,17 +342,23 @@ impl From<Bolt> for crate::BoltType {
                 let BoltType::Map(properties) = properties else {
                     panic!("properties should be a map");
                 };
-                Self::Node(BoltNode::new(
-                    BoltInteger::new(id.try_into().unwrap()),
-                    BoltList::from(labels),
-                    properties,
-                ))
+                Self::Node(BoltNode {
+                    element_id,
+                    ..BoltNode::new(
+                        BoltInteger::new(id.try_into().unwrap()),
+                        BoltList::from(labels),
+                        properties,
+                    )
+                })
             }
             Bolt::Relationship(v) => {
                 let id = v.id();
                 let start_node_id = v.start_node_id();
                 let end_node_id = v.end_node_id();
                 let typ = BoltString::from(v.typ());
+                let element_id = v.element_id().map(BoltString::from);
+                let start_node_element_id = v.start_node_element_id().map(BoltString::from);
+                let end_node_element_id = v.end_node_element_id().map(BoltString::from);
                 let properties = v.into::<Bolt>().unwrap();
                 let properties = BoltType::from(properties);
                 let BoltType::Map(properties) = properties else {
@@ -353,6 +370,9 @@ impl From<Bolt> for crate::BoltType {
                     end_node_id: BoltInteger::new(end_node_id.try_into().unwrap()),
                     typ,
                     properties,
+                    element_id,
+                    start_node_element_id,
+                    end_node_element_id,
                 })
             }
             Bolt::Path(v) => {
@@ -395,12 +415,17 @@ impl From<Bolt> for crate::BoltType {
                 nanoseconds: BoltInteger::new(v.nanoseconds_since_midnight().try_into().unwrap()),
             }),
             Bolt::DateTime(v) => Self::DateTime(BoltDateTime {
-                seconds: v.seconds_since_epoch().into(),
+                seconds: utc_to_local_seconds(v.seconds_since_epoch(), v.timezone_offset_seconds())
+                    .into(),
                 nanoseconds: BoltInteger::new(v.nanoseconds().into()),
                 tz_offset_seconds: v.timezone_offset_seconds().into(),
             }),
             Bolt::DateTimeZoneId(v) => Self::DateTimeZoneId(BoltDateTimeZoneId {
-                seconds: v.seconds_since_epoch().into(),
+                seconds: v
+                    .as_chrono_datetime()
+                    .map(|dt| dt.naive_local().and_utc().timestamp())
+                    .unwrap_or_else(|| v.seconds_since_epoch())
+                    .into(),
                 nanoseconds: BoltInteger::new(v.nanoseconds().into()),
                 tz_id: v.timezone_identifier().into(),
             }),
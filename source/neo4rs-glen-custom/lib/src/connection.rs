@@ -4,8 +4,8 @@ use crate::messages::HelloBuilder;
 #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
 use {
     crate::bolt::{
-        ConnectionsHints, ExpectedResponse, Hello, HelloBuilder, Message, MessageResponse, Reset,
-        Summary,
+        ConnectionsHints, ExpectedResponse, Hello, HelloBuilder, Logoff, Logon, Message,
+        MessageResponse, Reset, Summary,
     },
     log::debug,
 };
@@ -56,6 +56,10 @@ impl Connection {
         let mut connection = Self::prepare(&info.prepare).await?;
         let hello = info.init.to_hello(connection.version);
         connection.hello(hello).await?;
+        if connection.version.has_logon() {
+            let logon = info.init.to_logon();
+            connection.logon(logon).await?;
+        }
         Ok(connection)
     }
 
@@ -135,6 +139,55 @@ impl Connection {
         }
     }
 
+    #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+    async fn logon(&mut self, req: BoltRequest) -> Result<()> {
+        match self.send_recv(req).await? {
+            BoltResponse::Success(_msg) => Ok(()),
+            BoltResponse::Failure(msg) => {
+                Err(Error::AuthenticationError(msg.get("message").unwrap()))
+            }
+            msg => Err(msg.into_error("LOGON")),
+        }
+    }
+
+    #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+    async fn logon(&mut self, logon: Logon<'_>) -> Result<()> {
+        match self.send_recv_as(logon).await? {
+            Summary::Success(_) => Ok(()),
+            Summary::Ignored => Err(Error::RequestIgnoredError),
+            Summary::Failure(msg) => Err(Error::AuthenticationError(msg.message)),
+        }
+    }
+
+    /// Drops the authentication of this connection, leaving it open for a new LOGON.
+    /// Only available from Bolt 5.1 on.
+    pub async fn logoff(&mut self) -> Result<()> {
+        if !self.version.has_logon() {
+            return Err(Error::UnexpectedMessage(format!(
+                "LOGOFF is not supported by Bolt {}",
+                self.version
+            )));
+        }
+
+        #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+        {
+            match self.send_recv(BoltRequest::logoff()).await? {
+                BoltResponse::Success(_) => Ok(()),
+                BoltResponse::Failure(f) => Err(Error::Neo4j(f.into_error())),
+                msg => Err(msg.into_error("LOGOFF")),
+            }
+        }
+
+        #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+        {
+            match self.send_recv_as(Logoff).await? {
+                Summary::Success(_) => Ok(()),
+                Summary::Ignored => Err(Error::RequestIgnoredError),
+                Summary::Failure(err) => Err(Error::ConnectionClosed(err)),
+            }
+        }
+    }
+
     #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
     pub async fn route(&mut self, route: Route) -> Result<RoutingTable> {
         debug!("Routing request: {}", route);
@@ -357,6 +410,16 @@ impl InitOpts {
                 .build(version),
         }
     }
+
+    #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+    pub(crate) fn to_logon(&self) -> BoltRequest {
+        BoltRequest::logon((&*self.user).into(), (&*self.password).into())
+    }
+
+    #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+    pub(crate) fn to_logon(&self) -> Logon {
+        Logon::basic(&self.user, &self.password)
+    }
 }
 
 #[derive(Clone)]
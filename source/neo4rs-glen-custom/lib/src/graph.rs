@@ -19,7 +19,7 @@ use crate::{
     pool::{create_pool, ConnectionPool},
     query::Query,
     stream::DetachedRowStream,
-    txn::Txn,
+    txn::{RetryableTxn, Txn, TxnConfig, TxnQueryResult},
     Operation,
 };
 use backon::{ExponentialBuilder, RetryableWithContext};
@@ -163,6 +163,46 @@ impl Graph {
             .await
     }
 
+    /// Executes the queries one after the other in a single write transaction on the configured
+    /// database and returns the records and the summary of each query.
+    ///
+    /// Unlike with [`Graph::start_txn`], the whole transaction is retried on certain failures.
+    /// All errors with the `Transient` error class as well as a few other error classes are considered retryable.
+    /// Retries happen with an exponential backoff until a retry delay exceeds 60s, at which point the transaction fails with the last error as it would without any retry.
+    pub async fn execute_txn<Q: Into<Query>>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+    ) -> Result<Vec<TxnQueryResult>> {
+        self.execute_txn_with(queries, TxnConfig::default()).await
+    }
+
+    /// Same as [`Graph::execute_txn`], with the timeout and metadata of `config` sent when the
+    /// transaction begins. The timeout applies to every attempt on its own, not to the retries.
+    pub async fn execute_txn_with<Q: Into<Query>>(
+        &self,
+        queries: impl IntoIterator<Item = Q>,
+        config: TxnConfig,
+    ) -> Result<Vec<TxnQueryResult>> {
+        let queries = queries.into_iter().map(Into::into).collect();
+        let txn = RetryableTxn::new(
+            &self.pool,
+            queries,
+            self.config.db.clone(),
+            self.config.fetch_size,
+            config,
+        );
+
+        let (_, result) = RetryableTxn::retry_execute
+            .retry(self.pool.backoff())
+            .sleep(tokio::time::sleep)
+            .context(txn)
+            .when(|e| matches!(e, Retry::Yes(_)))
+            .notify(Self::log_retry)
+            .await;
+
+        result.map_err(Retry::into_inner)
+    }
+
     #[allow(unused_variables)]
     async fn impl_start_txn_on(
         &self,
@@ -173,11 +213,26 @@ impl Graph {
         let connection = self.pool.get(Some(operation), db.clone()).await?;
         #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
         {
-            Txn::new(db, self.config.fetch_size, connection, operation, bookmarks).await
+            Txn::new(
+                db,
+                self.config.fetch_size,
+                connection,
+                operation,
+                bookmarks,
+                &TxnConfig::default(),
+            )
+            .await
         }
         #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
         {
-            Txn::new(db, self.config.fetch_size, connection, operation).await
+            Txn::new(
+                db,
+                self.config.fetch_size,
+                connection,
+                operation,
+                &TxnConfig::default(),
+            )
+            .await
         }
     }
 
@@ -219,7 +274,7 @@ impl Graph {
     }
 
     #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
-    pub async fn run_on(&self, db: impl Into<Database>, q: impl Into<Query>) -> Result<()> {
+    pub async fn run_on(&self, db: impl Into<Database>, q: impl Into<Query>) -> Result<RunResult> {
         self.impl_run_on(Some(db.into()), q.into(), Operation::Write)
             .await
     }
@@ -5,6 +5,8 @@ mod discard;
 mod failure;
 mod hello;
 mod ignore;
+mod logoff;
+mod logon;
 mod pull;
 mod record;
 mod reset;
@@ -16,7 +18,7 @@ use crate::{
     errors::{Error, Result},
     types::{BoltMap, BoltWireFormat},
     version::Version,
-    BoltString, BoltType,
+    BoltString, BoltType, TxnConfig,
 };
 use begin::Begin;
 use bytes::Bytes;
@@ -26,6 +28,9 @@ use record::Record;
 use run::Run;
 pub(crate) use success::Success;
 
+/// Sent as `bolt_agent.product` in HELLO, which is mandatory from Bolt 5.3 on.
+pub(crate) const BOLT_AGENT_PRODUCT: &str = concat!("neo4rs/", env!("CARGO_PKG_VERSION"));
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum BoltResponse {
     Success(Success),
@@ -42,6 +47,16 @@ pub enum BoltRequest {
         deprecated(since = "0.9.0", note = "Use `crate::bolt::Hello` instead.")
     )]
     Hello(hello::Hello),
+    #[cfg_attr(
+        feature = "unstable-bolt-protocol-impl-v2",
+        deprecated(since = "0.9.0", note = "Use `crate::bolt::Logon` instead.")
+    )]
+    Logon(logon::Logon),
+    #[cfg_attr(
+        feature = "unstable-bolt-protocol-impl-v2",
+        deprecated(since = "0.9.0", note = "Use `crate::bolt::Logoff` instead.")
+    )]
+    Logoff(logoff::Logoff),
     Run(Run),
     #[cfg_attr(
         feature = "unstable-bolt-protocol-impl-v2",
@@ -124,17 +139,45 @@ impl BoltRequest {
     ) -> BoltRequest {
         let mut data = BoltMap::default();
         data.put("user_agent".into(), BoltType::String(agent));
-        data.put("scheme".into(), "basic".into());
-        data.put("principal".into(), BoltType::String(principal));
-        data.put("credentials".into(), BoltType::String(credentials));
+        // From 5.1 on the credentials are sent with LOGON instead
+        if !version.has_logon() {
+            data.put("scheme".into(), "basic".into());
+            data.put("principal".into(), BoltType::String(principal));
+            data.put("credentials".into(), BoltType::String(credentials));
+        }
         if version >= Version::V4_1 {
             if let Some(context) = routing {
                 data.put("routing".into(), BoltType::Map(context));
             }
         }
+        if version >= Version::V5_3 {
+            let mut bolt_agent = BoltMap::default();
+            bolt_agent.put("product".into(), BOLT_AGENT_PRODUCT.into());
+            data.put("bolt_agent".into(), BoltType::Map(bolt_agent));
+        }
         BoltRequest::Hello(hello::Hello::new(data))
     }
 
+    #[cfg_attr(
+        feature = "unstable-bolt-protocol-impl-v2",
+        deprecated(since = "0.9.0", note = "Use `crate::bolt::Logon` instead.")
+    )]
+    pub fn logon(principal: BoltString, credentials: BoltString) -> BoltRequest {
+        let mut auth = BoltMap::default();
+        auth.put("scheme".into(), "basic".into());
+        auth.put("principal".into(), BoltType::String(principal));
+        auth.put("credentials".into(), BoltType::String(credentials));
+        BoltRequest::Logon(logon::Logon::new(auth))
+    }
+
+    #[cfg_attr(
+        feature = "unstable-bolt-protocol-impl-v2",
+        deprecated(since = "0.9.0", note = "Use `crate::bolt::Logoff` instead.")
+    )]
+    pub fn logoff() -> BoltRequest {
+        BoltRequest::Logoff(logoff::Logoff::new())
+    }
+
     pub fn run(query: &str, params: BoltMap, extra: BoltMap) -> BoltRequest {
         BoltRequest::Run(Run::new(query.into(), params, extra))
     }
@@ -167,8 +210,19 @@ impl BoltRequest {
         feature = "unstable-bolt-protocol-impl-v2",
         deprecated(since = "0.9.0", note = "Use `crate::bolt::Begin` instead.")
     )]
-    pub fn begin(db: Option<&str>) -> BoltRequest {
-        let extra = db.into_iter().map(|db| ("db".into(), db.into())).collect();
+    pub fn begin(db: Option<&str>, config: &TxnConfig) -> BoltRequest {
+        let mut extra: BoltMap = db.into_iter().map(|db| ("db".into(), db.into())).collect();
+        if let Some(timeout) = config.timeout() {
+            extra.put("tx_timeout".into(), (timeout.as_millis() as i64).into());
+        }
+        if !config.metadata().is_empty() {
+            let metadata = config
+                .metadata()
+                .iter()
+                .map(|(key, value)| (key.as_str().into(), value.as_str().into()))
+                .collect();
+            extra.put("tx_metadata".into(), BoltType::Map(metadata));
+        }
         let begin = Begin::new(extra);
         BoltRequest::Begin(begin)
     }
@@ -203,6 +257,8 @@ impl BoltRequest {
     pub fn into_bytes(self, version: Version) -> Result<Bytes> {
         let bytes: Bytes = match self {
             BoltRequest::Hello(hello) => hello.into_bytes(version)?,
+            BoltRequest::Logon(logon) => logon.into_bytes(version)?,
+            BoltRequest::Logoff(logoff) => logoff.into_bytes(version)?,
             BoltRequest::Run(run) => run.into_bytes(version)?,
             BoltRequest::Pull(pull) => pull.into_bytes(version)?,
             BoltRequest::Discard(discard) => discard.into_bytes(version)?,
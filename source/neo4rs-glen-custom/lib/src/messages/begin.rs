@@ -16,8 +16,11 @@ impl Begin {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::messages::BoltRequest;
     use crate::version::Version;
+    use crate::TxnConfig;
     use bytes::*;
+    use std::time::Duration;
 
     #[test]
     fn should_serialize_begin() {
@@ -52,4 +55,38 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn should_send_the_transaction_config() {
+        let config = TxnConfig::new()
+            .with_timeout(Duration::from_secs(2))
+            .with_metadata("app", "neo4rs");
+
+        let BoltRequest::Begin(begin) = BoltRequest::begin(Some("neo4j"), &config) else {
+            panic!("expected a BEGIN request");
+        };
+
+        let metadata: BoltMap = vec![("app".into(), "neo4rs".into())].into_iter().collect();
+        assert_eq!(
+            begin,
+            Begin::new(
+                vec![
+                    ("db".into(), "neo4j".into()),
+                    ("tx_timeout".into(), 2000.into()),
+                    ("tx_metadata".into(), BoltType::Map(metadata)),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+    }
+
+    #[test]
+    fn should_omit_an_empty_transaction_config() {
+        let BoltRequest::Begin(begin) = BoltRequest::begin(None, &TxnConfig::default()) else {
+            panic!("expected a BEGIN request");
+        };
+
+        assert_eq!(begin, Begin::new(BoltMap::new()));
+    }
 }
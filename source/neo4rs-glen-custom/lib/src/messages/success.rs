@@ -15,6 +15,17 @@ impl Success {
     {
         self.metadata.get::<T>(key)
     }
+
+    #[cfg(all(
+        feature = "unstable-result-summary",
+        not(feature = "unstable-bolt-protocol-impl-v2")
+    ))]
+    pub(crate) fn to<'this, T>(&'this self) -> Result<T, DeError>
+    where
+        T: Deserialize<'this>,
+    {
+        self.metadata.to::<T>()
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+use std::ops::{Deref, DerefMut};
 use std::time::Duration;
 
 use crate::auth::ConnectionTLSConfig;
@@ -8,7 +9,7 @@ use crate::{
 };
 use backon::ExponentialBuilder;
 use deadpool::managed::{Manager, Metrics, Object, Pool, RecycleResult};
-use log::{info, trace};
+use log::{debug, info, trace};
 
 pub type ConnectionPool = Pool<ConnectionManager>;
 pub type ManagedConnection = Object<ConnectionManager>;
@@ -60,6 +61,69 @@ impl Manager for ConnectionManager {
     }
 }
 
+/// A connection taken from the pool for an exchange with the server, such as a
+/// result that is still being read or an open transaction. Dropped before it is
+/// [released](ActiveConnection::release), e.g. by a cancelled future or an
+/// abandoned stream, it is reset right away, which stops the query and rolls back
+/// its transaction on the server, instead of when the pool hands it out again.
+pub(crate) struct ActiveConnection {
+    /// Only taken when dropped
+    connection: Option<ManagedConnection>,
+    released: bool,
+}
+
+impl ActiveConnection {
+    pub(crate) fn new(connection: ManagedConnection) -> Self {
+        ActiveConnection {
+            connection: Some(connection),
+            released: false,
+        }
+    }
+
+    /// The exchange is over, the connection can go back to the pool as it is.
+    pub(crate) fn release(&mut self) {
+        self.released = true;
+    }
+}
+
+impl Deref for ActiveConnection {
+    type Target = ManagedConnection;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection
+            .as_ref()
+            .expect("connection taken before drop")
+    }
+}
+
+impl DerefMut for ActiveConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.connection
+            .as_mut()
+            .expect("connection taken before drop")
+    }
+}
+
+impl Drop for ActiveConnection {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let Some(mut connection) = self.connection.take() else {
+            return;
+        };
+        // Outside of a runtime the connection is reset when it is recycled
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Err(e) = connection.reset().await {
+                    debug!("discarding connection that failed to reset: {e}");
+                    drop(Object::take(connection));
+                }
+            });
+        }
+    }
+}
+
 pub fn create_pool(config: &Config) -> Result<ConnectionPool> {
     let mgr = ConnectionManager::new(
         &config.uri,
@@ -1,21 +1,23 @@
 use std::cell::{Cell, RefCell};
 
 #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
-use crate::{bolt::Summary, summary::ResultSummary};
+use crate::bolt::Summary;
+#[cfg(feature = "unstable-result-summary")]
+use crate::summary::ResultSummary;
 use crate::{
     errors::Result,
     graph::ConnectionPoolManager,
     messages::{BoltRequest, BoltResponse},
-    pool::ManagedConnection,
+    pool::{ActiveConnection, ManagedConnection},
     retry::Retry,
     stream::{DetachedRowStream, RowStream},
     types::{BoltList, BoltMap, BoltString, BoltType},
     Database, Error, Operation, Success,
 };
 
-#[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+#[cfg(feature = "unstable-result-summary")]
 pub type RunResult = ResultSummary;
-#[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+#[cfg(not(feature = "unstable-result-summary"))]
 pub type RunResult = ();
 
 /// Abstracts a cypher query that is sent to neo4j server.
@@ -132,8 +134,9 @@ impl Query {
     pub(crate) async fn execute_retryable(
         &self,
         fetch_size: usize,
-        mut connection: ManagedConnection,
+        connection: ManagedConnection,
     ) -> QueryResult<DetachedRowStream> {
+        let mut connection = ActiveConnection::new(connection);
         let request = BoltRequest::run(&self.query, self.params.clone(), self.extra.clone());
         Self::try_execute(request, fetch_size, &mut connection)
             .await
@@ -168,13 +171,13 @@ impl Query {
             let fields: BoltList = success.get("fields").unwrap_or_default();
             let qid: i64 = success.get("qid").unwrap_or(-1);
 
-            #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+            #[cfg(feature = "unstable-result-summary")]
             {
                 let available: i64 = success.get("t_first").unwrap_or(-1);
                 RowStream::new(qid, available, fields, fetch_size)
             }
 
-            #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+            #[cfg(not(feature = "unstable-result-summary"))]
             {
                 RowStream::new(qid, fields, fetch_size)
             }
@@ -216,16 +219,19 @@ impl std::fmt::Debug for Query {
 pub(crate) type QueryResult<T> = Result<T, Retry<Error>>;
 
 fn wrap_error<T>(resp: impl IntoError, req: &'static str) -> QueryResult<T> {
-    let error = resp.into_error(req);
+    Err(classify_error(resp.into_error(req)))
+}
+
+pub(crate) fn classify_error(error: Error) -> Retry<Error> {
     let can_retry = match &error {
         Error::Neo4j(e) => e.can_retry(),
         _ => false,
     };
 
     if can_retry {
-        Err(Retry::yes(error))
+        Retry::yes(error)
     } else {
-        Err(Retry::no(error))
+        Retry::no(error)
     }
 }
 
@@ -249,8 +255,12 @@ impl<'a> RetryableQuery<'a> {
     }
 
     async fn run(&self) -> QueryResult<RunResult> {
-        let mut connection = self.connect().await?;
-        self.query.run_retryable(&mut connection).await
+        let mut connection = ActiveConnection::new(self.connect().await?);
+        let result = self.query.run_retryable(&mut connection).await;
+        if result.is_ok() {
+            connection.release();
+        }
+        result
     }
 
     pub(crate) async fn retry_execute(self) -> (Self, QueryResult<DetachedRowStream>) {
@@ -182,67 +182,188 @@ impl Row {
     }
 
     /// Convert BOLT values to proper JSON (matches frontend parser output)
+    ///
+    /// Every value has a defined encoding: points become `{srid, x, y, z}` objects, paths
+    /// become ordered `nodes` / `relationships` arrays, and temporal values and bytes
+    /// become single-key type hints holding an ISO-8601 or base64 string, e.g.
+    /// `{"$datetime": "2024-03-09T14:05:07+01:00"}` or `{"$bytes": "AAEC"}`. The gateway
+    /// reads the same encodings as query parameters, so values can be sent back unchanged.
     fn convert_bolt_to_json(&self, bolt_value: &crate::types::BoltType) -> serde_json::Value {
         use crate::types::BoltType;
 
         match bolt_value {
             BoltType::String(s) => serde_json::Value::String(s.value.clone()),
             BoltType::Integer(i) => serde_json::Value::Number(serde_json::Number::from(i.value)),
-            BoltType::Float(f) => serde_json::Value::Number(
-                serde_json::Number::from_f64(f.value).unwrap_or(serde_json::Number::from(0)),
-            ),
+            BoltType::Float(f) => float_to_json(f.value),
             BoltType::Boolean(b) => serde_json::Value::Bool(b.value),
             BoltType::Null(_) => serde_json::Value::Null,
 
-            BoltType::Node(node) => {
-                // Match frontend extractNodeData() format exactly
-                let mut properties = serde_json::Map::new();
-
-                // Extract all properties from BoltMap
-                for (prop_key, prop_value) in &node.properties.value {
-                    let prop_name = prop_key.value.clone();
-                    let prop_json = self.convert_bolt_to_json(prop_value);
-                    properties.insert(prop_name, prop_json);
-                }
-
-                // Create node object matching frontend format
-                serde_json::json!({
-                    "neo4jId": node.id.value,
-                    "GUID": properties.get("GUID").cloned(),
-                    "labels": self.convert_bolt_list_to_json(&node.labels),
-                    "properties": serde_json::Value::Object(properties)
-                })
-            }
-
-            BoltType::Relation(rel) => {
-                // Match frontend extractRelationshipData() format exactly
-                let mut properties = serde_json::Map::new();
-
-                // Extract all properties from BoltMap
-                for (prop_key, prop_value) in &rel.properties.value {
-                    let prop_name = prop_key.value.clone();
-                    let prop_json = self.convert_bolt_to_json(prop_value);
-                    properties.insert(prop_name, prop_json);
-                }
-
+            BoltType::Node(node) => self.convert_node_to_json(node),
+            BoltType::Relation(rel) => self.convert_relation_to_json(rel),
+            BoltType::UnboundedRelation(rel) => {
+                let properties = self.convert_bolt_map_to_json(&rel.properties);
                 serde_json::json!({
                     "neo4jId": rel.id.value,
+                    "elementId": rel.element_id.as_ref().map(|id| id.value.clone()),
                     "GUID": properties.get("GUID").cloned(),
                     "type": rel.typ.value.clone(),
-                    "fromGUID": properties.get("fromGUID").cloned(),
-                    "toGUID": properties.get("toGUID").cloned(),
-                    "startNodeId": rel.start_node_id.value,
-                    "endNodeId": rel.end_node_id.value,
-                    "properties": serde_json::Value::Object(properties)
+                    "properties": properties
                 })
             }
+            BoltType::Path(path) => self.convert_path_to_json(path),
 
             BoltType::List(list) => self.convert_bolt_list_to_json(list),
             BoltType::Map(map) => self.convert_bolt_map_to_json(map),
 
-            // Handle datetime and other types as strings for now
-            _ => serde_json::Value::String(format!("{:?}", bolt_value)),
+            BoltType::Point2D(point) => serde_json::json!({
+                "srid": point.sr_id.value,
+                "x": float_to_json(point.x.value),
+                "y": float_to_json(point.y.value),
+            }),
+            BoltType::Point3D(point) => serde_json::json!({
+                "srid": point.sr_id.value,
+                "x": float_to_json(point.x.value),
+                "y": float_to_json(point.y.value),
+                "z": float_to_json(point.z.value),
+            }),
+            BoltType::Bytes(bytes) => {
+                use base64::Engine as _;
+                typed_json(
+                    "$bytes",
+                    base64::engine::general_purpose::STANDARD.encode(&bytes.value),
+                )
+            }
+
+            BoltType::Date(date) => temporal_to_json(
+                "$date",
+                date.try_to_chrono()
+                    .map(|date| date.format("%Y-%m-%d").to_string()),
+                bolt_value,
+            ),
+            BoltType::Time(time) => {
+                let (time, offset) = time.to_chrono();
+                typed_json("$time", format!("{}{}", time.format(TIME_FORMAT), offset))
+            }
+            BoltType::LocalTime(time) => typed_json(
+                "$localtime",
+                time.to_chrono().format(TIME_FORMAT).to_string(),
+            ),
+            BoltType::DateTime(datetime) => temporal_to_json(
+                "$datetime",
+                datetime
+                    .try_to_chrono()
+                    .map(|datetime| datetime.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, false)),
+                bolt_value,
+            ),
+            BoltType::LocalDateTime(datetime) => temporal_to_json(
+                "$localdatetime",
+                datetime
+                    .try_to_chrono()
+                    .map(|datetime| datetime.format(LOCAL_DATE_TIME_FORMAT).to_string()),
+                bolt_value,
+            ),
+            BoltType::DateTimeZoneId(datetime) => {
+                let local = chrono::NaiveDateTime::try_from(datetime)
+                    .map(|local| local.format(LOCAL_DATE_TIME_FORMAT).to_string());
+                // The offset is informational, the zone id is what makes the value unambiguous
+                let offset = datetime
+                    .try_to_chrono()
+                    .map(|datetime| datetime.offset().to_string())
+                    .unwrap_or_default();
+                temporal_to_json(
+                    "$datetime",
+                    local.map(|local| format!("{local}{offset}[{}]", datetime.tz_id())),
+                    bolt_value,
+                )
+            }
+            BoltType::Duration(duration) => typed_json("$duration", duration_to_iso8601(duration)),
+        }
+    }
+
+    fn convert_node_to_json(&self, node: &BoltNode) -> serde_json::Value {
+        // Match frontend extractNodeData() format exactly
+        let properties = self.convert_bolt_map_to_json(&node.properties);
+
+        serde_json::json!({
+            "neo4jId": node.id.value,
+            "elementId": node.element_id.as_ref().map(|id| id.value.clone()),
+            "GUID": properties.get("GUID").cloned(),
+            "labels": self.convert_bolt_list_to_json(&node.labels),
+            "properties": properties
+        })
+    }
+
+    fn convert_relation_to_json(&self, rel: &BoltRelation) -> serde_json::Value {
+        // Match frontend extractRelationshipData() format exactly
+        let properties = self.convert_bolt_map_to_json(&rel.properties);
+
+        serde_json::json!({
+            "neo4jId": rel.id.value,
+            "elementId": rel.element_id.as_ref().map(|id| id.value.clone()),
+            "GUID": properties.get("GUID").cloned(),
+            "type": rel.typ.value.clone(),
+            "fromGUID": properties.get("fromGUID").cloned(),
+            "toGUID": properties.get("toGUID").cloned(),
+            "startNodeId": rel.start_node_id.value,
+            "endNodeId": rel.end_node_id.value,
+            "startNodeElementId": rel.start_node_element_id.as_ref().map(|id| id.value.clone()),
+            "endNodeElementId": rel.end_node_element_id.as_ref().map(|id| id.value.clone()),
+            "properties": properties
+        })
+    }
+
+    /// Paths are flattened into their nodes and relationships in traversal order,
+    /// with each relationship bound to the nodes it connects.
+    fn convert_path_to_json(&self, path: &BoltPath) -> serde_json::Value {
+        let nodes = path.nodes();
+        let rels = path.rels();
+        let indices = path.indices();
+
+        let mut relationships = Vec::with_capacity(indices.len() / 2);
+        let mut previous = nodes.first();
+        for segment in indices.chunks_exact(2) {
+            let rel_index = segment[0].value;
+            let next = nodes.get(segment[1].value as usize);
+            // Relationship indices are 1-based, 0 is not a valid one
+            let rel = (rel_index.unsigned_abs() as usize)
+                .checked_sub(1)
+                .and_then(|index| rels.get(index));
+
+            if let (Some(from), Some(to), Some(rel)) = (previous, next, rel) {
+                // A negative index means the relationship was traversed against its direction
+                let (start, end) = if rel_index > 0 {
+                    (from, to)
+                } else {
+                    (to, from)
+                };
+                let bound = BoltRelation {
+                    id: rel.id.clone(),
+                    start_node_id: start.id.clone(),
+                    end_node_id: end.id.clone(),
+                    typ: rel.typ.clone(),
+                    properties: rel.properties.clone(),
+                    element_id: rel.element_id.clone(),
+                    start_node_element_id: start.element_id.clone(),
+                    end_node_element_id: end.element_id.clone(),
+                };
+                relationships.push(self.convert_relation_to_json(&bound));
+            }
+            previous = next;
+        }
+
+        // Nodes are listed in traversal order, revisited nodes appear again
+        let mut ordered = Vec::with_capacity(indices.len() / 2 + 1);
+        ordered.extend(nodes.first().map(|node| self.convert_node_to_json(node)));
+        for segment in indices.chunks_exact(2) {
+            if let Some(node) = nodes.get(segment[1].value as usize) {
+                ordered.push(self.convert_node_to_json(node));
+            }
         }
+
+        serde_json::json!({
+            "nodes": ordered,
+            "relationships": relationships
+        })
     }
 
     fn convert_bolt_list_to_json(&self, list: &crate::types::BoltList) -> serde_json::Value {
@@ -298,6 +419,76 @@ impl Row {
     }
 }
 
+const TIME_FORMAT: &str = "%H:%M:%S%.f";
+const LOCAL_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+fn float_to_json(value: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(value)
+        .map(serde_json::Value::Number)
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// A value the gateway turns back into its Bolt type, e.g. `{"$date": "2024-03-09"}`
+fn typed_json(hint: &str, text: String) -> serde_json::Value {
+    serde_json::json!({ hint: text })
+}
+
+/// Temporal values outside of chrono's range keep the previous debug representation,
+/// as a plain string since there is nothing to parse them back from
+fn temporal_to_json(
+    hint: &str,
+    formatted: Result<String, crate::errors::Error>,
+    bolt_value: &BoltType,
+) -> serde_json::Value {
+    match formatted {
+        Ok(text) => typed_json(hint, text),
+        Err(_) => serde_json::Value::String(format!("{:?}", bolt_value)),
+    }
+}
+
+/// Formats a duration the way Neo4j prints it, e.g. `P1M2DT3.5S`
+fn duration_to_iso8601(duration: &crate::types::BoltDuration) -> String {
+    let months = duration.months.value;
+    let days = duration.days.value;
+    let total_nanos =
+        duration.seconds.value as i128 * 1_000_000_000 + duration.nanoseconds.value as i128;
+
+    let mut iso = String::from("P");
+    if months / 12 != 0 {
+        iso.push_str(&format!("{}Y", months / 12));
+    }
+    if months % 12 != 0 {
+        iso.push_str(&format!("{}M", months % 12));
+    }
+    if days != 0 {
+        iso.push_str(&format!("{days}D"));
+    }
+    if total_nanos != 0 || iso.len() == 1 {
+        let sign = if total_nanos < 0 { "-" } else { "" };
+        let total_nanos = total_nanos.unsigned_abs();
+        let seconds = total_nanos / 1_000_000_000;
+        let nanos = total_nanos % 1_000_000_000;
+
+        iso.push('T');
+        if seconds / 3600 != 0 {
+            iso.push_str(&format!("{sign}{}H", seconds / 3600));
+        }
+        if seconds % 3600 / 60 != 0 {
+            iso.push_str(&format!("{sign}{}M", seconds % 3600 / 60));
+        }
+        if seconds % 60 != 0 || nanos != 0 || seconds == 0 {
+            iso.push_str(&format!("{sign}{}", seconds % 60));
+            if nanos != 0 {
+                let fraction = format!("{nanos:09}");
+                iso.push('.');
+                iso.push_str(fraction.trim_end_matches('0'));
+            }
+            iso.push('S');
+        }
+    }
+    iso
+}
+
 impl Node {
     pub fn new(inner: BoltNode) -> Self {
         Node { inner }
@@ -308,6 +499,11 @@ impl Node {
         self.inner.id.value
     }
 
+    /// Element id of the node, only sent by servers speaking Bolt 5.0 or later
+    pub fn element_id(&self) -> Option<&str> {
+        self.inner.element_id.as_ref().map(|id| id.value.as_str())
+    }
+
     /// various labels attached to this node
     pub fn labels(&self) -> Vec<&str> {
         self.to::<crate::Labels<_>>().unwrap().0
@@ -352,6 +548,25 @@ impl Relation {
         self.inner.end_node_id.value
     }
 
+    /// Element id of the relationship, only sent by servers speaking Bolt 5.0 or later
+    pub fn element_id(&self) -> Option<&str> {
+        self.inner.element_id.as_ref().map(|id| id.value.as_str())
+    }
+
+    pub fn start_node_element_id(&self) -> Option<&str> {
+        self.inner
+            .start_node_element_id
+            .as_ref()
+            .map(|id| id.value.as_str())
+    }
+
+    pub fn end_node_element_id(&self) -> Option<&str> {
+        self.inner
+            .end_node_element_id
+            .as_ref()
+            .map(|id| id.value.as_str())
+    }
+
     pub fn typ(&self) -> &str {
         self.to::<crate::Type<_>>().unwrap().0
     }
@@ -387,6 +602,11 @@ impl UnboundedRelation {
         self.inner.id.value
     }
 
+    /// Element id of the relationship, only sent by servers speaking Bolt 5.0 or later
+    pub fn element_id(&self) -> Option<&str> {
+        self.inner.element_id.as_ref().map(|id| id.value.as_str())
+    }
+
     pub fn typ(&self) -> &str {
         self.to::<crate::Type<_>>().unwrap().0
     }
@@ -417,7 +637,7 @@ impl UnboundedRelation {
 mod tests {
     use serde::Deserialize;
 
-    use crate::types::{BoltString, BoltType};
+    use crate::types::{BoltDuration, BoltFloat, BoltString, BoltType};
 
     use super::*;
 
@@ -503,4 +723,174 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    fn single_value_json(value: BoltType) -> serde_json::Value {
+        let row = Row::new(
+            BoltList::from(vec![BoltType::from("v")]),
+            BoltList::from(vec![value]),
+        );
+        row.get_all_json()["v"].clone()
+    }
+
+    #[test]
+    fn temporal_values_are_encoded_as_iso8601_hints() {
+        use chrono::{FixedOffset, NaiveDate, NaiveTime, TimeZone};
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let time = NaiveTime::from_hms_milli_opt(14, 5, 7, 250).unwrap();
+        let offset = FixedOffset::east_opt(3600).unwrap();
+
+        assert_eq!(
+            single_value_json(date.into()),
+            serde_json::json!({"$date": "2024-03-09"})
+        );
+        assert_eq!(
+            single_value_json(time.into()),
+            serde_json::json!({"$localtime": "14:05:07.250"})
+        );
+        assert_eq!(
+            single_value_json((time, offset).into()),
+            serde_json::json!({"$time": "14:05:07.250+01:00"})
+        );
+        assert_eq!(
+            single_value_json(date.and_time(time).into()),
+            serde_json::json!({"$localdatetime": "2024-03-09T14:05:07.250"})
+        );
+        assert_eq!(
+            single_value_json(
+                offset
+                    .from_local_datetime(&date.and_time(time))
+                    .unwrap()
+                    .into()
+            ),
+            serde_json::json!({"$datetime": "2024-03-09T14:05:07.250+01:00"})
+        );
+        assert_eq!(
+            single_value_json(BoltType::DateTimeZoneId(
+                (date.and_hms_opt(14, 5, 7).unwrap(), "Europe/Paris").into()
+            )),
+            serde_json::json!({"$datetime": "2024-03-09T14:05:07+01:00[Europe/Paris]"})
+        );
+    }
+
+    #[test]
+    fn durations_are_encoded_as_iso8601() {
+        let duration = |months: i64, days: i64, seconds: i64, nanos: i64| {
+            single_value_json(BoltType::Duration(BoltDuration::new(
+                months.into(),
+                days.into(),
+                seconds.into(),
+                nanos.into(),
+            )))["$duration"]
+                .clone()
+        };
+
+        assert_eq!(duration(14, 3, 3723, 500_000_000), "P1Y2M3DT1H2M3.5S");
+        assert_eq!(duration(0, 0, 0, 0), "PT0S");
+        assert_eq!(duration(0, 2, 0, 0), "P2D");
+        assert_eq!(duration(0, 0, -90, 0), "PT-1M-30S");
+    }
+
+    #[test]
+    fn points_and_bytes_are_encoded_losslessly() {
+        let point = BoltType::Point2D(BoltPoint2D {
+            sr_id: 7203.into(),
+            x: BoltFloat::new(1.5),
+            y: BoltFloat::new(-2.0),
+        });
+        assert_eq!(
+            single_value_json(point),
+            serde_json::json!({"srid": 7203, "x": 1.5, "y": -2.0})
+        );
+
+        let point = BoltType::Point3D(BoltPoint3D {
+            sr_id: 4979.into(),
+            x: BoltFloat::new(12.99),
+            y: BoltFloat::new(55.61),
+            z: BoltFloat::new(10.0),
+        });
+        assert_eq!(
+            single_value_json(point),
+            serde_json::json!({"srid": 4979, "x": 12.99, "y": 55.61, "z": 10.0})
+        );
+
+        assert_eq!(
+            single_value_json(BoltType::from(vec![0_u8, 1, 2, 254, 255])),
+            serde_json::json!({"$bytes": "AAEC/v8="})
+        );
+    }
+
+    #[test]
+    fn paths_are_encoded_as_ordered_nodes_and_relationships() {
+        let node = |id: i64, guid: &str| {
+            BoltType::Node(BoltNode::new(
+                id.into(),
+                BoltList::from(vec![BoltType::from("Item")]),
+                [(BoltString::from("GUID"), BoltType::from(guid))]
+                    .into_iter()
+                    .collect(),
+            ))
+        };
+        let rel = |id: i64, typ: &str| {
+            BoltType::UnboundedRelation(BoltUnboundedRelation::new(
+                id.into(),
+                typ.into(),
+                BoltMap::default(),
+            ))
+        };
+
+        // (a)-[:CONTAINS]->(b)<-[:LINKS]-(c)
+        let path = BoltType::Path(BoltPath {
+            nodes: BoltList::from(vec![node(1, "a"), node(2, "b"), node(3, "c")]),
+            rels: BoltList::from(vec![rel(10, "CONTAINS"), rel(11, "LINKS")]),
+            indices: BoltList::from(vec![
+                BoltType::from(1),
+                BoltType::from(1),
+                BoltType::from(-2),
+                BoltType::from(2),
+            ]),
+        });
+
+        let json = single_value_json(path);
+
+        let guids = json["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| node["GUID"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(guids, ["a", "b", "c"]);
+
+        let relationships = json["relationships"].as_array().unwrap();
+        assert_eq!(relationships.len(), 2);
+        assert_eq!(relationships[0]["type"], "CONTAINS");
+        assert_eq!(relationships[0]["startNodeId"], 1);
+        assert_eq!(relationships[0]["endNodeId"], 2);
+        assert_eq!(relationships[1]["type"], "LINKS");
+        assert_eq!(relationships[1]["startNodeId"], 3);
+        assert_eq!(relationships[1]["endNodeId"], 2);
+    }
+
+    #[test]
+    fn paths_skip_segments_with_invalid_relationship_indices() {
+        let node = |id: i64| {
+            BoltType::Node(BoltNode::new(
+                id.into(),
+                BoltList::from(vec![BoltType::from("Item")]),
+                BoltMap::default(),
+            ))
+        };
+        let path = BoltType::Path(BoltPath {
+            nodes: BoltList::from(vec![node(1), node(2)]),
+            rels: BoltList::from(vec![BoltType::UnboundedRelation(
+                BoltUnboundedRelation::new(10.into(), "LINKS".into(), BoltMap::default()),
+            )]),
+            indices: BoltList::from(vec![BoltType::from(0), BoltType::from(1)]),
+        });
+
+        let json = single_value_json(path);
+
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+        assert!(json["relationships"].as_array().unwrap().is_empty());
+    }
 }
@@ -9,7 +9,7 @@ use crate::{
 };
 use crate::{
     errors::{Error, Result},
-    pool::ManagedConnection,
+    pool::ActiveConnection,
     row::Row,
     txn::TransactionHandle,
     types::BoltList,
@@ -26,6 +26,19 @@ type BoxedSummary = Box<ResultSummary>;
 #[cfg(not(feature = "unstable-result-summary"))]
 type BoxedSummary = ();
 
+#[cfg(all(
+    feature = "unstable-result-summary",
+    not(feature = "unstable-bolt-protocol-impl-v2")
+))]
+fn into_run_result(summary: BoxedSummary) -> RunResult {
+    *summary
+}
+
+#[cfg(not(feature = "unstable-result-summary"))]
+fn into_run_result(summary: BoxedSummary) -> RunResult {
+    summary
+}
+
 /// An abstraction over a stream of rows, this is returned as a result of [`crate::Txn::execute`].
 ///
 /// A stream needs a running transaction to be consumed.
@@ -33,7 +46,7 @@ type BoxedSummary = ();
 pub struct RowStream {
     qid: i64,
     fields: BoltList,
-    #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+    #[cfg(feature = "unstable-result-summary")]
     available_after: i64,
     state: State,
     fetch_size: usize,
@@ -43,13 +56,13 @@ pub struct RowStream {
 impl RowStream {
     pub(crate) fn new(
         qid: i64,
-        #[cfg(feature = "unstable-bolt-protocol-impl-v2")] available_after: i64,
+        #[cfg(feature = "unstable-result-summary")] available_after: i64,
         fields: BoltList,
         fetch_size: usize,
     ) -> Self {
         RowStream {
             qid,
-            #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+            #[cfg(feature = "unstable-result-summary")]
             available_after,
             fields,
             fetch_size,
@@ -57,22 +70,36 @@ impl RowStream {
             buffer: VecDeque::with_capacity(fetch_size),
         }
     }
+
+    /// Whether the server has sent the whole result
+    pub(crate) fn is_complete(&self) -> bool {
+        matches!(self.state, State::Complete(_))
+    }
 }
 
 /// An abstraction over a stream of rows, this is returned as a result of [`crate::Graph::execute`].
 ///
 /// A stream will contain a connection from the connection pool which will be released to the pool
-/// when the stream is dropped.
+/// when the stream is dropped. Dropped before the result has been read or [finished](Self::finish),
+/// the connection is reset right away, which stops the query on the server.
 #[must_use = "Results must be streamed through with `next` in order to execute the query"]
 pub struct DetachedRowStream {
     stream: RowStream,
-    connection: ManagedConnection,
+    connection: ActiveConnection,
 }
 
 impl DetachedRowStream {
-    pub(crate) fn new(stream: RowStream, connection: ManagedConnection) -> Self {
+    pub(crate) fn new(stream: RowStream, connection: ActiveConnection) -> Self {
         DetachedRowStream { stream, connection }
     }
+
+    /// Once the server has sent the whole result, the connection can go back to the
+    /// pool without being reset.
+    fn release_if_complete(&mut self) {
+        if self.stream.is_complete() {
+            self.connection.release();
+        }
+    }
 }
 
 impl RowStream {
@@ -128,13 +155,7 @@ impl RowStream {
 
                     self.state = loop {
                         match connection.recv().await {
-                            Ok(BoltResponse::Success(s)) => {
-                                break if s.get("has_more").unwrap_or(false) {
-                                    State::Ready
-                                } else {
-                                    State::Complete(())
-                                };
-                            }
+                            Ok(BoltResponse::Success(s)) => break self.state_after(s)?,
                             Ok(BoltResponse::Record(record)) => {
                                 let row = Row::new(self.fields.clone(), record.data);
                                 self.buffer.push_back(row);
@@ -245,6 +266,7 @@ impl RowStream {
 
     /// Stop consuming the stream and return a summary, if available.
     /// Stopping the stream will also discard any messages on the server side.
+    #[cfg_attr(not(feature = "unstable-result-summary"), allow(clippy::unit_arg))]
     pub async fn finish(mut self, mut handle: impl TransactionHandle) -> Result<RunResult> {
         self.buffer.clear();
 
@@ -261,7 +283,9 @@ impl RowStream {
                     Summary::Success(s) => match s.metadata {
                         Streaming::Done(summary) => *summary,
                         Streaming::HasMore => {
-                            unreachable!("Query returned has_more after a discard_all");
+                            return Err(Error::UnexpectedMessage(
+                                "unexpected has_more after DISCARD".to_string(),
+                            ));
                         }
                     },
                     Summary::Ignored => {
@@ -285,15 +309,45 @@ impl RowStream {
                         .send_recv(BoltRequest::discard_all_for(self.qid))
                         .await
                 }?;
-                let summary = match summary {
-                    crate::messages::BoltResponse::Success(_) => Ok(()),
-                    crate::messages::BoltResponse::Failure(f) => Err(Error::Neo4j(f.into_error())),
+                match summary {
+                    BoltResponse::Success(s) => match self.state_after(s)? {
+                        State::Complete(summary) => Ok(into_run_result(summary)),
+                        State::Ready => Err(Error::UnexpectedMessage(
+                            "unexpected has_more after DISCARD".to_string(),
+                        )),
+                    },
+                    BoltResponse::Failure(f) => Err(Error::Neo4j(f.into_error())),
                     msg => Err(msg.into_error("DISCARD")),
-                };
-                self.state = State::Complete(());
-                summary
+                }
             }
-            State::Complete(_) => Ok(()),
+            State::Complete(summary) => Ok(into_run_result(summary)),
+        }
+    }
+
+    /// The SUCCESS ending a PULL or DISCARD either announces more records or carries the summary.
+    #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+    fn state_after(&self, success: crate::messages::Success) -> Result<State> {
+        #[cfg(feature = "unstable-result-summary")]
+        {
+            let streaming = success
+                .to::<Streaming>()
+                .map_err(Error::DeserializationError)?;
+            Ok(match streaming {
+                Streaming::HasMore => State::Ready,
+                Streaming::Done(mut summary) => {
+                    summary.set_t_first(self.available_after);
+                    State::Complete(summary)
+                }
+            })
+        }
+
+        #[cfg(not(feature = "unstable-result-summary"))]
+        {
+            Ok(if success.get("has_more").unwrap_or(false) {
+                State::Ready
+            } else {
+                State::Complete(())
+            })
         }
     }
 
@@ -359,7 +413,9 @@ impl DetachedRowStream {
     /// if the buffer is empty and the server has more rows left to consume, then a new batch of rows
     /// are fetched from the server (using the fetch_size value configured see [`crate::ConfigBuilder::fetch_size`])
     pub async fn next(&mut self) -> Result<Option<Row>> {
-        self.stream.next(&mut self.connection).await
+        let row = self.stream.next(&mut self.connection).await;
+        self.release_if_complete();
+        row
     }
 
     /// Return the [`RowStream::next`] item,
@@ -367,7 +423,9 @@ impl DetachedRowStream {
     ///
     /// Unlike `next`, this method returns a missing items as an error ([`Error::NoMoreRows`]).
     pub async fn next_as<'this, T: DeserializeOwned + 'this>(&'this mut self) -> Result<T> {
-        self.stream.next_as(&mut self.connection).await
+        let row = self.stream.next_as(&mut self.connection).await;
+        self.release_if_complete();
+        row
     }
 
     /// Return the first [`crate::Row`] in the result.
@@ -375,7 +433,9 @@ impl DetachedRowStream {
     /// If there are 0 results, [`Error::NoMoreRows`] is returned.
     /// If there are 2 or more results, [`Error::NotSingleResult`] is returned.
     pub async fn single(&mut self) -> Result<Row> {
-        self.stream.single(&mut self.connection).await
+        let row = self.stream.single(&mut self.connection).await;
+        self.release_if_complete();
+        row
     }
 
     /// Return the first [`crate::Row`] in the result.
@@ -384,7 +444,9 @@ impl DetachedRowStream {
     /// If there are 0 results, [`Error::NoMoreRows`] is returned.
     /// If there are 2 or more results, [`Error::NotSingleResult`] is returned.
     pub async fn single_as<'this, T: DeserializeOwned + 'this>(&'this mut self) -> Result<T> {
-        self.stream.single_as(&mut self.connection).await
+        let row = self.stream.single_as(&mut self.connection).await;
+        self.release_if_complete();
+        row
     }
 
     /// Return the first [`crate::Row`] buffered result without consuming it.
@@ -436,7 +498,11 @@ impl DetachedRowStream {
     /// Stop consuming the stream and return a summary, if available.
     /// Stopping the stream will also discard any messages on the server side.
     pub async fn finish(mut self) -> Result<RunResult> {
-        self.stream.finish(&mut self.connection).await
+        let summary = self.stream.finish(&mut self.connection).await;
+        if summary.is_ok() {
+            self.connection.release();
+        }
+        summary
     }
 
     /// Turns this RowStream into a [`futures::stream::TryStream`] where
@@ -480,3 +546,101 @@ enum State {
     Ready,
     Complete(BoxedSummary),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::auth::ConnectionTLSConfig;
+    use crate::pool::{ConnectionManager, ConnectionPool};
+
+    const RESET: u8 = 0x0F;
+    /// SUCCESS without metadata as a single chunk
+    const SUCCESS: [u8; 7] = [0x00, 0x03, 0xB1, 0x70, 0xA0, 0x00, 0x00];
+
+    /// SUCCESS for HELLO, naming the server and the connection
+    fn hello_success() -> Vec<u8> {
+        let mut message = vec![0xB1, 0x70, 0xA2];
+        for text in ["server", "Neo4j/4.4.0", "connection_id", "bolt-1"] {
+            message.push(0x80 | text.len() as u8);
+            message.extend(text.as_bytes());
+        }
+        let mut chunk = (message.len() as u16).to_be_bytes().to_vec();
+        chunk.extend(message);
+        chunk.extend([0, 0]);
+        chunk
+    }
+
+    /// The signature of the next message, skipping NOOPs
+    async fn read_message(socket: &mut TcpStream) -> Option<u8> {
+        let mut message = Vec::new();
+        loop {
+            let size = socket.read_u16().await.ok()? as usize;
+            if size == 0 {
+                if message.is_empty() {
+                    continue;
+                }
+                return message.get(1).copied();
+            }
+            let mut chunk = vec![0; size];
+            socket.read_exact(&mut chunk).await.ok()?;
+            message.extend(chunk);
+        }
+    }
+
+    /// A Bolt 4.4 server for one connection, reporting the signature of every
+    /// message after HELLO and answering each with SUCCESS
+    async fn fake_server() -> (u16, mpsc::UnboundedReceiver<u8>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut handshake = [0; 20];
+            socket.read_exact(&mut handshake).await.unwrap();
+            socket.write_all(&[0, 0, 4, 4]).await.unwrap();
+            read_message(&mut socket).await;
+            socket.write_all(&hello_success()).await.unwrap();
+            while let Some(signature) = read_message(&mut socket).await {
+                let _ = tx.send(signature);
+                socket.write_all(&SUCCESS).await.unwrap();
+            }
+        });
+        (port, rx)
+    }
+
+    #[tokio::test]
+    async fn dropping_an_unfinished_stream_resets_its_connection() {
+        let (port, mut messages) = fake_server().await;
+        let manager = ConnectionManager::new(
+            &format!("bolt://127.0.0.1:{port}"),
+            "neo4j",
+            "neo4j",
+            &ConnectionTLSConfig::None,
+        )
+        .unwrap();
+        let pool = ConnectionPool::builder(manager)
+            .max_size(1)
+            .build()
+            .unwrap();
+        let connection = pool.get().await.unwrap();
+
+        #[cfg(feature = "unstable-result-summary")]
+        let stream = RowStream::new(0, -1, BoltList::new(), 10);
+        #[cfg(not(feature = "unstable-result-summary"))]
+        let stream = RowStream::new(0, BoltList::new(), 10);
+        drop(DetachedRowStream::new(
+            stream,
+            ActiveConnection::new(connection),
+        ));
+
+        // Nobody takes the connection from the pool again, which would reset it as well
+        let message = tokio::time::timeout(Duration::from_secs(5), messages.recv()).await;
+        assert_eq!(message, Ok(Some(RESET)));
+    }
+}
@@ -13,10 +13,6 @@ type MapValue = crate::bolt::Bolt;
 #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
 type Map = std::collections::HashMap<MapKey, MapValue>;
 
-#[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
-type MapKey = crate::BoltString;
-#[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
-type MapValue = crate::BoltType;
 #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
 type Map = crate::BoltMap;
 
@@ -152,6 +148,16 @@ impl ResultSummary {
         &self.notifications
     }
 
+    /// The plan of an `EXPLAIN` query, as reported by the server.
+    pub fn plan(&self) -> Option<&Map> {
+        self.plan.as_ref()
+    }
+
+    /// The profiled plan of a `PROFILE` query, as reported by the server.
+    pub fn profile(&self) -> Option<&Map> {
+        self.profile.as_ref()
+    }
+
     pub fn nodes_created(&self) -> u64 {
         self.stats.nodes_created
     }
@@ -749,4 +755,47 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+    fn parse_summary_from_bolt_map() {
+        use crate::{BoltList, BoltMap, BoltType};
+
+        let mut stats = BoltMap::default();
+        stats.put("nodes-created".into(), 2.into());
+        stats.put("properties-set".into(), 3.into());
+
+        let mut notification = BoltMap::default();
+        notification.put(
+            "code".into(),
+            "Neo.ClientNotification.Statement.CartesianProduct".into(),
+        );
+        notification.put("severity".into(), "WARNING".into());
+
+        let mut plan = BoltMap::default();
+        plan.put("operatorType".into(), "ProduceResults@neo4j".into());
+
+        let mut metadata = BoltMap::default();
+        metadata.put("type".into(), "w".into());
+        metadata.put("stats".into(), BoltType::Map(stats));
+        metadata.put(
+            "notifications".into(),
+            BoltType::List(BoltList::from(vec![BoltType::Map(notification)])),
+        );
+        metadata.put("plan".into(), BoltType::Map(plan.clone()));
+
+        let Streaming::Done(summary) = metadata.to::<Streaming>().unwrap() else {
+            panic!("expected a complete summary");
+        };
+
+        assert_eq!(summary.query_type(), Type::Write);
+        assert_eq!(summary.nodes_created(), 2);
+        assert_eq!(summary.properties_set(), 3);
+        assert_eq!(summary.notifications().len(), 1);
+        assert_eq!(
+            summary.notifications()[0].severity,
+            Some(NotificationSeverity::Warning)
+        );
+        assert_eq!(summary.plan(), Some(&plan));
+    }
 }
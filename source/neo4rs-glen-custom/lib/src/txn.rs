@@ -8,18 +8,59 @@ use {
 };
 
 use crate::{
-    config::Database, errors::Result, pool::ManagedConnection, query::Query, stream::RowStream,
-    Operation, RunResult,
+    config::Database,
+    errors::Result,
+    graph::ConnectionPoolManager,
+    pool::{ActiveConnection, ManagedConnection},
+    query::{classify_error, Query, QueryResult},
+    retry::Retry,
+    stream::RowStream,
+    Operation, Row, RunResult,
 };
+use std::time::Duration;
+
+/// Settings sent with the BEGIN of a transaction, see [`crate::Graph::execute_txn_with`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxnConfig {
+    timeout: Option<Duration>,
+    metadata: Vec<(String, String)>,
+}
+
+impl TxnConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The server terminates the transaction once it has been running for longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Metadata the server shows for the transaction in its query log and `SHOW TRANSACTIONS`
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+}
 
 /// A handle which is used to control a transaction, created as a result of [`crate::Graph::start_txn`]
 ///
 /// When a transaction is started, a dedicated connection is reserved and moved into the handle which
-/// will be released to the connection pool when the [`Txn`] handle is dropped.
+/// will be released to the connection pool when the [`Txn`] handle is dropped. Dropped without a
+/// commit or rollback, the connection is reset first, which rolls the transaction back.
 pub struct Txn {
     db: Option<Database>,
     fetch_size: usize,
-    connection: ManagedConnection,
+    connection: ActiveConnection,
     operation: Operation,
     #[allow(dead_code)]
     bookmark: Option<String>,
@@ -30,10 +71,12 @@ impl Txn {
     pub(crate) async fn new(
         db: Option<Database>,
         fetch_size: usize,
-        mut connection: ManagedConnection,
+        connection: ManagedConnection,
         operation: Operation,
+        config: &TxnConfig,
     ) -> Result<Self> {
-        let begin = BoltRequest::begin(db.as_deref());
+        let mut connection = ActiveConnection::new(connection);
+        let begin = BoltRequest::begin(db.as_deref(), config);
         match connection.send_recv(begin).await? {
             BoltResponse::Success(_) => Ok(Txn {
                 db,
@@ -50,14 +93,21 @@ impl Txn {
     pub(crate) async fn new(
         db: Option<Database>,
         fetch_size: usize,
-        mut connection: ManagedConnection,
+        connection: ManagedConnection,
         operation: Operation,
         bookmarks: &[String],
+        config: &TxnConfig,
     ) -> Result<Self> {
+        let mut connection = ActiveConnection::new(connection);
         debug!("Starting transaction with bookmarks: {:?}", bookmarks);
-        let begin = Begin::builder(db.as_deref())
-            .with_bookmarks(bookmarks.to_vec())
-            .build(connection.version());
+        let mut begin = Begin::builder(db.as_deref()).with_bookmarks(bookmarks.to_vec());
+        if let Some(timeout) = config.timeout() {
+            begin = begin.with_tx_timeout(u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX));
+        }
+        if !config.metadata().is_empty() {
+            begin = begin.with_tx_metadata(config.metadata().to_vec());
+        }
+        let begin = begin.build(connection.version());
         match connection.send_recv_as(begin).await? {
             Summary::Success(response) => Ok(Txn {
                 db: response.metadata.db.or(db),
@@ -145,7 +195,10 @@ impl Txn {
     pub async fn commit(mut self) -> Result<()> {
         let commit = BoltRequest::commit();
         match self.connection.send_recv(commit).await? {
-            BoltResponse::Success(_) => Ok(()),
+            BoltResponse::Success(_) => {
+                self.connection.release();
+                Ok(())
+            }
             msg => Err(msg.into_error("COMMIT")),
         }
     }
@@ -154,6 +207,7 @@ impl Txn {
     pub async fn commit(mut self) -> Result<Option<String>> {
         match self.connection.send_recv_as(Commit).await? {
             Summary::Success(resp) => {
+                self.connection.release();
                 self.save_bookmark_state(&resp.metadata);
                 Ok(self.bookmark)
             }
@@ -167,7 +221,10 @@ impl Txn {
         {
             let rollback = BoltRequest::rollback();
             match self.connection.send_recv(rollback).await? {
-                BoltResponse::Success(_) => Ok(()),
+                BoltResponse::Success(_) => {
+                    self.connection.release();
+                    Ok(())
+                }
                 msg => Err(msg.into_error("ROLLBACK")),
             }
         }
@@ -175,7 +232,10 @@ impl Txn {
         #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
         {
             match self.connection.send_recv_as(Rollback).await? {
-                Summary::Success(_) => Ok(()),
+                Summary::Success(_) => {
+                    self.connection.release();
+                    Ok(())
+                }
                 msg => Err(msg.into_error("ROLLBACK")),
             }
         }
@@ -205,14 +265,105 @@ const _: () = {
     assert_send_sync::<Txn>();
 };
 
+/// The records and the summary of a single query run by [`crate::Graph::execute_txn`].
+pub struct TxnQueryResult {
+    pub rows: Vec<Row>,
+    pub summary: RunResult,
+}
+
+/// A list of queries that are run in one write transaction, so that the whole
+/// transaction can be retried as a single unit of work.
+pub(crate) struct RetryableTxn<'a> {
+    pool: &'a ConnectionPoolManager,
+    queries: Vec<Query>,
+    db: Option<Database>,
+    fetch_size: usize,
+    config: TxnConfig,
+}
+
+impl<'a> RetryableTxn<'a> {
+    pub(crate) fn new(
+        pool: &'a ConnectionPoolManager,
+        queries: Vec<Query>,
+        db: Option<Database>,
+        fetch_size: usize,
+        config: TxnConfig,
+    ) -> Self {
+        RetryableTxn {
+            pool,
+            queries,
+            db,
+            fetch_size,
+            config,
+        }
+    }
+
+    pub(crate) async fn retry_execute(self) -> (Self, QueryResult<Vec<TxnQueryResult>>) {
+        let result = self.execute().await;
+        (self, result)
+    }
+
+    async fn execute(&self) -> QueryResult<Vec<TxnQueryResult>> {
+        // an error when retrieving a connection is considered permanent
+        let connection = self
+            .pool
+            .get(Some(Operation::Write), self.db.clone())
+            .await
+            .map_err(Retry::No)?;
+
+        #[cfg(not(feature = "unstable-bolt-protocol-impl-v2"))]
+        let txn = Txn::new(
+            self.db.clone(),
+            self.fetch_size,
+            connection,
+            Operation::Write,
+            &self.config,
+        )
+        .await;
+        #[cfg(feature = "unstable-bolt-protocol-impl-v2")]
+        let txn = Txn::new(
+            self.db.clone(),
+            self.fetch_size,
+            connection,
+            Operation::Write,
+            &[],
+            &self.config,
+        )
+        .await;
+        let mut txn = txn.map_err(classify_error)?;
+
+        // On failure the transaction is dropped without a COMMIT, and the RESET
+        // sent when its connection is dropped rolls it back.
+        let mut results = Vec::with_capacity(self.queries.len());
+        for query in &self.queries {
+            let mut stream = txn.execute(query.clone()).await.map_err(classify_error)?;
+            let mut rows = Vec::new();
+            while let Some(row) = stream.next(txn.handle()).await.map_err(classify_error)? {
+                rows.push(row);
+            }
+            results.push(TxnQueryResult {
+                rows,
+                summary: stream.finish(txn.handle()).await.map_err(classify_error)?,
+            });
+        }
+
+        txn.commit().await.map_err(classify_error)?;
+        Ok(results)
+    }
+}
+
 pub trait TransactionHandle: private::Handle {}
 
 impl TransactionHandle for Txn {}
 impl TransactionHandle for ManagedConnection {}
+impl TransactionHandle for ActiveConnection {}
 impl<T: TransactionHandle> TransactionHandle for &mut T {}
 
 pub(crate) mod private {
-    use crate::{pool::ManagedConnection, Txn};
+    use crate::{
+        pool::{ActiveConnection, ManagedConnection},
+        Txn,
+    };
 
     pub trait Handle {
         fn connection(&mut self) -> &mut ManagedConnection;
@@ -224,6 +375,12 @@ pub(crate) mod private {
         }
     }
 
+    impl Handle for ActiveConnection {
+        fn connection(&mut self) -> &mut ManagedConnection {
+            self
+        }
+    }
+
     impl Handle for ManagedConnection {
         fn connection(&mut self) -> &mut ManagedConnection {
             self
@@ -1,11 +1,21 @@
 use crate::errors::Error;
 use crate::types::*;
-use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, Timelike};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, TimeZone, Timelike};
 use neo4rs_macros::BoltStruct;
 use std::convert::TryInto;
 
-#[derive(Debug, PartialEq, Eq, Clone, BoltStruct)]
-#[signature(0xB3, 0x46)]
+const LEGACY_DATE_TIME_SIGNATURE: u8 = 0x46;
+const DATE_TIME_SIGNATURE: u8 = 0x49;
+const LEGACY_DATE_TIME_ZONE_ID_SIGNATURE: u8 = 0x66;
+const DATE_TIME_ZONE_ID_SIGNATURE: u8 = 0x69;
+
+/// A datetime with a fixed offset.
+///
+/// `seconds` are always kept as wall-clock seconds in the given offset,
+/// which is what Bolt 4 sends. Bolt 5 sends seconds since the epoch in UTC
+/// (signature `0x49`), which are converted on the wire.
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BoltDateTime {
     pub(crate) seconds: BoltInteger,
     pub(crate) nanoseconds: BoltInteger,
@@ -19,14 +29,126 @@ pub struct BoltLocalDateTime {
     pub(crate) nanoseconds: BoltInteger,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, BoltStruct)]
-#[signature(0xB3, 0x66)]
+/// A datetime with a named time zone.
+///
+/// Like [`BoltDateTime`], `seconds` are wall-clock seconds in the named zone.
+/// Bolt 5 sends seconds since the epoch in UTC (signature `0x69`).
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BoltDateTimeZoneId {
     pub(crate) seconds: BoltInteger,
     pub(crate) nanoseconds: BoltInteger,
     pub(crate) tz_id: BoltString,
 }
 
+impl BoltWireFormat for BoltDateTime {
+    fn can_parse(_version: Version, input: &[u8]) -> bool {
+        input.len() >= 2
+            && input[0] == 0xB3
+            && matches!(input[1], LEGACY_DATE_TIME_SIGNATURE | DATE_TIME_SIGNATURE)
+    }
+
+    fn parse(version: Version, input: &mut Bytes) -> Result<Self> {
+        input.get_u8();
+        let signature = input.get_u8();
+        let seconds = BoltInteger::parse(version, input)?;
+        let nanoseconds = BoltInteger::parse(version, input)?;
+        let tz_offset_seconds = BoltInteger::parse(version, input)?;
+        let seconds = if signature == DATE_TIME_SIGNATURE {
+            BoltInteger::new(seconds.value + tz_offset_seconds.value)
+        } else {
+            seconds
+        };
+        Ok(BoltDateTime {
+            seconds,
+            nanoseconds,
+            tz_offset_seconds,
+        })
+    }
+
+    fn write_into(&self, version: Version, bytes: &mut BytesMut) -> Result<()> {
+        bytes.reserve(2);
+        bytes.put_u8(0xB3);
+        if version.has_element_ids() {
+            bytes.put_u8(DATE_TIME_SIGNATURE);
+            BoltInteger::new(self.seconds.value - self.tz_offset_seconds.value)
+                .write_into(version, bytes)?;
+        } else {
+            bytes.put_u8(LEGACY_DATE_TIME_SIGNATURE);
+            self.seconds.write_into(version, bytes)?;
+        }
+        self.nanoseconds.write_into(version, bytes)?;
+        self.tz_offset_seconds.write_into(version, bytes)?;
+        Ok(())
+    }
+}
+
+impl BoltWireFormat for BoltDateTimeZoneId {
+    fn can_parse(_version: Version, input: &[u8]) -> bool {
+        input.len() >= 2
+            && input[0] == 0xB3
+            && matches!(
+                input[1],
+                LEGACY_DATE_TIME_ZONE_ID_SIGNATURE | DATE_TIME_ZONE_ID_SIGNATURE
+            )
+    }
+
+    fn parse(version: Version, input: &mut Bytes) -> Result<Self> {
+        input.get_u8();
+        let signature = input.get_u8();
+        let seconds = BoltInteger::parse(version, input)?;
+        let nanoseconds = BoltInteger::parse(version, input)?;
+        let tz_id = BoltString::parse(version, input)?;
+        let seconds = if signature == DATE_TIME_ZONE_ID_SIGNATURE {
+            BoltInteger::new(utc_to_zoned_seconds(seconds.value, &tz_id.value)?)
+        } else {
+            seconds
+        };
+        Ok(BoltDateTimeZoneId {
+            seconds,
+            nanoseconds,
+            tz_id,
+        })
+    }
+
+    fn write_into(&self, version: Version, bytes: &mut BytesMut) -> Result<()> {
+        bytes.reserve(2);
+        bytes.put_u8(0xB3);
+        if version.has_element_ids() {
+            bytes.put_u8(DATE_TIME_ZONE_ID_SIGNATURE);
+            BoltInteger::new(zoned_to_utc_seconds(self.seconds.value, &self.tz_id.value)?)
+                .write_into(version, bytes)?;
+        } else {
+            bytes.put_u8(LEGACY_DATE_TIME_ZONE_ID_SIGNATURE);
+            self.seconds.write_into(version, bytes)?;
+        }
+        self.nanoseconds.write_into(version, bytes)?;
+        self.tz_id.write_into(version, bytes)?;
+        Ok(())
+    }
+}
+
+fn parse_tz(tz_id: &str) -> Result<chrono_tz::Tz> {
+    tz_id.parse().map_err(|_| Error::ConversionError)
+}
+
+fn utc_to_zoned_seconds(seconds: i64, tz_id: &str) -> Result<i64> {
+    let tz = parse_tz(tz_id)?;
+    let utc = DateTime::from_timestamp(seconds, 0).ok_or(Error::ConversionError)?;
+    Ok(utc.with_timezone(&tz).naive_local().and_utc().timestamp())
+}
+
+fn zoned_to_utc_seconds(seconds: i64, tz_id: &str) -> Result<i64> {
+    let tz = parse_tz(tz_id)?;
+    let local = DateTime::from_timestamp(seconds, 0)
+        .ok_or(Error::ConversionError)?
+        .naive_utc();
+    // Ambiguous wall-clock times (DST fold) resolve to the earlier instant, like the server does.
+    tz.from_local_datetime(&local)
+        .earliest()
+        .map(|datetime| datetime.timestamp())
+        .ok_or(Error::ConversionError)
+}
+
 impl BoltDateTime {
     pub(crate) fn try_to_chrono(&self) -> Result<DateTime<FixedOffset>> {
         self.try_into()
@@ -269,4 +391,46 @@ mod tests {
         assert_eq!(datetime.to_string(), "2015-07-01 08:59:60.123");
         assert_eq!(zone_id, "Europe/Paris");
     }
+
+    #[test]
+    fn should_serialize_a_datetime_as_utc_for_bolt5() {
+        let date: BoltDateTime = DateTime::parse_from_rfc2822("Wed, 24 Jun 2015 12:50:35 +0100")
+            .unwrap()
+            .into();
+
+        assert_eq!(
+            date.into_bytes(Version::V5_0).unwrap(),
+            Bytes::from_static(&[
+                0xB3, 0x49, 0xCA, 0x55, 0x8A, 0x99, 0x8B, 0x00, 0xC9, 0x0E, 0x10,
+            ])
+        );
+    }
+
+    #[test]
+    fn should_deserialize_a_utc_datetime() {
+        let mut bytes = Bytes::from_static(&[
+            0xB3, 0x49, 0xCA, 0x55, 0x8A, 0x99, 0x8B, 0x00, 0xC9, 0x0E, 0x10,
+        ]);
+
+        let datetime: DateTime<FixedOffset> = BoltDateTime::parse(Version::V5_0, &mut bytes)
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        assert_eq!(datetime.to_rfc2822(), "Wed, 24 Jun 2015 12:50:35 +0100");
+    }
+
+    #[test]
+    fn should_round_trip_a_datetime_with_zoneid_for_bolt5() {
+        let datetime =
+            NaiveDateTime::parse_from_str("2015-07-01 08:59:59.123", "%Y-%m-%d %H:%M:%S%.f")
+                .unwrap();
+        let date: BoltDateTimeZoneId = (datetime, "Europe/Paris").into();
+
+        let mut bytes = date.clone().into_bytes(Version::V5_0).unwrap();
+        assert_eq!(bytes[1], 0x69);
+
+        let parsed = BoltDateTimeZoneId::parse(Version::V5_0, &mut bytes).unwrap();
+        assert_eq!(parsed, date);
+    }
 }
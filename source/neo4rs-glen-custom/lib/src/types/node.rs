@@ -1,13 +1,22 @@
-use crate::types::{serde::DeError, BoltInteger, BoltList, BoltMap, BoltType, Result};
+use crate::{
+    types::{
+        serde::DeError, BoltInteger, BoltList, BoltMap, BoltString, BoltType, BoltWireFormat,
+        Result,
+    },
+    version::Version,
+};
 use ::serde::Deserialize;
-use neo4rs_macros::BoltStruct;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB3, 0x4E)]
+const SIGNATURE: u8 = 0x4E;
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltNode {
     pub id: BoltInteger,
     pub labels: BoltList,
     pub properties: BoltMap,
+    /// Only sent by servers speaking Bolt 5.0 or later.
+    pub element_id: Option<BoltString>,
 }
 
 impl BoltNode {
@@ -16,10 +25,67 @@ impl BoltNode {
             id,
             labels,
             properties,
+            element_id: None,
+        }
+    }
+
+    pub fn with_element_id(self, element_id: impl Into<BoltString>) -> Self {
+        BoltNode {
+            element_id: Some(element_id.into()),
+            ..self
         }
     }
 }
 
+impl BoltWireFormat for BoltNode {
+    fn can_parse(_version: Version, input: &[u8]) -> bool {
+        input.len() >= 2 && matches!(input[0], 0xB3 | 0xB4) && input[1] == SIGNATURE
+    }
+
+    fn parse(version: Version, input: &mut Bytes) -> Result<Self> {
+        let marker = input.get_u8();
+        input.get_u8();
+        let id = BoltInteger::parse(version, input)?;
+        let labels = BoltList::parse(version, input)?;
+        let properties = BoltMap::parse(version, input)?;
+        let element_id = if marker == 0xB4 {
+            Some(BoltString::parse(version, input)?)
+        } else {
+            None
+        };
+        Ok(BoltNode {
+            id,
+            labels,
+            properties,
+            element_id,
+        })
+    }
+
+    fn write_into(&self, version: Version, bytes: &mut BytesMut) -> Result<()> {
+        let with_element_id = version.has_element_ids();
+        bytes.reserve(2);
+        bytes.put_u8(if with_element_id { 0xB4 } else { 0xB3 });
+        bytes.put_u8(SIGNATURE);
+        self.id.write_into(version, bytes)?;
+        self.labels.write_into(version, bytes)?;
+        self.properties.write_into(version, bytes)?;
+        if with_element_id {
+            element_id_or_legacy(&self.element_id, &self.id).write_into(version, bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Servers speaking Bolt 5 derive the element id from the legacy id when none is known.
+pub(crate) fn element_id_or_legacy(
+    element_id: &Option<BoltString>,
+    id: &BoltInteger,
+) -> BoltString {
+    element_id
+        .clone()
+        .unwrap_or_else(|| BoltString::from(id.value.to_string()))
+}
+
 impl BoltNode {
     pub fn get<'this, T>(&'this self, key: &str) -> Result<T, DeError>
     where
@@ -38,8 +104,6 @@ impl From<BoltNode> for BoltType {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{types::BoltWireFormat, version::Version};
-    use bytes::Bytes;
 
     #[test]
     fn should_deserialize_a_node() {
@@ -63,11 +127,7 @@ mod tests {
         let id = BoltInteger::new(19);
         let labels = vec!["Person".into()].into();
         let properties = vec![("name".into(), "Mark".into())].into_iter().collect();
-        let node = BoltNode {
-            id,
-            labels,
-            properties,
-        };
+        let node = BoltNode::new(id, labels, properties);
 
         let bytes: Bytes = node.into_bytes(Version::V4_1).unwrap();
 
@@ -79,4 +139,34 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn should_deserialize_a_node_with_element_id() {
+        let mut input = Bytes::from_static(&[
+            0xB4, 0x4E, 0x13, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6F, 0x6E, 0xA0, 0x84, 0x34,
+            0x3A, 0x31, 0x39,
+        ]);
+
+        let node: BoltNode = BoltNode::parse(Version::V5_0, &mut input).unwrap();
+
+        assert_eq!(node.id, BoltInteger::new(19));
+        assert_eq!(node.labels, vec!["Person".into()].into());
+        assert_eq!(node.element_id, Some(BoltString::new("4:19")));
+    }
+
+    #[test]
+    fn should_serialize_a_node_with_element_id() {
+        let node = BoltNode::new(19.into(), vec!["Person".into()].into(), BoltMap::default())
+            .with_element_id("4:19");
+
+        let bytes: Bytes = node.into_bytes(Version::V5_0).unwrap();
+
+        assert_eq!(
+            bytes,
+            Bytes::from_static(&[
+                0xB4, 0x4E, 0x13, 0x91, 0x86, 0x50, 0x65, 0x72, 0x73, 0x6F, 0x6E, 0xA0, 0x84, 0x34,
+                0x3A, 0x31, 0x39,
+            ])
+        );
+    }
 }
@@ -1,23 +1,73 @@
-use crate::types::{serde::DeError, BoltInteger, BoltMap, BoltString, BoltType, Result};
+use crate::{
+    types::{
+        node::element_id_or_legacy, serde::DeError, BoltInteger, BoltMap, BoltString, BoltType,
+        BoltWireFormat, Result,
+    },
+    version::Version,
+};
 use ::serde::Deserialize;
-use neo4rs_macros::BoltStruct;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB5, 0x52)]
+const RELATION_SIGNATURE: u8 = 0x52;
+const UNBOUNDED_RELATION_SIGNATURE: u8 = 0x72;
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltRelation {
     pub id: BoltInteger,
     pub start_node_id: BoltInteger,
     pub end_node_id: BoltInteger,
     pub typ: BoltString,
     pub properties: BoltMap,
+    /// Only sent by servers speaking Bolt 5.0 or later.
+    pub element_id: Option<BoltString>,
+    /// Only sent by servers speaking Bolt 5.0 or later.
+    pub start_node_element_id: Option<BoltString>,
+    /// Only sent by servers speaking Bolt 5.0 or later.
+    pub end_node_element_id: Option<BoltString>,
 }
 
-#[derive(Debug, PartialEq, Clone, BoltStruct)]
-#[signature(0xB3, 0x72)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct BoltUnboundedRelation {
     pub id: BoltInteger,
     pub typ: BoltString,
     pub properties: BoltMap,
+    /// Only sent by servers speaking Bolt 5.0 or later.
+    pub element_id: Option<BoltString>,
+}
+
+impl BoltRelation {
+    pub fn new(
+        id: BoltInteger,
+        start_node_id: BoltInteger,
+        end_node_id: BoltInteger,
+        typ: BoltString,
+        properties: BoltMap,
+    ) -> Self {
+        BoltRelation {
+            id,
+            start_node_id,
+            end_node_id,
+            typ,
+            properties,
+            element_id: None,
+            start_node_element_id: None,
+            end_node_element_id: None,
+        }
+    }
+
+    pub fn with_element_ids(
+        self,
+        element_id: impl Into<BoltString>,
+        start_node_element_id: impl Into<BoltString>,
+        end_node_element_id: impl Into<BoltString>,
+    ) -> Self {
+        BoltRelation {
+            element_id: Some(element_id.into()),
+            start_node_element_id: Some(start_node_element_id.into()),
+            end_node_element_id: Some(end_node_element_id.into()),
+            ..self
+        }
+    }
 }
 
 impl BoltUnboundedRelation {
@@ -26,7 +76,111 @@ impl BoltUnboundedRelation {
             id,
             typ,
             properties,
+            element_id: None,
+        }
+    }
+
+    pub fn with_element_id(self, element_id: impl Into<BoltString>) -> Self {
+        BoltUnboundedRelation {
+            element_id: Some(element_id.into()),
+            ..self
+        }
+    }
+}
+
+impl BoltWireFormat for BoltRelation {
+    fn can_parse(_version: Version, input: &[u8]) -> bool {
+        input.len() >= 2 && matches!(input[0], 0xB5 | 0xB8) && input[1] == RELATION_SIGNATURE
+    }
+
+    fn parse(version: Version, input: &mut Bytes) -> Result<Self> {
+        let marker = input.get_u8();
+        input.get_u8();
+        let id = BoltInteger::parse(version, input)?;
+        let start_node_id = BoltInteger::parse(version, input)?;
+        let end_node_id = BoltInteger::parse(version, input)?;
+        let typ = BoltString::parse(version, input)?;
+        let properties = BoltMap::parse(version, input)?;
+        let (element_id, start_node_element_id, end_node_element_id) = if marker == 0xB8 {
+            (
+                Some(BoltString::parse(version, input)?),
+                Some(BoltString::parse(version, input)?),
+                Some(BoltString::parse(version, input)?),
+            )
+        } else {
+            (None, None, None)
+        };
+        Ok(BoltRelation {
+            id,
+            start_node_id,
+            end_node_id,
+            typ,
+            properties,
+            element_id,
+            start_node_element_id,
+            end_node_element_id,
+        })
+    }
+
+    fn write_into(&self, version: Version, bytes: &mut BytesMut) -> Result<()> {
+        let with_element_id = version.has_element_ids();
+        bytes.reserve(2);
+        bytes.put_u8(if with_element_id { 0xB8 } else { 0xB5 });
+        bytes.put_u8(RELATION_SIGNATURE);
+        self.id.write_into(version, bytes)?;
+        self.start_node_id.write_into(version, bytes)?;
+        self.end_node_id.write_into(version, bytes)?;
+        self.typ.write_into(version, bytes)?;
+        self.properties.write_into(version, bytes)?;
+        if with_element_id {
+            element_id_or_legacy(&self.element_id, &self.id).write_into(version, bytes)?;
+            element_id_or_legacy(&self.start_node_element_id, &self.start_node_id)
+                .write_into(version, bytes)?;
+            element_id_or_legacy(&self.end_node_element_id, &self.end_node_id)
+                .write_into(version, bytes)?;
+        }
+        Ok(())
+    }
+}
+
+impl BoltWireFormat for BoltUnboundedRelation {
+    fn can_parse(_version: Version, input: &[u8]) -> bool {
+        input.len() >= 2
+            && matches!(input[0], 0xB3 | 0xB4)
+            && input[1] == UNBOUNDED_RELATION_SIGNATURE
+    }
+
+    fn parse(version: Version, input: &mut Bytes) -> Result<Self> {
+        let marker = input.get_u8();
+        input.get_u8();
+        let id = BoltInteger::parse(version, input)?;
+        let typ = BoltString::parse(version, input)?;
+        let properties = BoltMap::parse(version, input)?;
+        let element_id = if marker == 0xB4 {
+            Some(BoltString::parse(version, input)?)
+        } else {
+            None
+        };
+        Ok(BoltUnboundedRelation {
+            id,
+            typ,
+            properties,
+            element_id,
+        })
+    }
+
+    fn write_into(&self, version: Version, bytes: &mut BytesMut) -> Result<()> {
+        let with_element_id = version.has_element_ids();
+        bytes.reserve(2);
+        bytes.put_u8(if with_element_id { 0xB4 } else { 0xB3 });
+        bytes.put_u8(UNBOUNDED_RELATION_SIGNATURE);
+        self.id.write_into(version, bytes)?;
+        self.typ.write_into(version, bytes)?;
+        self.properties.write_into(version, bytes)?;
+        if with_element_id {
+            element_id_or_legacy(&self.element_id, &self.id).write_into(version, bytes)?;
         }
+        Ok(())
     }
 }
 
@@ -63,8 +217,6 @@ impl From<BoltUnboundedRelation> for BoltType {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{types::BoltWireFormat, Version};
-    use bytes::Bytes;
 
     #[test]
     fn should_serialize_a_relation() {
@@ -74,13 +226,7 @@ mod tests {
         let typ = BoltString::new("rel");
         let properties = vec![("name".into(), "Mark".into())].into_iter().collect();
 
-        let relation = BoltRelation {
-            id,
-            start_node_id,
-            end_node_id,
-            typ,
-            properties,
-        };
+        let relation = BoltRelation::new(id, start_node_id, end_node_id, typ, properties);
 
         let bytes: Bytes = relation.into_bytes(Version::V4_1).unwrap();
 
@@ -147,4 +293,40 @@ mod tests {
             vec![("name".into(), "Mark".into())].into_iter().collect()
         );
     }
+
+    #[test]
+    fn should_deserialize_a_relation_with_element_ids() {
+        let mut input = Bytes::from_static(&[
+            0xB8, 0x52, 0x2A, 0x01, 0x02, 0x83, 0x72, 0x65, 0x6C, 0xA0, 0x84, 0x35, 0x3A, 0x34,
+            0x32, 0x83, 0x34, 0x3A, 0x31, 0x83, 0x34, 0x3A, 0x32,
+        ]);
+
+        let relation: BoltRelation = BoltRelation::parse(Version::V5_0, &mut input).unwrap();
+
+        assert_eq!(relation.id, BoltInteger::new(42));
+        assert_eq!(relation.typ, BoltString::new("rel"));
+        assert_eq!(relation.element_id, Some(BoltString::new("5:42")));
+        assert_eq!(relation.start_node_element_id, Some(BoltString::new("4:1")));
+        assert_eq!(relation.end_node_element_id, Some(BoltString::new("4:2")));
+    }
+
+    #[test]
+    fn should_serialize_an_unbounded_relation_with_element_id() {
+        let relation =
+            BoltUnboundedRelation::new(42.into(), BoltString::new("rel"), BoltMap::default())
+                .with_element_id("5:42");
+
+        let bytes: Bytes = relation.clone().into_bytes(Version::V5_0).unwrap();
+
+        assert_eq!(
+            bytes,
+            Bytes::from_static(&[
+                0xB4, 0x72, 0x2A, 0x83, 0x72, 0x65, 0x6C, 0xA0, 0x84, 0x35, 0x3A, 0x34, 0x32,
+            ])
+        );
+
+        let mut input = bytes;
+        let parsed = BoltUnboundedRelation::parse(Version::V5_0, &mut input).unwrap();
+        assert_eq!(parsed, relation);
+    }
 }
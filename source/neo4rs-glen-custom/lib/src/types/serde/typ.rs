@@ -43,7 +43,7 @@ impl<'de> Deserialize<'de> for BoltType {
 }
 
 impl BoltType {
-    pub(crate) fn to<'this, T>(&'this self) -> Result<T, DeError>
+    pub fn to<'this, T>(&'this self) -> Result<T, DeError>
     where
         T: Deserialize<'this>,
     {
@@ -52,7 +52,7 @@ impl BoltType {
 }
 
 impl BoltMap {
-    pub(crate) fn to<'this, T>(&'this self) -> Result<T, DeError>
+    pub fn to<'this, T>(&'this self) -> Result<T, DeError>
     where
         T: Deserialize<'this>,
     {
@@ -1184,11 +1184,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let node = BoltNode {
-            id,
-            labels,
-            properties,
-        };
+        let node = BoltNode::new(id, labels, properties);
         let node = BoltType::Node(node);
 
         let actual = node.to::<Person>().unwrap();
@@ -1226,13 +1222,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let relation = BoltRelation {
-            id,
-            start_node_id,
-            end_node_id,
-            properties,
-            typ,
-        };
+        let relation = BoltRelation::new(id, start_node_id, end_node_id, typ, properties);
         let relation = BoltType::Relation(relation);
 
         let actual = relation.to::<Person>().unwrap();
@@ -1268,11 +1258,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let relation = BoltUnboundedRelation {
-            id,
-            properties,
-            typ,
-        };
+        let relation = BoltUnboundedRelation::new(id, typ, properties);
         let relation = BoltType::UnboundedRelation(relation);
 
         let actual = relation.to::<Person>().unwrap();
@@ -10,19 +10,29 @@ pub enum Version {
     V4_1,
     V4_3,
     V4_4,
+    V5_0,
+    V5_1,
+    V5_2,
+    V5_3,
+    V5_4,
 }
 
 impl Version {
     pub fn add_supported_versions(bytes: &mut BytesMut) {
         bytes.reserve(16);
-        bytes.put_u32(0x0404); // V4_4
-        bytes.put_u32(0x0304); // V4_3
+        bytes.put_u32(0x0004_0405); // V5_4 down to V5_0
+        bytes.put_u32(0x0001_0404); // V4_4 down to V4_3
         bytes.put_u32(0x0104); // V4_1
         bytes.put_u32(0x0004); // V4
     }
 
     pub fn parse(version_bytes: [u8; 4]) -> Result<Version> {
         match version_bytes {
+            [0, 0, 4, 5] => Ok(Version::V5_4),
+            [0, 0, 3, 5] => Ok(Version::V5_3),
+            [0, 0, 2, 5] => Ok(Version::V5_2),
+            [0, 0, 1, 5] => Ok(Version::V5_1),
+            [0, 0, 0, 5] => Ok(Version::V5_0),
             [0, 0, 4, 4] => Ok(Version::V4_4),
             [0, 0, 3, 4] => Ok(Version::V4_3),
             [0, 0, 1, 4] => Ok(Version::V4_1),
@@ -31,6 +41,17 @@ impl Version {
             otherwise => Err(Error::ProtocolMismatch(u32::from_be_bytes(otherwise))),
         }
     }
+
+    /// Starting with 5.1, credentials are sent in a separate LOGON message after HELLO.
+    pub fn has_logon(self) -> bool {
+        self >= Version::V5_1
+    }
+
+    /// Starting with 5.0, nodes and relationships carry element ids and
+    /// datetimes are sent as UTC instants.
+    pub fn has_element_ids(self) -> bool {
+        self >= Version::V5_0
+    }
 }
 
 impl Display for Version {
@@ -40,6 +61,11 @@ impl Display for Version {
             Version::V4_1 => write!(f, "4.1"),
             Version::V4_3 => write!(f, "4.3"),
             Version::V4_4 => write!(f, "4.4"),
+            Version::V5_0 => write!(f, "5.0"),
+            Version::V5_1 => write!(f, "5.1"),
+            Version::V5_2 => write!(f, "5.2"),
+            Version::V5_3 => write!(f, "5.3"),
+            Version::V5_4 => write!(f, "5.4"),
         }
     }
 }
@@ -50,9 +76,33 @@ mod tests {
 
     #[tokio::test]
     async fn should_parse_version() {
+        assert_eq!(Version::parse([0, 0, 4, 5]).unwrap(), Version::V5_4);
+        assert_eq!(Version::parse([0, 0, 3, 5]).unwrap(), Version::V5_3);
+        assert_eq!(Version::parse([0, 0, 2, 5]).unwrap(), Version::V5_2);
+        assert_eq!(Version::parse([0, 0, 1, 5]).unwrap(), Version::V5_1);
+        assert_eq!(Version::parse([0, 0, 0, 5]).unwrap(), Version::V5_0);
         assert_eq!(Version::parse([0, 0, 4, 4]).unwrap(), Version::V4_4);
         assert_eq!(Version::parse([0, 0, 3, 4]).unwrap(), Version::V4_3);
         assert_eq!(Version::parse([0, 0, 1, 4]).unwrap(), Version::V4_1);
         assert_eq!(Version::parse([0, 0, 0, 4]).unwrap(), Version::V4);
     }
+
+    #[test]
+    fn should_reject_unknown_version() {
+        assert!(matches!(
+            Version::parse([0, 0, 5, 5]),
+            Err(Error::UnsupportedVersion(5, 5))
+        ));
+    }
+
+    #[test]
+    fn should_propose_version_ranges() {
+        let mut bytes = BytesMut::new();
+        Version::add_supported_versions(&mut bytes);
+
+        assert_eq!(
+            &bytes[..],
+            &[0, 4, 4, 5, 0, 1, 4, 4, 0, 0, 1, 4, 0, 0, 0, 4]
+        );
+    }
 }
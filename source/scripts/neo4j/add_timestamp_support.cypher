@@ -37,11 +37,13 @@ CREATE INDEX view_node_last_modified IF NOT EXISTS
 FOR (n:ViewNode)
 ON (n.lastModified);
 
-// Generic index for all nodes (optional, but useful for CDC)
-// Note: This creates a composite index that can be used for range queries
-CREATE INDEX node_last_modified IF NOT EXISTS
-FOR (n)
-ON (n.lastModified);
+// Indexes are per label or relationship type; the gateway only looks for changes
+// on those listed in GRAPH_DELTA_TIMESTAMP_LABELS and
+// GRAPH_DELTA_TIMESTAMP_RELATIONSHIP_TYPES, so index every one listed there, e.g.
+//
+// CREATE INDEX contains_last_modified IF NOT EXISTS
+// FOR ()-[r:CONTAINS]-()
+// ON (r.lastModified);
 
 // Step 4: Verify indexes were created
 // ============================================================================
@@ -1,5 +1,12 @@
 use serde::Deserialize;
 use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::database::access::CypherRoles;
+use crate::database::limits::QueryLimits;
+use crate::graph_events::{GraphDeltaConfig, TimestampIndexes};
+use crate::websocket::DeltaCoalescing;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
@@ -29,6 +36,26 @@ pub struct Config {
     // Content Security Policy
     #[allow(dead_code)]
     pub csp_report_endpoint: String,
+    // Stored query registry
+    pub stored_queries_dir: String,
+    pub stored_queries_from_neo4j: bool,
+    /// Whether raw Cypher from clients is accepted; only stored queries otherwise
+    pub allow_adhoc_cypher: bool,
+    /// Which roles may write or change the schema through Cypher
+    pub cypher_roles: CypherRoles,
+    pub query_limits: QueryLimits,
+    /// How long the graph schema for autocompletion is used before it is reloaded
+    pub schema_cache_ttl: Duration,
+    /// Lifetime of cached canvas results, zero disables the cache
+    pub canvas_cache_ttl: Duration,
+    /// Cached canvas results also kept in process
+    pub canvas_cache_local_entries: usize,
+    /// How often open sockets re-check their session
+    pub ws_session_check_interval: Duration,
+    /// Redis pub/sub channels every user of the SPA bridge may receive
+    pub spa_bridge_ui_channels: Vec<String>,
+    pub delta_coalescing: DeltaCoalescing,
+    pub graph_deltas: GraphDeltaConfig,
 }
 
 impl Config {
@@ -36,6 +63,12 @@ impl Config {
         let jwt_secret = env::var("JWT_SECRET")
             .expect("JWT_SECRET must be set in .env file - no defaults allowed");
 
+        let environment = env::var("ENVIRONMENT").expect("ENVIRONMENT must be set in .env file");
+        let allow_adhoc_cypher = env::var("ALLOW_ADHOC_CYPHER")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_else(|| environment.eq_ignore_ascii_case("development"));
+
         let approved_emails = env::var("APPROVED_EMAILS")
             .unwrap_or_default()
             .split(',')
@@ -47,7 +80,7 @@ impl Config {
             jwt_secret,
             redis_url: env::var("REDIS_URL").expect("REDIS_URL must be set in .env file"),
             approved_emails,
-            environment: env::var("ENVIRONMENT").expect("ENVIRONMENT must be set in .env file"),
+            environment,
             resend_api_key: env::var("RESEND_API_KEY").ok(),
             email_otp_enabled: env::var("EMAIL_OTP_ENABLED")
                 .unwrap_or_else(|_| "false".to_string())
@@ -81,6 +114,103 @@ impl Config {
                 .parse()
                 .unwrap_or(false),
             csp_report_endpoint: "/csp-report".to_string(),
+            stored_queries_dir: env::var("STORED_QUERIES_DIR")
+                .unwrap_or_else(|_| "config/queries".to_string()),
+            stored_queries_from_neo4j: env::var("STORED_QUERIES_FROM_NEO4J")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            allow_adhoc_cypher,
+            cypher_roles: CypherRoles::from_env(),
+            query_limits: query_limits_from_env(),
+            schema_cache_ttl: env_secs("GRAPH_SCHEMA_CACHE_TTL_SECS", 300),
+            canvas_cache_ttl: env_secs("CANVAS_CACHE_TTL_SECS", 60),
+            canvas_cache_local_entries: env_or("CANVAS_CACHE_LOCAL_ENTRIES", 128),
+            ws_session_check_interval: env_secs("WS_SESSION_CHECK_SECS", 30),
+            spa_bridge_ui_channels: env_list("SPA_BRIDGE_UI_CHANNELS")
+                .unwrap_or_else(|| vec!["ui:logs_panel".to_string()]),
+            delta_coalescing: delta_coalescing_from_env(),
+            graph_deltas: graph_deltas_from_env(),
         })
     }
 }
+
+/// Reads `NEO4J_QUERY_TIMEOUT_MS`, where 0 disables the timeout, and
+/// `NEO4J_MAX_CONCURRENT_QUERIES_PER_USER`
+fn query_limits_from_env() -> QueryLimits {
+    let defaults = QueryLimits::default();
+    QueryLimits {
+        timeout: match env_parse::<u64>("NEO4J_QUERY_TIMEOUT_MS") {
+            Some(0) => None,
+            Some(ms) => Some(Duration::from_millis(ms)),
+            None => defaults.timeout,
+        },
+        max_concurrent_per_user: env_or(
+            "NEO4J_MAX_CONCURRENT_QUERIES_PER_USER",
+            defaults.max_concurrent_per_user,
+        ),
+    }
+}
+
+/// Reads `GRAPH_DELTA_COALESCE_MS` and `GRAPH_DELTA_MAX_PENDING`
+fn delta_coalescing_from_env() -> DeltaCoalescing {
+    let defaults = DeltaCoalescing::default();
+    DeltaCoalescing {
+        window: env_millis("GRAPH_DELTA_COALESCE_MS").unwrap_or(defaults.window),
+        max_pending: env_or("GRAPH_DELTA_MAX_PENDING", defaults.max_pending),
+    }
+}
+
+/// Reads `ENABLE_GRAPH_DELTA`, `GRAPH_DELTA_STREAM_MAXLEN`, the `GRAPH_CDC_*`
+/// settings and the comma separated `GRAPH_DELTA_TIMESTAMP_LABELS` and
+/// `GRAPH_DELTA_TIMESTAMP_RELATIONSHIP_TYPES`
+fn graph_deltas_from_env() -> GraphDeltaConfig {
+    let defaults = GraphDeltaConfig::default();
+    GraphDeltaConfig {
+        enabled: env_or("ENABLE_GRAPH_DELTA", defaults.enabled),
+        stream_maxlen: env_or("GRAPH_DELTA_STREAM_MAXLEN", defaults.stream_maxlen),
+        cdc_enabled: env_or("GRAPH_CDC_ENABLED", defaults.cdc_enabled),
+        cdc_poll_interval: env_millis("GRAPH_CDC_POLL_INTERVAL_MS")
+            .unwrap_or(defaults.cdc_poll_interval),
+        cdc_resync_interval: env_millis("GRAPH_CDC_RESYNC_INTERVAL_MS")
+            .unwrap_or(defaults.cdc_resync_interval),
+        timestamp_indexes: TimestampIndexes {
+            labels: env_list("GRAPH_DELTA_TIMESTAMP_LABELS")
+                .unwrap_or(defaults.timestamp_indexes.labels),
+            relationship_types: env_list("GRAPH_DELTA_TIMESTAMP_RELATIONSHIP_TYPES")
+                .unwrap_or(defaults.timestamp_indexes.relationship_types),
+        },
+    }
+}
+
+/// `name` parsed as `T`, `None` when it is unset or does not parse
+fn env_parse<T: FromStr>(name: &str) -> Option<T> {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env_parse(name).unwrap_or(default)
+}
+
+fn env_secs(name: &str, default: u64) -> Duration {
+    Duration::from_secs(env_or(name, default))
+}
+
+fn env_millis(name: &str) -> Option<Duration> {
+    env_parse(name).map(Duration::from_millis)
+}
+
+/// `name` as a comma separated list, without blank entries
+fn env_list(name: &str) -> Option<Vec<String>> {
+    let value = env::var(name).ok()?;
+    Some(
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
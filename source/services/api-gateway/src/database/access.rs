@@ -0,0 +1,118 @@
+use serde::Deserialize;
+
+use crate::database::neo4j_gateway::QueryType;
+
+/// What a caller may run through the Cypher endpoints, see [`CypherRoles`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CypherAccess {
+    /// Pure reads only
+    ReadOnly,
+    /// Reads and data writes, but no schema changes
+    Writer,
+    /// Everything, including schema changes and queries the server cannot classify
+    Admin,
+}
+
+impl CypherAccess {
+    pub fn permits(self, query_type: QueryType) -> bool {
+        match self {
+            CypherAccess::ReadOnly => query_type == QueryType::Read,
+            CypherAccess::Writer => matches!(
+                query_type,
+                QueryType::Read | QueryType::Write | QueryType::ReadWrite
+            ),
+            CypherAccess::Admin => true,
+        }
+    }
+}
+
+/// Roles that get more than read-only access, from `CYPHER_WRITER_ROLES` and
+/// `CYPHER_ADMIN_ROLES` (comma separated, case-insensitive). Every other role is
+/// read-only, including `user`, which every account is issued by default.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CypherRoles {
+    writers: Vec<String>,
+    admins: Vec<String>,
+}
+
+impl Default for CypherRoles {
+    fn default() -> Self {
+        Self::new("writer,editor", "admin,administrator")
+    }
+}
+
+impl CypherRoles {
+    pub fn new(writers: &str, admins: &str) -> Self {
+        Self {
+            writers: role_list(writers),
+            admins: role_list(admins),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let roles = |name: &str, default: Vec<String>| {
+            std::env::var(name)
+                .map(|value| role_list(&value))
+                .unwrap_or(default)
+        };
+
+        Self {
+            writers: roles("CYPHER_WRITER_ROLES", defaults.writers),
+            admins: roles("CYPHER_ADMIN_ROLES", defaults.admins),
+        }
+    }
+
+    /// What `role`, i.e. `Claims.role`, may run through the Cypher endpoints
+    pub fn access_for(&self, role: &str) -> CypherAccess {
+        let role = role.to_ascii_lowercase();
+        if self.admins.contains(&role) {
+            CypherAccess::Admin
+        } else if self.writers.contains(&role) {
+            CypherAccess::Writer
+        } else {
+            CypherAccess::ReadOnly
+        }
+    }
+}
+
+fn role_list(roles: &str) -> Vec<String> {
+    roles
+        .split(',')
+        .map(|role| role.trim().to_ascii_lowercase())
+        .filter(|role| !role.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_roles_to_access() {
+        let roles = CypherRoles::default();
+        assert_eq!(roles.access_for("admin"), CypherAccess::Admin);
+        assert_eq!(roles.access_for("Writer"), CypherAccess::Writer);
+        assert_eq!(roles.access_for("user"), CypherAccess::ReadOnly);
+        assert_eq!(roles.access_for("viewer"), CypherAccess::ReadOnly);
+        assert_eq!(roles.access_for(""), CypherAccess::ReadOnly);
+    }
+
+    #[test]
+    fn configured_roles_replace_the_defaults() {
+        let roles = CypherRoles::new(" User, editor ", "ops");
+        assert_eq!(roles.access_for("user"), CypherAccess::Writer);
+        assert_eq!(roles.access_for("OPS"), CypherAccess::Admin);
+        assert_eq!(roles.access_for("admin"), CypherAccess::ReadOnly);
+    }
+
+    #[test]
+    fn enforces_query_types() {
+        assert!(CypherAccess::ReadOnly.permits(QueryType::Read));
+        assert!(!CypherAccess::ReadOnly.permits(QueryType::ReadWrite));
+        assert!(CypherAccess::Writer.permits(QueryType::Write));
+        assert!(!CypherAccess::Writer.permits(QueryType::SchemaOnly));
+        assert!(!CypherAccess::Writer.permits(QueryType::Unknown));
+        assert!(CypherAccess::Admin.permits(QueryType::SchemaOnly));
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// Bounds on the Neo4j work behind the gateway, its counterpart to the
+/// `ResourceLimits` of agent-runtime
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryLimits {
+    /// Sent as the transaction timeout of every query that does not bring its
+    /// own, see `StoredQueryDefinition::timeout_ms`. `None` leaves the server's.
+    pub timeout: Option<Duration>,
+    /// Queries a single user may have running at once, 0 for no limit
+    pub max_concurrent_per_user: usize,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            max_concurrent_per_user: 4,
+        }
+    }
+}
+
+/// Number of queries running per user
+#[derive(Debug, Default)]
+pub struct RunningQueries {
+    users: Mutex<HashMap<String, usize>>,
+}
+
+impl RunningQueries {
+    /// Counts a query of `user` as running until the permit is dropped, or
+    /// `None` when `limit` of them already are
+    pub fn acquire(self: &Arc<Self>, user: &str, limit: usize) -> Option<QueryPermit> {
+        let mut users = self.users.lock().unwrap();
+        let running = users.entry(user.to_string()).or_default();
+        if limit > 0 && *running >= limit {
+            return None;
+        }
+        *running += 1;
+
+        Some(QueryPermit {
+            running: self.clone(),
+            user: user.to_string(),
+        })
+    }
+
+    fn release(&self, user: &str) {
+        let mut users = self.users.lock().unwrap();
+        if let Some(running) = users.get_mut(user) {
+            *running -= 1;
+            if *running == 0 {
+                users.remove(user);
+            }
+        }
+    }
+}
+
+/// A running query of a user, see [`RunningQueries::acquire`]
+#[derive(Debug)]
+pub struct QueryPermit {
+    running: Arc<RunningQueries>,
+    user: String,
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        self.running.release(&self.user);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_queries_per_user() {
+        let running = Arc::new(RunningQueries::default());
+        let first = running.acquire("alice", 2).unwrap();
+        let _second = running.acquire("alice", 2).unwrap();
+
+        assert!(running.acquire("alice", 2).is_none());
+        assert!(running.acquire("bob", 2).is_some());
+
+        drop(first);
+        assert!(running.acquire("alice", 2).is_some());
+    }
+
+    #[test]
+    fn forgets_idle_users() {
+        let running = Arc::new(RunningQueries::default());
+        let permits: Vec<_> = (0..3)
+            .map(|_| running.acquire("alice", 0).unwrap())
+            .collect();
+        assert_eq!(running.users.lock().unwrap()["alice"], 3);
+
+        drop(permits);
+        assert!(running.users.lock().unwrap().is_empty());
+    }
+}
@@ -1 +1,7 @@
+pub mod access;
+pub mod limits;
 pub mod neo4j_gateway;
+mod params;
+pub mod plan;
+pub mod schema;
+pub mod stored_queries;
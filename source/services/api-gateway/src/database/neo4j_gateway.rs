@@ -1,20 +1,72 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
-use neo4rs::{query, BoltNull, BoltType, ConfigBuilder, Graph, Query};
-use serde::Serialize;
+use neo4rs::summary::{ResultSummary, Type};
+use neo4rs::{BoltMap, BoltType, ConfigBuilder, DetachedRowStream, Graph, Query, TxnConfig};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::{debug, info, warn};
 
 use crate::config::Config;
+use crate::database::access::CypherAccess;
+use crate::database::limits::{QueryLimits, QueryPermit, RunningQueries};
+use crate::database::params::json_to_bolt;
 
 #[derive(Debug, thiserror::Error)]
 pub enum GatewayError {
     #[error("neo4j connection error: {0}")]
     Connection(String),
-    #[error("neo4j query error: {0}")]
-    Query(String),
+    /// `code` is the Neo4j status code, e.g. `Neo.ClientError.Statement.SyntaxError`,
+    /// and is absent for failures raised by the gateway itself.
+    #[error("neo4j query error: {message}")]
+    Query {
+        code: Option<String>,
+        message: String,
+    },
+    /// The caller's role does not allow the query type the server reported
+    #[error("{query_type:?} queries are not permitted for this role")]
+    Forbidden { query_type: QueryType },
+    /// The user already has as many queries running as [`QueryLimits`] allows
+    #[error("too many concurrent queries, at most {limit} per user")]
+    Busy { limit: usize },
+    /// Every page runs the query again, which only reads may do
+    #[error("{query_type:?} queries cannot be paged, only reads")]
+    Unpageable { query_type: QueryType },
+}
+
+impl GatewayError {
+    pub fn query(message: impl Into<String>) -> Self {
+        GatewayError::Query {
+            code: None,
+            message: message.into(),
+        }
+    }
+
+    /// Whether the server terminated the query for exceeding its transaction timeout
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            GatewayError::Query {
+                code: Some(code), ..
+            } => code.starts_with("Neo.ClientError.Transaction.TransactionTimedOut"),
+            _ => false,
+        }
+    }
+}
+
+impl From<neo4rs::Error> for GatewayError {
+    fn from(error: neo4rs::Error) -> Self {
+        match error {
+            neo4rs::Error::Neo4j(error) => GatewayError::Query {
+                code: Some(error.code().to_string()),
+                message: error.message().to_string(),
+            },
+            neo4rs::Error::IOError { .. } | neo4rs::Error::ConnectionError => {
+                GatewayError::Connection(error.to_string())
+            }
+            other => GatewayError::query(other.to_string()),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -23,16 +75,251 @@ pub struct QueryMetrics {
     pub result_count: usize,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryType {
+    Read,
+    Write,
+    ReadWrite,
+    SchemaOnly,
+    Unknown,
+}
+
+impl QueryType {
+    /// Only queries the server reported as pure reads are routed to readers.
+    pub fn is_read(self) -> bool {
+        matches!(self, QueryType::Read)
+    }
+}
+
+impl From<Type> for QueryType {
+    fn from(value: Type) -> Self {
+        match value {
+            Type::Read => QueryType::Read,
+            Type::Write => QueryType::Write,
+            Type::ReadWrite => QueryType::ReadWrite,
+            Type::SchemaOnly => QueryType::SchemaOnly,
+            Type::Unknown => QueryType::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryCounters {
+    pub nodes_created: u64,
+    pub nodes_deleted: u64,
+    pub relationships_created: u64,
+    pub relationships_deleted: u64,
+    pub properties_set: u64,
+    pub labels_added: u64,
+    pub labels_removed: u64,
+    pub indexes_added: u64,
+    pub indexes_removed: u64,
+    pub constraints_added: u64,
+    pub constraints_removed: u64,
+    pub system_updates: u64,
+}
+
+impl QueryCounters {
+    /// Whether nodes, relationships, properties or labels were changed
+    pub fn contains_graph_updates(&self) -> bool {
+        self.nodes_created > 0
+            || self.nodes_deleted > 0
+            || self.relationships_created > 0
+            || self.relationships_deleted > 0
+            || self.properties_set > 0
+            || self.labels_added > 0
+            || self.labels_removed > 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryNotification {
+    pub code: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub severity: Option<String>,
+    pub category: Option<String>,
+    /// `{offset, line, column}` of the offending part of the query
+    pub position: Option<Value>,
+}
+
+/// Result summary reported by Neo4j once the stream has been fully consumed.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuerySummary {
+    pub query_type: QueryType,
+    pub counters: QueryCounters,
+    pub notifications: Vec<QueryNotification>,
+    /// Only present for `EXPLAIN` and `PROFILE` queries
+    pub plan: Option<Value>,
+}
+
+impl From<&ResultSummary> for QuerySummary {
+    fn from(summary: &ResultSummary) -> Self {
+        let stats = summary.stats();
+        let counters = QueryCounters {
+            nodes_created: stats.nodes_created,
+            nodes_deleted: stats.nodes_deleted,
+            relationships_created: stats.relationships_created,
+            relationships_deleted: stats.relationships_deleted,
+            properties_set: stats.properties_set,
+            labels_added: stats.labels_added,
+            labels_removed: stats.labels_removed,
+            indexes_added: stats.indexes_added,
+            indexes_removed: stats.indexes_removed,
+            constraints_added: stats.constraints_added,
+            constraints_removed: stats.constraints_removed,
+            system_updates: stats.system_updates,
+        };
+
+        let notifications = summary
+            .notifications()
+            .iter()
+            .map(|notification| QueryNotification {
+                code: notification.code.clone(),
+                title: notification.title.clone(),
+                description: notification.description.clone(),
+                severity: notification
+                    .severity
+                    .map(|severity| format!("{severity:?}").to_uppercase()),
+                category: notification
+                    .category
+                    .map(|category| format!("{category:?}").to_uppercase()),
+                position: notification.position.as_ref().map(|position| {
+                    serde_json::json!({
+                        "offset": position.offset,
+                        "line": position.line,
+                        "column": position.column,
+                    })
+                }),
+            })
+            .collect();
+
+        let plan = summary
+            .profile()
+            .or_else(|| summary.plan())
+            .and_then(|plan| plan.to::<Value>().ok());
+
+        QuerySummary {
+            query_type: summary.query_type().into(),
+            counters,
+            notifications,
+            plan,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GatewayQueryResult {
     pub metrics: QueryMetrics,
     pub raw_response: Value,
+    pub summary: QuerySummary,
+}
+
+/// A page of rows read by [`Neo4jGateway::execute_page`].
+#[derive(Debug)]
+pub struct GatewayQueryPage {
+    pub result: GatewayQueryResult,
+    /// Number of rows skipped before this page
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+/// Rows of a running query, converted to JSON one at a time as they are read.
+/// Dropping it unfinished abandons the query; its connection is reset right
+/// away, which stops the query and ends its transaction on the server.
+pub struct GatewayRowStream {
+    query_id: String,
+    cypher: String,
+    stream: DetachedRowStream,
+    rows_read: usize,
+    start: Instant,
+    /// Counts the query against its user until the stream is gone
+    _permit: Option<QueryPermit>,
 }
 
+impl GatewayRowStream {
+    pub async fn next_row(&mut self) -> Result<Option<Value>, GatewayError> {
+        // A FAILURE after some records must not be mistaken for the end of the stream
+        match self.stream.next().await {
+            Ok(Some(row)) => {
+                self.rows_read += 1;
+                Ok(Some(row.get_all_json()))
+            }
+            Ok(None) => Ok(None),
+            Err(error) => {
+                warn!(
+                    target: "kalisi_gateway::database::neo4j",
+                    query_id = %self.query_id,
+                    rows_received = self.rows_read,
+                    %error,
+                    "Cypher query failed while streaming results"
+                );
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Read and drop up to `count` rows, e.g. those before a page.
+    pub async fn skip(&mut self, count: usize) -> Result<(), GatewayError> {
+        for _ in 0..count {
+            if self.stream.next().await?.is_none() {
+                break;
+            }
+            self.rows_read += 1;
+        }
+        Ok(())
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    /// Discards whatever has not been read and returns the result for `rows`
+    /// together with the summary.
+    pub async fn finish(self, rows: Vec<Value>) -> Result<GatewayQueryResult, GatewayError> {
+        let summary = self.stream.finish().await?;
+        let elapsed_ms = self.start.elapsed().as_millis() as u64;
+
+        if elapsed_ms > 750 {
+            warn!(
+                target: "kalisi_gateway::database::neo4j",
+                query_id = %self.query_id,
+                elapsed_ms,
+                row_count = self.rows_read,
+                cypher = self.cypher,
+                "Slow Cypher query detected"
+            );
+        }
+
+        Ok(query_result(rows, &summary, elapsed_ms))
+    }
+}
+
+/// One statement of a transaction run through [`Neo4jGateway::execute_transaction`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct GatewayStatement {
+    pub query: String,
+    #[serde(default)]
+    pub parameters: HashMap<String, Value>,
+}
+
+/// Upper bound on the number of distinct Cypher texts whose query type is remembered.
+const MAX_CACHED_QUERY_TYPES: usize = 1024;
+
+/// Application name in the transaction metadata, e.g. in `SHOW TRANSACTIONS`
+const TX_METADATA_APP: &str = "kalisi-gateway";
+
 #[derive(Clone)]
 pub struct Neo4jGateway {
     graph: Arc<Graph>,
     log_queries: bool,
+    fetch_size: usize,
+    query_types: Arc<RwLock<HashMap<String, QueryType>>>,
+    limits: QueryLimits,
+    running: Arc<RunningQueries>,
+    /// Whose queries these are, see [`Neo4jGateway::for_user`]
+    user: Option<Arc<str>>,
 }
 
 impl Neo4jGateway {
@@ -51,6 +338,8 @@ impl Neo4jGateway {
             .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
             .unwrap_or(false);
 
+        let limits = config.query_limits.clone();
+
         let config_builder = ConfigBuilder::default()
             .uri(config.neo4j_uri.clone())
             .user(config.neo4j_username.clone())
@@ -64,29 +353,272 @@ impl Neo4jGateway {
 
         info!(
             target: "kalisi_gateway::database::neo4j",
-            "Neo4j connection pool initialised (uri={}, max_connections={}, fetch_size={})",
+            "Neo4j connection pool initialised (uri={}, max_connections={}, fetch_size={}, query_timeout={:?}, max_concurrent_per_user={})",
             config.neo4j_uri,
             max_connections,
-            fetch_size
+            fetch_size,
+            limits.timeout,
+            limits.max_concurrent_per_user
         );
 
         Ok(Self {
             graph: Arc::new(graph),
             log_queries,
+            fetch_size,
+            query_types: Arc::new(RwLock::new(HashMap::new())),
+            limits,
+            running: Arc::new(RunningQueries::default()),
+            user: None,
         })
     }
 
+    /// The same gateway with its queries run on behalf of `user_id`: they count
+    /// against the user's concurrency limit and carry the id in their transaction
+    /// metadata. Queries of the gateway itself are not limited.
+    pub fn for_user(&self, user_id: impl Into<String>) -> Self {
+        Self {
+            user: Some(Arc::from(user_id.into())),
+            ..self.clone()
+        }
+    }
+
+    fn acquire_permit(&self) -> Result<Option<QueryPermit>, GatewayError> {
+        let Some(user) = self.user.as_deref() else {
+            return Ok(None);
+        };
+        let limit = self.limits.max_concurrent_per_user;
+        match self.running.acquire(user, limit) {
+            Some(permit) => Ok(Some(permit)),
+            None => {
+                warn!(
+                    target: "kalisi_gateway::database::neo4j",
+                    user_id = user,
+                    limit,
+                    "Concurrent query limit reached"
+                );
+                Err(GatewayError::Busy { limit })
+            }
+        }
+    }
+
+    /// The query with the default timeout, unless it has its own, and with
+    /// metadata naming the gateway, query and user for the server's logs
+    fn with_transaction_config(&self, query: Query, query_id: &str) -> Query {
+        let query = match self.limits.timeout {
+            Some(timeout) if !query.has_extra_key("tx_timeout") => {
+                query.extra("tx_timeout", timeout.as_millis() as i64)
+            }
+            _ => query,
+        };
+
+        query.extra("tx_metadata", self.transaction_metadata(query_id))
+    }
+
+    /// [`Self::with_transaction_config`] for an explicit transaction, whose
+    /// timeout and metadata go with its BEGIN rather than with each statement
+    fn txn_config(&self, query_id: &str) -> TxnConfig {
+        let config = match self.limits.timeout {
+            Some(timeout) => TxnConfig::new().with_timeout(timeout),
+            None => TxnConfig::new(),
+        };
+        self.transaction_metadata(query_id)
+            .into_iter()
+            .fold(config, |config, (key, value)| {
+                config.with_metadata(key, value)
+            })
+    }
+
+    fn transaction_metadata<'a>(&'a self, query_id: &'a str) -> HashMap<&'a str, &'a str> {
+        let mut metadata = HashMap::from([("app", TX_METADATA_APP), ("query_id", query_id)]);
+        if let Some(user) = self.user.as_deref() {
+            metadata.insert("user_id", user);
+        }
+        metadata
+    }
+
+    /// Number of records pulled from the server per round trip
+    pub fn fetch_size(&self) -> usize {
+        self.fetch_size
+    }
+
+    /// Classifies a query by the type the server reports for its `EXPLAIN` plan,
+    /// which nothing is executed for. Results are cached per Cypher text.
+    pub async fn classify(
+        &self,
+        cypher: &str,
+        parameters: &BoltMap,
+    ) -> Result<QueryType, GatewayError> {
+        let trimmed = cypher.trim_start();
+        if starts_with_keyword(trimmed, "EXPLAIN") {
+            return Ok(QueryType::Read);
+        }
+        let statement = if starts_with_keyword(trimmed, "PROFILE") {
+            &trimmed["PROFILE".len()..]
+        } else {
+            trimmed
+        };
+
+        if let Some(query_type) = self.cached_query_type(statement) {
+            return Ok(query_type);
+        }
+
+        let explain = Query::new(format!("EXPLAIN {statement}")).with_params(parameters.clone());
+        let query_type = QueryType::from(self.graph.run(explain).await?.query_type());
+
+        if let Ok(mut query_types) = self.query_types.write() {
+            if query_types.len() >= MAX_CACHED_QUERY_TYPES {
+                query_types.clear();
+            }
+            query_types.insert(statement.to_string(), query_type);
+        }
+
+        Ok(query_type)
+    }
+
+    fn cached_query_type(&self, cypher: &str) -> Option<QueryType> {
+        self.query_types
+            .read()
+            .ok()
+            .and_then(|query_types| query_types.get(cypher).copied())
+    }
+
+    /// Classifies the query and rejects it before it runs when `access` does not
+    /// permit the server-reported query type.
+    pub async fn authorize(
+        &self,
+        cypher: &str,
+        parameters: &BoltMap,
+        access: CypherAccess,
+    ) -> Result<QueryType, GatewayError> {
+        let query_type = self.classify(cypher, parameters).await?;
+        if access.permits(query_type) {
+            Ok(query_type)
+        } else {
+            Err(GatewayError::Forbidden { query_type })
+        }
+    }
+
+    /// [`Neo4jGateway::authorize`] for a query with JSON parameters, such as one
+    /// about to be run with [`Neo4jGateway::execute`]
+    pub async fn authorize_json(
+        &self,
+        cypher: &str,
+        parameters: &HashMap<String, Value>,
+        access: CypherAccess,
+    ) -> Result<QueryType, GatewayError> {
+        let parameters = bolt_parameters(parameters)?;
+        self.authorize(cypher, &parameters, access).await
+    }
+
     pub async fn execute(
         &self,
         query_id: &str,
         cypher: &str,
         parameters: &HashMap<String, Value>,
+        access: CypherAccess,
+    ) -> Result<GatewayQueryResult, GatewayError> {
+        let prepared = Query::new(cypher.to_string());
+        self.execute_prepared(query_id, &prepared, parameters, access)
+            .await
+    }
+
+    /// Runs an already prepared query, such as a cached stored query, with `parameters`.
+    pub async fn execute_prepared(
+        &self,
+        query_id: &str,
+        prepared: &Query,
+        parameters: &HashMap<String, Value>,
+        access: CypherAccess,
     ) -> Result<GatewayQueryResult, GatewayError> {
-        let mut prepared = query(cypher);
-        for (key, value) in parameters {
-            prepared = apply_parameter(prepared, key, value).map_err(|error| {
-                GatewayError::Query(format!("invalid parameter {key}: {error}"))
-            })?;
+        let mut stream = self
+            .stream_prepared(query_id, prepared, parameters, access)
+            .await?;
+
+        let mut rows = Vec::new();
+        while let Some(row) = stream.next_row().await? {
+            rows.push(row);
+        }
+
+        stream.finish(rows).await
+    }
+
+    /// Reads one page of `limit` rows after skipping `offset` rows. Records are
+    /// pulled in `fetch_size` batches, so reading stops shortly after the page and
+    /// the rest of the result is discarded on the server.
+    ///
+    /// Every page runs the query again, so only reads are accepted, and pages
+    /// only line up when the query orders its rows completely: without `ORDER BY`
+    /// Neo4j may return them in a different order each time.
+    pub async fn execute_page(
+        &self,
+        query_id: &str,
+        prepared: &Query,
+        parameters: &HashMap<String, Value>,
+        access: CypherAccess,
+        offset: usize,
+        limit: usize,
+    ) -> Result<GatewayQueryPage, GatewayError> {
+        let mut stream = self
+            .stream_page(query_id, prepared, parameters, access)
+            .await?;
+        stream.skip(offset).await?;
+
+        let mut rows = Vec::with_capacity(limit.min(1024));
+        while rows.len() < limit {
+            match stream.next_row().await? {
+                Some(row) => rows.push(row),
+                None => break,
+            }
+        }
+        let has_more = rows.len() == limit && stream.next_row().await?.is_some();
+
+        Ok(GatewayQueryPage {
+            result: stream.finish(rows).await?,
+            offset,
+            has_more,
+        })
+    }
+
+    /// Opens the result of a query as a stream of JSON rows, for callers that
+    /// forward rows as they arrive instead of buffering the whole result.
+    pub async fn stream_prepared(
+        &self,
+        query_id: &str,
+        prepared: &Query,
+        parameters: &HashMap<String, Value>,
+        access: CypherAccess,
+    ) -> Result<GatewayRowStream, GatewayError> {
+        self.open_prepared(query_id, prepared, parameters, access, false)
+            .await
+    }
+
+    /// Like [`Neo4jGateway::stream_prepared`], for reading a page of the result
+    /// after skipping the rows before it; see [`Neo4jGateway::execute_page`]
+    pub async fn stream_page(
+        &self,
+        query_id: &str,
+        prepared: &Query,
+        parameters: &HashMap<String, Value>,
+        access: CypherAccess,
+    ) -> Result<GatewayRowStream, GatewayError> {
+        self.open_prepared(query_id, prepared, parameters, access, true)
+            .await
+    }
+
+    async fn open_prepared(
+        &self,
+        query_id: &str,
+        prepared: &Query,
+        parameters: &HashMap<String, Value>,
+        access: CypherAccess,
+        paged: bool,
+    ) -> Result<GatewayRowStream, GatewayError> {
+        let permit = self.acquire_permit()?;
+        let cypher = prepared.query();
+        let parameters_bolt = bolt_parameters(parameters)?;
+        let query_type = self.authorize(cypher, &parameters_bolt, access).await?;
+        if paged && !query_type.is_read() {
+            return Err(GatewayError::Unpageable { query_type });
         }
 
         if self.log_queries {
@@ -95,22 +627,73 @@ impl Neo4jGateway {
                 query_id = %query_id,
                 cypher = cypher,
                 parameters = ?parameters,
+                query_type = ?query_type,
                 "Executing Cypher query"
             );
         }
 
         let start = Instant::now();
-        let mut stream = self
-            .graph
-            .execute(prepared)
-            .await
-            .map_err(|error| GatewayError::Connection(error.to_string()))?;
+        let query =
+            self.with_transaction_config(prepared.clone().with_params(parameters_bolt), query_id);
+        let stream = self.open_stream(query, query_type).await?;
 
-        let mut rows = Vec::new();
-        while let Ok(Some(row)) = stream.next().await {
-            rows.push(row.get_all_json());
+        Ok(GatewayRowStream {
+            query_id: query_id.to_string(),
+            cypher: cypher.to_string(),
+            stream,
+            rows_read: 0,
+            start,
+            _permit: permit,
+        })
+    }
+
+    async fn open_stream(
+        &self,
+        prepared: Query,
+        query_type: QueryType,
+    ) -> Result<DetachedRowStream, neo4rs::Error> {
+        if query_type.is_read() {
+            self.graph.execute_read(prepared).await
+        } else {
+            self.graph.execute(prepared).await
+        }
+    }
+
+    /// Runs all statements atomically in one write transaction. The transaction
+    /// is retried as a whole when Neo4j reports a transient failure. Every
+    /// statement is authorized before the transaction begins.
+    ///
+    /// The query timeout is sent with BEGIN, so the server terminates and rolls
+    /// back an attempt that runs too long; retries each get the full timeout.
+    pub async fn execute_transaction(
+        &self,
+        query_id: &str,
+        statements: &[GatewayStatement],
+        access: CypherAccess,
+    ) -> Result<Vec<GatewayQueryResult>, GatewayError> {
+        let _permit = self.acquire_permit()?;
+        let mut prepared = Vec::with_capacity(statements.len());
+        for statement in statements {
+            let parameters = bolt_parameters(&statement.parameters)?;
+            self.authorize(&statement.query, &parameters, access)
+                .await?;
+            prepared.push(Query::new(statement.query.clone()).with_params(parameters));
+        }
+
+        if self.log_queries {
+            debug!(
+                target: "kalisi_gateway::database::neo4j",
+                query_id = %query_id,
+                statements = statements.len(),
+                "Executing Cypher transaction"
+            );
         }
 
+        let start = Instant::now();
+        let results = self
+            .graph
+            .execute_txn_with(prepared, self.txn_config(query_id))
+            .await?;
         let elapsed_ms = start.elapsed().as_millis() as u64;
 
         if elapsed_ms > 750 {
@@ -118,41 +701,78 @@ impl Neo4jGateway {
                 target: "kalisi_gateway::database::neo4j",
                 query_id = %query_id,
                 elapsed_ms,
-                row_count = rows.len(),
-                cypher = cypher,
-                "Slow Cypher query detected"
+                statements = statements.len(),
+                "Slow Cypher transaction detected"
             );
         }
 
-        let raw_response = serde_json::json!({
-            "results": rows,
-            "count": rows.len(),
-        });
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                // Per statement timings are only known from the server
+                let server_ms = [
+                    result.summary.available_after(),
+                    result.summary.consumed_after(),
+                ]
+                .into_iter()
+                .flatten()
+                .map(|duration| duration.as_millis() as u64)
+                .sum();
+                let rows = result.rows.iter().map(|row| row.get_all_json()).collect();
+                query_result(rows, &result.summary, server_ms)
+            })
+            .collect())
+    }
+}
 
-        Ok(GatewayQueryResult {
-            metrics: QueryMetrics {
-                elapsed_ms,
-                result_count: rows.len(),
-            },
-            raw_response,
-        })
+fn query_result(rows: Vec<Value>, summary: &ResultSummary, elapsed_ms: u64) -> GatewayQueryResult {
+    let result_count = rows.len();
+    let raw_response = serde_json::json!({
+        "results": rows,
+        "count": result_count,
+    });
+
+    GatewayQueryResult {
+        metrics: QueryMetrics {
+            elapsed_ms,
+            result_count,
+        },
+        raw_response,
+        summary: QuerySummary::from(summary),
     }
 }
 
-fn apply_parameter(query: Query, key: &str, value: &Value) -> Result<Query, String> {
-    Ok(match value {
-        Value::Null => query.param(key, BoltType::Null(BoltNull)),
-        Value::Bool(boolean) => query.param(key, *boolean),
-        Value::Number(number) => {
-            if let Some(int_value) = number.as_i64() {
-                query.param(key, int_value)
-            } else if let Some(float_value) = number.as_f64() {
-                query.param(key, float_value)
-            } else {
-                return Err("unsupported numeric type".to_string());
-            }
-        }
-        Value::String(text) => query.param(key, text.clone()),
-        Value::Array(_) | Value::Object(_) => query.param(key, value.to_string()),
-    })
+fn bolt_parameters(parameters: &HashMap<String, Value>) -> Result<BoltMap, GatewayError> {
+    let mut bolt = BoltMap::with_capacity(parameters.len());
+    for (key, value) in parameters {
+        let value: BoltType = json_to_bolt(value)
+            .map_err(|error| GatewayError::query(format!("invalid parameter {key}: {error}")))?;
+        bolt.put(key.as_str().into(), value);
+    }
+    Ok(bolt)
+}
+
+/// Case-insensitive check for a leading Cypher keyword followed by whitespace.
+pub(crate) fn starts_with_keyword(cypher: &str, keyword: &str) -> bool {
+    cypher
+        .get(..keyword.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(keyword))
+        && cypher[keyword.len()..].starts_with(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::starts_with_keyword;
+
+    #[test]
+    fn detects_leading_keywords() {
+        assert!(starts_with_keyword("EXPLAIN MATCH (n) RETURN n", "EXPLAIN"));
+        assert!(starts_with_keyword(
+            "profile\nMATCH (n) RETURN n",
+            "PROFILE"
+        ));
+        assert!(!starts_with_keyword("EXPLAINED", "EXPLAIN"));
+        assert!(!starts_with_keyword("MATCH (n) RETURN n", "PROFILE"));
+        assert!(!starts_with_keyword("EXPL", "EXPLAIN"));
+    }
 }
@@ -0,0 +1,433 @@
+//! Conversion of JSON request parameters into Bolt values.
+//!
+//! Arrays become lists and objects become maps, recursively, so that `UNWIND $items`
+//! and `SET n += $props` work with parameters sent by the frontend.
+//!
+//! The encodings mirror what `Row::get_all_json` produces so that values read from
+//! Neo4j can be sent back unchanged:
+//!
+//! * points are objects with `srid`, `x`, `y` and an optional `z`
+//! * temporal values and bytes are single-key type hints around their ISO-8601 or
+//!   base64 string, e.g. `{"$datetime": "2024-03-09T14:05:07+01:00"}` or `{"$bytes": "AAEC"}`
+
+use base64::Engine as _;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use neo4rs::{
+    BoltDuration, BoltFloat, BoltInteger, BoltList, BoltMap, BoltNull, BoltPoint2D, BoltPoint3D,
+    BoltString, BoltType,
+};
+use serde_json::{Map, Value};
+
+pub(crate) fn json_to_bolt(value: &Value) -> Result<BoltType, String> {
+    Ok(match value {
+        Value::Null => BoltType::Null(BoltNull),
+        Value::Bool(boolean) => BoltType::from(*boolean),
+        Value::Number(number) => {
+            if let Some(int_value) = number.as_i64() {
+                BoltType::from(int_value)
+            } else if let Some(float_value) = number.as_f64() {
+                BoltType::from(float_value)
+            } else {
+                return Err("unsupported numeric type".to_string());
+            }
+        }
+        Value::String(text) => BoltType::from(text.clone()),
+        Value::Object(object) => match typed_value(object)? {
+            Some(typed) => typed,
+            None => {
+                let mut map = BoltMap::with_capacity(object.len());
+                for (key, value) in object {
+                    let value = json_to_bolt(value).map_err(|error| format!("{key}: {error}"))?;
+                    map.put(BoltString::from(key.as_str()), value);
+                }
+                BoltType::Map(map)
+            }
+        },
+        Value::Array(items) => {
+            let mut list = BoltList::with_capacity(items.len());
+            for (index, item) in items.iter().enumerate() {
+                let item = json_to_bolt(item).map_err(|error| format!("[{index}]: {error}"))?;
+                list.push(item);
+            }
+            BoltType::List(list)
+        }
+    })
+}
+
+/// Recognises type hints and point objects, returns `None` for plain maps.
+fn typed_value(object: &Map<String, Value>) -> Result<Option<BoltType>, String> {
+    if let Some(point) = point_value(object) {
+        return Ok(Some(point));
+    }
+
+    let mut entries = object.iter();
+    let (Some((hint, Value::String(text))), None) = (entries.next(), entries.next()) else {
+        return Ok(None);
+    };
+
+    let typed = match hint.as_str() {
+        "$date" => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .map(BoltType::from)
+            .map_err(|error| format!("invalid $date {text:?}: {error}"))?,
+        "$localtime" => parse_local_time(text)
+            .map(BoltType::from)
+            .ok_or_else(|| format!("invalid $localtime {text:?}"))?,
+        "$time" => parse_time(text)
+            .map(BoltType::from)
+            .ok_or_else(|| format!("invalid $time {text:?}"))?,
+        "$localdatetime" => parse_local_date_time(text)
+            .map(BoltType::from)
+            .ok_or_else(|| format!("invalid $localdatetime {text:?}"))?,
+        "$datetime" => {
+            parse_date_time(text).ok_or_else(|| format!("invalid $datetime {text:?}"))?
+        }
+        "$duration" => parse_duration(text)
+            .map(BoltType::Duration)
+            .ok_or_else(|| format!("invalid $duration {text:?}"))?,
+        "$bytes" => base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .map(BoltType::from)
+            .map_err(|error| format!("invalid $bytes: {error}"))?,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(typed))
+}
+
+fn point_value(object: &Map<String, Value>) -> Option<BoltType> {
+    if !object
+        .keys()
+        .all(|key| matches!(key.as_str(), "srid" | "x" | "y" | "z"))
+    {
+        return None;
+    }
+
+    let sr_id = BoltInteger::new(object.get("srid")?.as_i64()?);
+    let x = BoltFloat::new(object.get("x")?.as_f64()?);
+    let y = BoltFloat::new(object.get("y")?.as_f64()?);
+
+    Some(match object.get("z") {
+        Some(z) => BoltType::Point3D(BoltPoint3D {
+            sr_id,
+            x,
+            y,
+            z: BoltFloat::new(z.as_f64()?),
+        }),
+        None => BoltType::Point2D(BoltPoint2D { sr_id, x, y }),
+    })
+}
+
+fn parse_local_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text, "%H:%M:%S%.f").ok()
+}
+
+fn parse_time(text: &str) -> Option<(NaiveTime, FixedOffset)> {
+    let (time, offset) = if let Some(time) = text.strip_suffix('Z') {
+        (time, FixedOffset::east_opt(0)?)
+    } else {
+        let split = text.rfind(['+', '-'])?;
+        let offset =
+            DateTime::parse_from_rfc3339(&format!("1970-01-01T00:00:00{}", &text[split..]))
+                .ok()?
+                .offset()
+                .to_owned();
+        (&text[..split], offset)
+    };
+    Some((parse_local_time(time)?, offset))
+}
+
+fn parse_local_date_time(text: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+/// Accepts RFC 3339 with an optional `[Zone/Id]` suffix, as printed by Neo4j.
+fn parse_date_time(text: &str) -> Option<BoltType> {
+    match text.strip_suffix(']').and_then(|text| text.split_once('[')) {
+        Some((date_time, zone_id)) => {
+            // The zone id decides the offset, the wall-clock time is what Neo4j stores
+            let local = DateTime::parse_from_rfc3339(date_time)
+                .map(|date_time| date_time.naive_local())
+                .ok()
+                .or_else(|| parse_local_date_time(date_time))?;
+            Some(BoltType::from((local, zone_id)))
+        }
+        None => DateTime::parse_from_rfc3339(text).ok().map(BoltType::from),
+    }
+}
+
+/// Parses ISO-8601 durations such as `P1Y2M3DT4H5M6.5S`, components may be negative.
+fn parse_duration(text: &str) -> Option<BoltDuration> {
+    let text = text.strip_prefix('P')?;
+    let (date_part, time_part) = match text.split_once('T') {
+        Some((date_part, time_part)) => (date_part, Some(time_part)),
+        None => (text, None),
+    };
+
+    let mut months: i64 = 0;
+    let mut days: i64 = 0;
+    for (amount, unit) in duration_components(date_part)? {
+        let amount: i64 = amount.parse().ok()?;
+        match unit {
+            'Y' => months = months.checked_add(amount.checked_mul(12)?)?,
+            'M' => months = months.checked_add(amount)?,
+            'W' => days = days.checked_add(amount.checked_mul(7)?)?,
+            'D' => days = days.checked_add(amount)?,
+            _ => return None,
+        }
+    }
+
+    let mut nanos: i128 = 0;
+    for (amount, unit) in duration_components(time_part.unwrap_or_default())? {
+        let scale: i128 = match unit {
+            'H' => 3_600_000_000_000,
+            'M' => 60_000_000_000,
+            'S' => 1_000_000_000,
+            _ => return None,
+        };
+        nanos = nanos.checked_add(decimal_to_nanos(amount, scale)?)?;
+    }
+
+    let seconds = i64::try_from(nanos.div_euclid(1_000_000_000)).ok()?;
+    let nanoseconds = nanos.rem_euclid(1_000_000_000) as i64;
+    Some(BoltDuration::new(
+        months.into(),
+        days.into(),
+        seconds.into(),
+        nanoseconds.into(),
+    ))
+}
+
+fn duration_components(text: &str) -> Option<Vec<(&str, char)>> {
+    let mut components = Vec::new();
+    let mut start = 0;
+    for (index, unit) in text.char_indices() {
+        if unit.is_ascii_alphabetic() {
+            if index == start {
+                return None;
+            }
+            components.push((&text[start..index], unit));
+            start = index + unit.len_utf8();
+        }
+    }
+    (start == text.len()).then_some(components)
+}
+
+fn decimal_to_nanos(amount: &str, scale: i128) -> Option<i128> {
+    let (negative, amount) = match amount.strip_prefix('-') {
+        Some(amount) => (true, amount),
+        None => (false, amount),
+    };
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    if fraction.len() > 9 || !fraction.chars().all(|digit| digit.is_ascii_digit()) {
+        return None;
+    }
+
+    let whole: i128 = whole.parse().ok()?;
+    let fraction: i128 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{fraction:0<9}").parse().ok()?
+    };
+    let value = whole.checked_mul(scale)? + fraction * scale / 1_000_000_000;
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn scalars_keep_their_type() {
+        assert_eq!(json_to_bolt(&json!(42)).unwrap(), BoltType::from(42_i64));
+        assert_eq!(json_to_bolt(&json!(1.5)).unwrap(), BoltType::from(1.5));
+        assert_eq!(
+            json_to_bolt(&json!("text")).unwrap(),
+            BoltType::from("text")
+        );
+        assert_eq!(
+            json_to_bolt(&Value::Null).unwrap(),
+            BoltType::Null(BoltNull)
+        );
+    }
+
+    #[test]
+    fn temporal_hints_are_parsed() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let time = NaiveTime::from_hms_milli_opt(14, 5, 7, 250).unwrap();
+        let offset = FixedOffset::east_opt(3600).unwrap();
+
+        assert_eq!(
+            json_to_bolt(&json!({"$date": "2024-03-09"})).unwrap(),
+            BoltType::from(date)
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$localtime": "14:05:07.250"})).unwrap(),
+            BoltType::from(time)
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$time": "14:05:07.250+01:00"})).unwrap(),
+            BoltType::from((time, offset))
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$localdatetime": "2024-03-09T14:05:07.250"})).unwrap(),
+            BoltType::from(date.and_time(time))
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$datetime": "2024-03-09T14:05:07.250+01:00"})).unwrap(),
+            BoltType::from(DateTime::parse_from_rfc3339("2024-03-09T14:05:07.250+01:00").unwrap())
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$datetime": "2024-03-09T14:05:07+01:00[Europe/Paris]"})).unwrap(),
+            BoltType::from((date.and_hms_opt(14, 5, 7).unwrap(), "Europe/Paris"))
+        );
+        assert!(json_to_bolt(&json!({"$date": "09/03/2024"})).is_err());
+    }
+
+    #[test]
+    fn durations_are_parsed() {
+        let duration = |months: i64, days: i64, seconds: i64, nanos: i64| {
+            BoltType::Duration(BoltDuration::new(
+                months.into(),
+                days.into(),
+                seconds.into(),
+                nanos.into(),
+            ))
+        };
+
+        assert_eq!(
+            json_to_bolt(&json!({"$duration": "P1Y2M3DT1H2M3.5S"})).unwrap(),
+            duration(14, 3, 3723, 500_000_000)
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$duration": "PT-1M-30S"})).unwrap(),
+            duration(0, 0, -90, 0)
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$duration": "PT-1.5S"})).unwrap(),
+            duration(0, 0, -2, 500_000_000)
+        );
+        assert!(json_to_bolt(&json!({"$duration": "P1X"})).is_err());
+    }
+
+    #[test]
+    fn points_and_bytes_are_parsed() {
+        assert_eq!(
+            json_to_bolt(&json!({"srid": 7203, "x": 1.5, "y": -2.0})).unwrap(),
+            BoltType::Point2D(BoltPoint2D {
+                sr_id: BoltInteger::new(7203),
+                x: BoltFloat::new(1.5),
+                y: BoltFloat::new(-2.0),
+            })
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"srid": 4979, "x": 12.99, "y": 55.61, "z": 10.0})).unwrap(),
+            BoltType::Point3D(BoltPoint3D {
+                sr_id: BoltInteger::new(4979),
+                x: BoltFloat::new(12.99),
+                y: BoltFloat::new(55.61),
+                z: BoltFloat::new(10.0),
+            })
+        );
+        assert_eq!(
+            json_to_bolt(&json!({"$bytes": "AAEC/v8="})).unwrap(),
+            BoltType::from(vec![0_u8, 1, 2, 254, 255])
+        );
+    }
+
+    #[test]
+    fn arrays_and_objects_are_converted_recursively() {
+        let items = json!([
+            {"GUID": "a", "tags": ["x", "y"], "seen": {"$date": "2024-03-09"}},
+            {"GUID": "b", "location": {"srid": 7203, "x": 1.0, "y": 2.0}}
+        ]);
+
+        let BoltType::List(list) = json_to_bolt(&items).unwrap() else {
+            panic!("expected a list");
+        };
+        assert_eq!(list.len(), 2);
+
+        let BoltType::Map(first) = &list.value[0] else {
+            panic!("expected a map");
+        };
+        assert_eq!(first.get::<String>("GUID").unwrap(), "a");
+        assert_eq!(
+            first.get::<Vec<String>>("tags").unwrap(),
+            vec!["x".to_string(), "y".to_string()]
+        );
+        assert_eq!(
+            first.get::<NaiveDate>("seen").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 3, 9).unwrap()
+        );
+
+        let BoltType::Map(second) = &list.value[1] else {
+            panic!("expected a map");
+        };
+        assert!(matches!(
+            second.value.get(&BoltString::from("location")),
+            Some(BoltType::Point2D(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_hints_are_plain_maps() {
+        let BoltType::Map(map) = json_to_bolt(&json!({"$label": "Item"})).unwrap() else {
+            panic!("expected a map");
+        };
+        assert_eq!(map.get::<String>("$label").unwrap(), "Item");
+    }
+
+    #[test]
+    fn nested_errors_name_the_offending_path() {
+        let error = json_to_bolt(&json!({"items": [1, {"$date": "yesterday"}]})).unwrap_err();
+        assert!(error.starts_with("items: [1]: invalid $date"), "{error}");
+    }
+
+    /// A value read with `Row::get_all_json` and sent back as a parameter
+    fn round_trip(value: BoltType) -> BoltType {
+        let row = neo4rs::Row::new(
+            BoltList::from(vec![BoltType::from("v")]),
+            BoltList::from(vec![value]),
+        );
+        json_to_bolt(&row.get_all_json()["v"]).unwrap()
+    }
+
+    #[test]
+    fn values_read_from_rows_round_trip() {
+        use chrono::TimeZone;
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        let time = NaiveTime::from_hms_milli_opt(14, 5, 7, 250).unwrap();
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let values = [
+            BoltType::from(date),
+            BoltType::from(time),
+            BoltType::from((time, offset)),
+            BoltType::from(date.and_time(time)),
+            BoltType::from(offset.from_local_datetime(&date.and_time(time)).unwrap()),
+            BoltType::from((date.and_hms_opt(14, 5, 7).unwrap(), "Europe/Paris")),
+            BoltType::Duration(BoltDuration::new(
+                14.into(),
+                3.into(),
+                (-3723).into(),
+                500_000_000.into(),
+            )),
+            BoltType::from(vec![0_u8, 1, 2, 254, 255]),
+            BoltType::Point2D(BoltPoint2D {
+                sr_id: BoltInteger::new(7203),
+                x: BoltFloat::new(1.5),
+                y: BoltFloat::new(-2.0),
+            }),
+            BoltType::Point3D(BoltPoint3D {
+                sr_id: BoltInteger::new(4979),
+                x: BoltFloat::new(12.99),
+                y: BoltFloat::new(55.61),
+                z: BoltFloat::new(10.0),
+            }),
+        ];
+
+        for value in values {
+            assert_eq!(round_trip(value.clone()), value, "{value:?}");
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::database::neo4j_gateway::starts_with_keyword;
+
+/// Plan arguments lifted into their own fields of [`PlanOperator`]
+const LIFTED_ARGUMENTS: &[&str] = &["Details", "EstimatedRows", "DbHits", "Rows"];
+
+/// How to run a query whose plan is wanted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanMode {
+    /// Plan only, nothing is executed and no rows are returned
+    Explain,
+    /// Execute the query and report what every operator actually did
+    Profile,
+}
+
+impl PlanMode {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            PlanMode::Explain => "EXPLAIN",
+            PlanMode::Profile => "PROFILE",
+        }
+    }
+
+    /// The query prefixed with the mode's keyword, or `None` when it already
+    /// starts with `EXPLAIN` or `PROFILE`
+    pub fn apply(self, cypher: &str) -> Option<String> {
+        let trimmed = cypher.trim_start();
+        if starts_with_keyword(trimmed, "EXPLAIN") || starts_with_keyword(trimmed, "PROFILE") {
+            return None;
+        }
+        Some(format!("{} {trimmed}", self.keyword()))
+    }
+}
+
+/// Execution plan of an `EXPLAIN` or `PROFILE` query, decoded from the plan the
+/// server sends with the result summary
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryPlan {
+    /// Whether the operators carry actual rows and db hits
+    pub profiled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub planner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<String>,
+    /// Sum over all operators, profiled plans only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_db_hits: Option<u64>,
+    pub root: PlanOperator,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanOperator {
+    /// e.g. `NodeByLabelScan`, without the `@neo4j` runtime suffix of Neo4j 5
+    pub operator: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub identifiers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_rows: Option<f64>,
+    /// Rows produced, profiled plans only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u64>,
+    /// Storage accesses, profiled plans only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_hits: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_cache_hits: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_cache_misses: Option<u64>,
+    /// Remaining plan arguments as reported, e.g. `Order` or `Memory`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub arguments: BTreeMap<String, Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PlanOperator>,
+}
+
+impl QueryPlan {
+    /// Decode the plan of [`QuerySummary::plan`](super::neo4j_gateway::QuerySummary)
+    pub fn from_summary(plan: &Value) -> Option<Self> {
+        let root = PlanOperator::from_value(plan.as_object()?)?;
+        let profiled = root.db_hits.is_some() || root.rows.is_some();
+        let argument = |key: &str| root.arguments.get(key)?.as_str().map(str::to_string);
+
+        Some(Self {
+            profiled,
+            planner: argument("planner"),
+            runtime: argument("runtime"),
+            total_db_hits: profiled.then(|| root.total_db_hits()),
+            root,
+        })
+    }
+}
+
+impl PlanOperator {
+    fn from_value(plan: &Map<String, Value>) -> Option<Self> {
+        let operator = plan.get("operatorType")?.as_str()?;
+        let operator = operator.split_once('@').map_or(operator, |(name, _)| name);
+        let arguments = plan
+            .get("args")
+            .or_else(|| plan.get("arguments"))
+            .and_then(Value::as_object);
+        let argument = |key: &str| arguments.and_then(|arguments| arguments.get(key));
+        let count = |key: &str| plan.get(key).or_else(|| argument(key)).and_then(as_count);
+
+        Some(Self {
+            operator: operator.to_string(),
+            details: argument("Details")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            identifiers: plan
+                .get("identifiers")
+                .and_then(Value::as_array)
+                .map(|identifiers| {
+                    identifiers
+                        .iter()
+                        .filter_map(|identifier| identifier.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            estimated_rows: argument("EstimatedRows").and_then(Value::as_f64),
+            rows: count("rows").or_else(|| count("Rows")),
+            db_hits: count("dbHits").or_else(|| count("DbHits")),
+            page_cache_hits: count("pageCacheHits"),
+            page_cache_misses: count("pageCacheMisses"),
+            arguments: arguments
+                .map(|arguments| {
+                    arguments
+                        .iter()
+                        .filter(|(key, _)| !LIFTED_ARGUMENTS.contains(&key.as_str()))
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            children: plan
+                .get("children")
+                .and_then(Value::as_array)
+                .map(|children| {
+                    children
+                        .iter()
+                        .filter_map(Value::as_object)
+                        .filter_map(Self::from_value)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    fn total_db_hits(&self) -> u64 {
+        self.db_hits.unwrap_or(0)
+            + self
+                .children
+                .iter()
+                .map(PlanOperator::total_db_hits)
+                .sum::<u64>()
+    }
+}
+
+fn as_count(value: &Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_f64().map(|count| count as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn prefixes_queries_once() {
+        assert_eq!(
+            PlanMode::Profile.apply("  MATCH (n) RETURN n").as_deref(),
+            Some("PROFILE MATCH (n) RETURN n")
+        );
+        assert_eq!(PlanMode::Explain.apply("profile MATCH (n) RETURN n"), None);
+    }
+
+    #[test]
+    fn decodes_profiled_plans() {
+        let plan = json!({
+            "operatorType": "ProduceResults@neo4j",
+            "identifiers": ["n"],
+            "args": {
+                "planner": "COST",
+                "runtime": "PIPELINED",
+                "EstimatedRows": 12.5,
+                "Details": "n",
+            },
+            "dbHits": 0,
+            "rows": 10,
+            "children": [{
+                "operatorType": "NodeByLabelScan@neo4j",
+                "identifiers": ["n"],
+                "args": { "EstimatedRows": 12.5, "Details": "n:Service" },
+                "dbHits": 11,
+                "rows": 10,
+                "pageCacheHits": 3,
+                "pageCacheMisses": 0,
+            }],
+        });
+        let plan = QueryPlan::from_summary(&plan).unwrap();
+
+        assert!(plan.profiled);
+        assert_eq!(plan.runtime.as_deref(), Some("PIPELINED"));
+        assert_eq!(plan.total_db_hits, Some(11));
+        assert_eq!(plan.root.operator, "ProduceResults");
+        let scan = &plan.root.children[0];
+        assert_eq!(scan.operator, "NodeByLabelScan");
+        assert_eq!(scan.details.as_deref(), Some("n:Service"));
+        assert_eq!(scan.estimated_rows, Some(12.5));
+        assert_eq!(scan.page_cache_hits, Some(3));
+        assert!(!plan.root.arguments.contains_key("EstimatedRows"));
+    }
+
+    #[test]
+    fn explained_plans_have_no_actuals() {
+        let plan = json!({
+            "operatorType": "AllNodesScan",
+            "args": { "EstimatedRows": 100.0 },
+        });
+        let plan = QueryPlan::from_summary(&plan).unwrap();
+
+        assert!(!plan.profiled);
+        assert_eq!(plan.total_db_hits, None);
+        assert_eq!(plan.root.rows, None);
+    }
+}
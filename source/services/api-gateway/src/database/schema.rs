@@ -0,0 +1,429 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::database::access::CypherAccess;
+use crate::database::neo4j_gateway::{GatewayError, Neo4jGateway};
+use crate::graph_events::DeltaDispatcher;
+
+const LABELS_QUERY: &str = "CALL db.labels() YIELD label RETURN label ORDER BY label";
+const RELATIONSHIP_TYPES_QUERY: &str = "\
+    CALL db.relationshipTypes() YIELD relationshipType \
+    RETURN relationshipType ORDER BY relationshipType";
+const NODE_PROPERTIES_QUERY: &str = "\
+    CALL db.schema.nodeTypeProperties() \
+    YIELD nodeLabels, propertyName, propertyTypes, mandatory \
+    RETURN nodeLabels, propertyName, propertyTypes, mandatory";
+const RELATIONSHIP_PROPERTIES_QUERY: &str = "\
+    CALL db.schema.relTypeProperties() \
+    YIELD relType, propertyName, propertyTypes, mandatory \
+    RETURN relType, propertyName, propertyTypes, mandatory";
+const CONSTRAINTS_QUERY: &str = "\
+    SHOW CONSTRAINTS YIELD name, type, entityType, labelsOrTypes, properties \
+    RETURN name, type, entityType, labelsOrTypes, properties";
+const INDEXES_QUERY: &str = "\
+    SHOW INDEXES YIELD name, type, entityType, labelsOrTypes, properties, state \
+    RETURN name, type, entityType, labelsOrTypes, properties, state";
+
+/// What the graph currently contains, for autocompleting and validating Cypher
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphSchema {
+    pub labels: Vec<String>,
+    pub relationship_types: Vec<String>,
+    /// Properties seen on nodes, per label
+    pub node_properties: BTreeMap<String, Vec<PropertySchema>>,
+    /// Properties seen on relationships, per type
+    pub relationship_properties: BTreeMap<String, Vec<PropertySchema>>,
+    pub constraints: Vec<ConstraintSchema>,
+    pub indexes: Vec<IndexSchema>,
+    pub generated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PropertySchema {
+    pub name: String,
+    /// Neo4j value types seen, e.g. `String` or `List<Long>`
+    pub types: Vec<String>,
+    /// Set on every node with the label, or every relationship of the type
+    pub mandatory: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConstraintSchema {
+    pub name: String,
+    /// e.g. `UNIQUENESS`, `NODE_KEY` or `NODE_PROPERTY_EXISTENCE`
+    pub kind: String,
+    /// `NODE` or `RELATIONSHIP`
+    pub entity_type: String,
+    pub labels_or_types: Vec<String>,
+    pub properties: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexSchema {
+    pub name: String,
+    /// e.g. `RANGE`, `TEXT`, `FULLTEXT` or `LOOKUP`
+    pub kind: String,
+    pub entity_type: String,
+    /// Empty for token lookup indexes
+    pub labels_or_types: Vec<String>,
+    pub properties: Vec<String>,
+    pub state: String,
+}
+
+impl GraphSchema {
+    /// Read the schema with the `db.*` procedures and the constraint and index
+    /// listings. Listings the server does not support are left empty.
+    pub async fn load(neo4j: &Neo4jGateway) -> Result<Self, GatewayError> {
+        let rows = |query_id: &'static str, cypher: &'static str| async move {
+            let result = neo4j
+                .execute(query_id, cypher, &HashMap::new(), CypherAccess::ReadOnly)
+                .await?;
+            Ok::<_, GatewayError>(result_rows(result.raw_response))
+        };
+
+        let labels = string_column(&rows("graph-schema-labels", LABELS_QUERY).await?, "label");
+        let relationship_types = string_column(
+            &rows("graph-schema-relationship-types", RELATIONSHIP_TYPES_QUERY).await?,
+            "relationshipType",
+        );
+        let node_properties =
+            node_properties(&rows("graph-schema-node-properties", NODE_PROPERTIES_QUERY).await?);
+        let relationship_properties = relationship_properties(
+            &rows(
+                "graph-schema-relationship-properties",
+                RELATIONSHIP_PROPERTIES_QUERY,
+            )
+            .await?,
+        );
+
+        let constraints = match rows("graph-schema-constraints", CONSTRAINTS_QUERY).await {
+            Ok(rows) => rows.iter().map(constraint).collect(),
+            Err(e) => {
+                warn!("Failed to list constraints for the graph schema: {}", e);
+                Vec::new()
+            }
+        };
+        let indexes = match rows("graph-schema-indexes", INDEXES_QUERY).await {
+            Ok(rows) => rows.iter().map(index).collect(),
+            Err(e) => {
+                warn!("Failed to list indexes for the graph schema: {}", e);
+                Vec::new()
+            }
+        };
+
+        Ok(Self {
+            labels,
+            relationship_types,
+            node_properties,
+            relationship_properties,
+            constraints,
+            indexes,
+            generated_at: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+struct CachedSchema {
+    schema: Arc<GraphSchema>,
+    loaded_at: Instant,
+    generation: u64,
+}
+
+/// The last loaded [`GraphSchema`], reloaded on the first request after it
+/// expired or after graph changes arrived on the `graph:delta` feed. Changes made
+/// without the feed, such as new indexes, show up once the TTL runs out.
+pub struct SchemaCache {
+    ttl: Duration,
+    cached: Mutex<Option<CachedSchema>>,
+    /// Bumped by every graph change; a schema loaded before is stale
+    generation: AtomicU64,
+    /// Lets one request reload the schema while the others wait for it
+    reload: tokio::sync::Mutex<()>,
+}
+
+impl SchemaCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cached: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            reload: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub async fn get(&self, neo4j: &Neo4jGateway) -> Result<Arc<GraphSchema>, GatewayError> {
+        if let Some(schema) = self.fresh() {
+            return Ok(schema);
+        }
+
+        let _reload = self.reload.lock().await;
+        // Another request may have reloaded it while this one waited
+        if let Some(schema) = self.fresh() {
+            return Ok(schema);
+        }
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let schema = Arc::new(GraphSchema::load(neo4j).await?);
+        debug!(
+            "Graph schema loaded: {} labels, {} relationship types",
+            schema.labels.len(),
+            schema.relationship_types.len()
+        );
+        *self.cached.lock().unwrap() = Some(CachedSchema {
+            schema: schema.clone(),
+            loaded_at: Instant::now(),
+            generation,
+        });
+        Ok(schema)
+    }
+
+    /// Drop the cached schema at the next request
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn fresh(&self) -> Option<Arc<GraphSchema>> {
+        let generation = self.generation.load(Ordering::Acquire);
+        self.cached
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|cached| {
+                cached.generation == generation && cached.loaded_at.elapsed() < self.ttl
+            })
+            .map(|cached| cached.schema.clone())
+    }
+
+    /// Invalidate the cache whenever the `graph:delta` feed carries changes
+    pub fn watch(self: Arc<Self>, dispatcher: Arc<DeltaDispatcher>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            dispatcher
+                .follow_feed(
+                    "Graph schema cache",
+                    |_| {
+                        self.invalidate();
+                        std::future::ready(())
+                    },
+                    || self.invalidate(),
+                )
+                .await
+        })
+    }
+}
+
+fn result_rows(raw_response: Value) -> Vec<Value> {
+    match raw_response {
+        Value::Object(mut response) => match response.remove("results") {
+            Some(Value::Array(rows)) => rows,
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn string_column(rows: &[Value], column: &str) -> Vec<String> {
+    rows.iter()
+        .filter_map(|row| row[column].as_str().map(str::to_string))
+        .collect()
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// Property rows of `db.schema.nodeTypeProperties()`, one per label combination,
+/// merged per label. A property is only mandatory for a label when it is for
+/// every combination the label occurs in.
+fn node_properties(rows: &[Value]) -> BTreeMap<String, Vec<PropertySchema>> {
+    let mut combinations: BTreeMap<String, usize> = BTreeMap::new();
+    let mut seen: BTreeMap<String, BTreeMap<String, (BTreeSet<String>, usize)>> = BTreeMap::new();
+
+    // Every combination has one row per property, or one without a property
+    let mut counted: BTreeSet<Vec<String>> = BTreeSet::new();
+    for row in rows {
+        let labels = strings(&row["nodeLabels"]);
+        if counted.insert(labels.clone()) {
+            for label in &labels {
+                *combinations.entry(label.clone()).or_default() += 1;
+            }
+        }
+        let Some(name) = row["propertyName"].as_str() else {
+            continue;
+        };
+        let mandatory = row["mandatory"].as_bool().unwrap_or(false);
+        for label in labels {
+            let (types, mandatory_in) = seen
+                .entry(label)
+                .or_default()
+                .entry(name.to_string())
+                .or_default();
+            types.extend(strings(&row["propertyTypes"]));
+            *mandatory_in += usize::from(mandatory);
+        }
+    }
+
+    seen.into_iter()
+        .map(|(label, properties)| {
+            let total = combinations.get(&label).copied().unwrap_or(0);
+            let properties = properties
+                .into_iter()
+                .map(|(name, (types, mandatory_in))| PropertySchema {
+                    name,
+                    types: types.into_iter().collect(),
+                    mandatory: mandatory_in == total,
+                })
+                .collect();
+            (label, properties)
+        })
+        .collect()
+}
+
+/// Property rows of `db.schema.relTypeProperties()`, whose types are given as
+/// ``:`TYPE` ``
+fn relationship_properties(rows: &[Value]) -> BTreeMap<String, Vec<PropertySchema>> {
+    let mut properties: BTreeMap<String, Vec<PropertySchema>> = BTreeMap::new();
+    for row in rows {
+        let Some(name) = row["propertyName"].as_str() else {
+            continue;
+        };
+        let rel_type = text(&row["relType"]);
+        let rel_type = rel_type
+            .strip_prefix(':')
+            .unwrap_or(&rel_type)
+            .trim_matches('`')
+            .to_string();
+        let mut types = strings(&row["propertyTypes"]);
+        types.sort();
+        types.dedup();
+        properties
+            .entry(rel_type)
+            .or_default()
+            .push(PropertySchema {
+                name: name.to_string(),
+                types,
+                mandatory: row["mandatory"].as_bool().unwrap_or(false),
+            });
+    }
+    for type_properties in properties.values_mut() {
+        type_properties.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    properties
+}
+
+fn constraint(row: &Value) -> ConstraintSchema {
+    ConstraintSchema {
+        name: text(&row["name"]),
+        kind: text(&row["type"]),
+        entity_type: text(&row["entityType"]),
+        labels_or_types: strings(&row["labelsOrTypes"]),
+        properties: strings(&row["properties"]),
+    }
+}
+
+fn index(row: &Value) -> IndexSchema {
+    IndexSchema {
+        name: text(&row["name"]),
+        kind: text(&row["type"]),
+        entity_type: text(&row["entityType"]),
+        labels_or_types: strings(&row["labelsOrTypes"]),
+        properties: strings(&row["properties"]),
+        state: text(&row["state"]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merges_node_properties_per_label() {
+        let rows = vec![
+            json!({ "nodeLabels": ["Service"], "propertyName": "name",
+                    "propertyTypes": ["String"], "mandatory": true }),
+            json!({ "nodeLabels": ["Service"], "propertyName": "port",
+                    "propertyTypes": ["Long"], "mandatory": false }),
+            json!({ "nodeLabels": ["Service", "Deprecated"], "propertyName": "name",
+                    "propertyTypes": ["String"], "mandatory": true }),
+            json!({ "nodeLabels": ["Service", "Deprecated"], "propertyName": "port",
+                    "propertyTypes": ["String"], "mandatory": true }),
+            json!({ "nodeLabels": ["Empty"], "propertyName": null,
+                    "propertyTypes": null, "mandatory": false }),
+        ];
+        let properties = node_properties(&rows);
+
+        assert_eq!(
+            properties["Service"],
+            vec![
+                PropertySchema {
+                    name: "name".to_string(),
+                    types: vec!["String".to_string()],
+                    mandatory: true,
+                },
+                PropertySchema {
+                    name: "port".to_string(),
+                    types: vec!["Long".to_string(), "String".to_string()],
+                    mandatory: false,
+                },
+            ]
+        );
+        assert!(properties["Deprecated"].iter().all(|p| p.mandatory));
+        assert!(!properties.contains_key("Empty"));
+    }
+
+    #[test]
+    fn strips_relationship_type_quoting() {
+        let rows = vec![
+            json!({ "relType": ":`CALLS`", "propertyName": "weight",
+                    "propertyTypes": ["Double"], "mandatory": false }),
+            json!({ "relType": ":`CONTAINS`", "propertyName": null,
+                    "propertyTypes": null, "mandatory": false }),
+        ];
+        let properties = relationship_properties(&rows);
+
+        assert_eq!(properties.keys().collect::<Vec<_>>(), vec!["CALLS"]);
+        assert_eq!(properties["CALLS"][0].types, vec!["Double".to_string()]);
+    }
+
+    #[test]
+    fn expires_and_invalidates() {
+        let cache = SchemaCache::new(Duration::from_secs(60));
+        *cache.cached.lock().unwrap() = Some(CachedSchema {
+            schema: Arc::new(GraphSchema::default()),
+            loaded_at: Instant::now(),
+            generation: 0,
+        });
+        assert!(cache.fresh().is_some());
+
+        cache.invalidate();
+        assert!(cache.fresh().is_none());
+
+        let expired = SchemaCache::new(Duration::ZERO);
+        *expired.cached.lock().unwrap() = Some(CachedSchema {
+            schema: Arc::new(GraphSchema::default()),
+            loaded_at: Instant::now(),
+            generation: 0,
+        });
+        assert!(expired.fresh().is_none());
+    }
+}
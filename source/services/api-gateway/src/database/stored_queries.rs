@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use neo4rs::Query;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::database::access::CypherAccess;
+use crate::database::neo4j_gateway::Neo4jGateway;
+
+/// JSON type a stored query parameter must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterType {
+    String,
+    Integer,
+    /// Also accepts integers
+    Float,
+    Boolean,
+    List,
+    Map,
+    /// Anything, including the `$date`/`$duration`/... hints understood by the gateway
+    Any,
+}
+
+impl ParameterType {
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            ParameterType::String => value.is_string(),
+            ParameterType::Integer => value.is_i64() || value.is_u64(),
+            ParameterType::Float => value.is_number(),
+            ParameterType::Boolean => value.is_boolean(),
+            ParameterType::List => value.is_array(),
+            ParameterType::Map => value.is_object(),
+            ParameterType::Any => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterSpec {
+    #[serde(rename = "type")]
+    pub kind: ParameterType,
+    /// Used when the caller omits the parameter; a parameter without default is required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    /// Whether an explicit `null` is accepted
+    #[serde(default)]
+    pub nullable: bool,
+}
+
+/// A named, parameterized query as declared in `config/queries/*.json` or by a
+/// `(:StoredQuery)` node in Neo4j.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredQueryDefinition {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing)]
+    pub cypher: String,
+    #[serde(default)]
+    pub parameters: HashMap<String, ParameterSpec>,
+    /// Transaction timeout instead of the gateway's `NEO4J_QUERY_TIMEOUT_MS`,
+    /// for queries known to need longer, or to be cut off sooner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl StoredQueryDefinition {
+    /// `cypher`, normally the definition's own, as a query carrying its timeout
+    pub fn prepare(&self, cypher: String) -> Query {
+        let query = Query::new(cypher);
+        match self.timeout_ms {
+            Some(timeout_ms) => query.extra("tx_timeout", timeout_ms as i64),
+            None => query,
+        }
+    }
+
+    /// Check the caller's parameters against the declared schema, filling in defaults.
+    /// Undeclared parameters are rejected so that callers notice typos.
+    pub fn bind(
+        &self,
+        mut supplied: HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, String> {
+        if let Some(unknown) = supplied
+            .keys()
+            .find(|key| !self.parameters.contains_key(*key))
+        {
+            return Err(format!("unknown parameter {unknown}"));
+        }
+
+        let mut bound = HashMap::with_capacity(self.parameters.len());
+        for (name, spec) in &self.parameters {
+            let value = match supplied.remove(name).or_else(|| spec.default.clone()) {
+                Some(value) => value,
+                None => return Err(format!("missing parameter {name}")),
+            };
+
+            if value.is_null() {
+                if !spec.nullable {
+                    return Err(format!("parameter {name} must not be null"));
+                }
+            } else if !spec.kind.accepts(&value) {
+                return Err(format!("parameter {name} must be of type {:?}", spec.kind));
+            }
+
+            bound.insert(name.clone(), value);
+        }
+
+        Ok(bound)
+    }
+}
+
+/// A definition together with its prepared query, which is cloned for every call.
+#[derive(Debug, Clone)]
+pub struct StoredQuery {
+    pub definition: StoredQueryDefinition,
+    pub prepared: Query,
+}
+
+/// The allow-list of queries the frontend may invoke by id.
+#[derive(Debug, Default)]
+pub struct StoredQueryRegistry {
+    queries: HashMap<String, StoredQuery>,
+}
+
+impl StoredQueryRegistry {
+    /// Load every `*.json` file in `dir`; each holds one definition or an array of them.
+    /// A missing directory yields an empty registry.
+    pub fn load_dir(dir: &Path) -> anyhow::Result<Self> {
+        let mut registry = Self::default();
+        if !dir.is_dir() {
+            warn!(
+                target: "kalisi_gateway::database::stored_queries",
+                dir = %dir.display(),
+                "Stored query directory not found, registry is empty"
+            );
+            return Ok(registry);
+        }
+
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let contents = std::fs::read_to_string(&path)?;
+            let definitions = parse_definitions(&contents).map_err(|error| {
+                anyhow::anyhow!("invalid stored query file {}: {error}", path.display())
+            })?;
+            for definition in definitions {
+                registry.insert(definition)?;
+            }
+        }
+
+        info!(
+            target: "kalisi_gateway::database::stored_queries",
+            dir = %dir.display(),
+            count = registry.queries.len(),
+            "Stored queries loaded"
+        );
+        Ok(registry)
+    }
+
+    /// Add the definitions stored as `(:StoredQuery {id, cypher, description, parameters, timeoutMs})`
+    /// nodes, where `parameters` is the JSON encoded parameter schema.
+    pub async fn load_neo4j(&mut self, neo4j: &Neo4jGateway) -> anyhow::Result<()> {
+        let result = neo4j
+            .execute(
+                "stored-query-registry",
+                "MATCH (q:StoredQuery) RETURN q.id AS id, q.cypher AS cypher, \
+                 q.description AS description, q.parameters AS parameters, \
+                 q.timeoutMs AS timeout_ms",
+                &HashMap::new(),
+                CypherAccess::ReadOnly,
+            )
+            .await?;
+
+        let rows = result.raw_response["results"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        for mut row in rows {
+            // The parameter schema is kept as a JSON string property on the node
+            if let Some(parameters) = row.get("parameters").and_then(Value::as_str) {
+                row["parameters"] = serde_json::from_str(parameters)?;
+            } else {
+                row["parameters"] = Value::Object(Default::default());
+            }
+            self.insert(serde_json::from_value(row)?)?;
+        }
+
+        info!(
+            target: "kalisi_gateway::database::stored_queries",
+            count = self.queries.len(),
+            "Stored queries loaded from Neo4j"
+        );
+        Ok(())
+    }
+
+    fn insert(&mut self, definition: StoredQueryDefinition) -> anyhow::Result<()> {
+        if self.queries.contains_key(&definition.id) {
+            anyhow::bail!("duplicate stored query id {}", definition.id);
+        }
+        let prepared = definition.prepare(definition.cypher.clone());
+        self.queries.insert(
+            definition.id.clone(),
+            StoredQuery {
+                definition,
+                prepared,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&StoredQuery> {
+        self.queries.get(id)
+    }
+
+    pub fn definitions(&self) -> Vec<&StoredQueryDefinition> {
+        let mut definitions: Vec<_> = self
+            .queries
+            .values()
+            .map(|query| &query.definition)
+            .collect();
+        definitions.sort_by(|a, b| a.id.cmp(&b.id));
+        definitions
+    }
+}
+
+fn parse_definitions(contents: &str) -> serde_json::Result<Vec<StoredQueryDefinition>> {
+    match serde_json::from_str::<Value>(contents)? {
+        Value::Array(items) => items.into_iter().map(serde_json::from_value).collect(),
+        single => Ok(vec![serde_json::from_value(single)?]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn definition() -> StoredQueryDefinition {
+        serde_json::from_value(json!({
+            "id": "subtree",
+            "cypher": "MATCH (n {GUID: $guid}) RETURN n LIMIT $limit",
+            "parameters": {
+                "guid": {"type": "string"},
+                "limit": {"type": "integer", "default": 100}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn binds_parameters_with_defaults() {
+        let bound = definition()
+            .bind(HashMap::from([("guid".to_string(), json!("abc"))]))
+            .unwrap();
+
+        assert_eq!(bound["guid"], json!("abc"));
+        assert_eq!(bound["limit"], json!(100));
+    }
+
+    #[test]
+    fn rejects_invalid_parameters() {
+        let definition = definition();
+
+        assert_eq!(
+            definition.bind(HashMap::new()).unwrap_err(),
+            "missing parameter guid"
+        );
+        assert!(definition
+            .bind(HashMap::from([("guid".to_string(), json!(1))]))
+            .is_err());
+        assert!(definition
+            .bind(HashMap::from([
+                ("guid".to_string(), json!("abc")),
+                ("extra".to_string(), json!(true)),
+            ]))
+            .is_err());
+        assert!(definition
+            .bind(HashMap::from([("guid".to_string(), Value::Null)]))
+            .is_err());
+    }
+
+    #[test]
+    fn parses_single_and_multiple_definitions() {
+        let single = r#"{"id": "a", "cypher": "RETURN 1"}"#;
+        let many = r#"[{"id": "a", "cypher": "RETURN 1"}, {"id": "b", "cypher": "RETURN 2"}]"#;
+
+        assert_eq!(parse_definitions(single).unwrap().len(), 1);
+        assert_eq!(parse_definitions(many).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn prepares_queries_with_their_timeout() {
+        let mut definition = definition();
+        assert!(!definition
+            .prepare(definition.cypher.clone())
+            .has_extra_key("tx_timeout"));
+
+        definition.timeout_ms = Some(120_000);
+        let prepared = definition.prepare("PROFILE RETURN 1".to_string());
+        assert!(prepared.has_extra_key("tx_timeout"));
+        assert_eq!(prepared.query(), "PROFILE RETURN 1");
+    }
+}
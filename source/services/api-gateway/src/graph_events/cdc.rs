@@ -0,0 +1,652 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use crate::database::access::CypherAccess;
+use crate::database::neo4j_gateway::{GatewayError, Neo4jGateway};
+use crate::graph_events::emit::{fetch, FETCH_NODES_QUERY, FETCH_RELATIONSHIPS_QUERY};
+use crate::graph_events::{
+    GraphChanges, GraphDelta, GraphDeltaConfig, GraphDeltaPublisher, NodeUpdate,
+};
+use crate::runtime::canvas::CanvasHarvester;
+use crate::runtime::dto::{CanvasNodeDto, CanvasRelationshipDto};
+
+const VIEWS_QUERY: &str = "\
+    MATCH (vn:ViewNode) WHERE vn.GUID IS NOT NULL \
+    OPTIONAL MATCH (vn)-[:HAS_QUERY]->(q:QueryNode) \
+    WITH vn, head(collect(q.cypherQuery)) AS query \
+    RETURN vn.GUID AS guid, coalesce(query, vn.cypherQuery) AS cypher";
+
+const VIEW_QUERY: &str = "\
+    MATCH (vn:ViewNode {GUID: $guid}) \
+    OPTIONAL MATCH (vn)-[:HAS_QUERY]->(q:QueryNode) \
+    WITH vn, head(collect(q.cypherQuery)) AS query \
+    RETURN coalesce(query, vn.cypherQuery) AS cypher";
+
+/// `lastModified` is set by the writing transaction but becomes visible at commit,
+/// so a slower transaction can commit a timestamp below the watermark.
+const COMMIT_LAG_MS: i64 = 2_000;
+
+/// Labels and relationship types with a range index on `lastModified`. Changes
+/// are only looked for on these, since a lookup without a label or type scans the
+/// whole graph.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimestampIndexes {
+    pub labels: Vec<String>,
+    pub relationship_types: Vec<String>,
+}
+
+impl Default for TimestampIndexes {
+    fn default() -> Self {
+        Self {
+            labels: vec!["CodeElement".to_string(), "ViewNode".to_string()],
+            relationship_types: Vec::new(),
+        }
+    }
+}
+
+impl TimestampIndexes {
+    /// A query for the entities whose `lastModified` is at or after `$since`, one
+    /// indexed lookup per label and type with the entity bound to `e`, or `None`
+    /// when there is nothing to look at
+    pub(super) fn query(&self, node_returns: &str, relationship_returns: &str) -> Option<String> {
+        let nodes = self.labels.iter().map(|label| {
+            format!(
+                "MATCH (e:{}) WHERE e.lastModified >= $since AND e.GUID IS NOT NULL \
+                 RETURN {node_returns}",
+                quote_name(label)
+            )
+        });
+        let relationships = self.relationship_types.iter().map(|rel_type| {
+            format!(
+                "MATCH ()-[e:{}]->() WHERE e.lastModified >= $since AND e.GUID IS NOT NULL \
+                 RETURN {relationship_returns}",
+                quote_name(rel_type)
+            )
+        });
+        let lookups: Vec<String> = nodes.chain(relationships).collect();
+        (!lookups.is_empty()).then(|| lookups.join(" UNION ALL "))
+    }
+}
+
+/// A label or relationship type as a Cypher identifier
+fn quote_name(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// Background change data capture for writes that bypass the gateway, such as
+/// `build-model`, cypher-shell or agents.
+///
+/// Every poll looks for nodes and relationships whose `lastModified` moved (see
+/// `scripts/neo4j/add_timestamp_support.cypher` and [`TimestampIndexes`]). When
+/// something changed, the query scope of each ViewNode that shows a changed entity,
+/// or one of a changed label or type, is re-evaluated and compared with the
+/// previous result, which also catches deletions. Since deletions leave no
+/// timestamp behind and a query can start matching entities of a label it showed
+/// none of, every scope is re-evaluated every `resync_interval` as well.
+pub struct CdcPoller {
+    neo4j: Arc<Neo4jGateway>,
+    publisher: Arc<Mutex<GraphDeltaPublisher>>,
+    enabled: bool,
+    indexes: TimestampIndexes,
+    poll_interval: Duration,
+    resync_interval: Duration,
+    watermark: i64,
+    /// `lastModified` of changes already handled within the commit lag window
+    recent: HashMap<String, i64>,
+    scopes: HashMap<String, ViewScope>,
+    last_resync: Instant,
+}
+
+/// A ViewNode's query and the entities it returned last time
+#[derive(Debug, Default)]
+pub(super) struct ViewScope {
+    cypher: String,
+    /// `None` until the scope has been evaluated once
+    contents: Option<ScopeContents>,
+}
+
+#[derive(Debug, Default)]
+struct ScopeContents {
+    nodes: HashSet<String>,
+    relationships: HashSet<String>,
+    /// Node labels and relationship types
+    labels: HashSet<String>,
+}
+
+/// Entities whose `lastModified` moved since the last poll
+#[derive(Debug, Default)]
+struct Changes {
+    guids: HashSet<String>,
+    /// Their node labels and relationship types
+    labels: HashSet<String>,
+}
+
+impl CdcPoller {
+    pub fn new(
+        config: &GraphDeltaConfig,
+        neo4j: Arc<Neo4jGateway>,
+        publisher: Arc<Mutex<GraphDeltaPublisher>>,
+    ) -> Self {
+        Self {
+            neo4j,
+            publisher,
+            enabled: config.enabled && config.cdc_enabled,
+            indexes: config.timestamp_indexes.clone(),
+            poll_interval: config.cdc_poll_interval,
+            resync_interval: config.cdc_resync_interval,
+            watermark: 0,
+            recent: HashMap::new(),
+            scopes: HashMap::new(),
+            last_resync: Instant::now(),
+        }
+    }
+
+    /// Starts polling when both graph deltas and CDC are enabled.
+    pub fn spawn_if_enabled(self) -> Option<JoinHandle<()>> {
+        if !self.enabled {
+            return None;
+        }
+        Some(tokio::spawn(self.run()))
+    }
+
+    async fn run(mut self) {
+        info!(
+            "Graph CDC poller started: poll_interval={:?}, resync_interval={:?}",
+            self.poll_interval, self.resync_interval
+        );
+
+        // Start from the database clock so that history is not replayed
+        while let Err(e) = self.start().await {
+            warn!("Graph CDC poller could not start, retrying: {}", e);
+            tokio::time::sleep(self.resync_interval).await;
+        }
+
+        let mut interval = tokio::time::interval(self.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.poll().await {
+                warn!("Graph CDC poll failed: {}", e);
+            }
+        }
+    }
+
+    async fn start(&mut self) -> Result<(), GatewayError> {
+        self.watermark = database_clock(&self.neo4j).await?;
+
+        // Record the initial contents so that the first changes produce deltas
+        self.resync(&Changes::default(), true).await
+    }
+
+    async fn poll(&mut self) -> Result<(), GatewayError> {
+        let changes = self.changes().await?;
+        let full = self.last_resync.elapsed() >= self.resync_interval;
+        if changes.guids.is_empty() && !full {
+            return Ok(());
+        }
+
+        debug!(
+            "Graph CDC detected {} changed entities",
+            changes.guids.len()
+        );
+        self.resync(&changes, full).await
+    }
+
+    async fn changes(&mut self) -> Result<Changes, GatewayError> {
+        let mut changes = Changes::default();
+        let Some(cypher) = self.indexes.query(
+            "e.GUID AS guid, e.lastModified AS modified, labels(e) AS labels",
+            "e.GUID AS guid, e.lastModified AS modified, [type(e)] AS labels",
+        ) else {
+            return Ok(changes);
+        };
+
+        let since = self.watermark - COMMIT_LAG_MS;
+        let parameters = HashMap::from([("since".to_string(), json!(since))]);
+        let result = self
+            .neo4j
+            .execute(
+                "graph-cdc-changes",
+                &cypher,
+                &parameters,
+                CypherAccess::ReadOnly,
+            )
+            .await?;
+
+        for row in rows(&result.raw_response) {
+            let (Some(guid), Some(timestamp)) = (row["guid"].as_str(), row["modified"].as_i64())
+            else {
+                continue;
+            };
+            if self.recent.get(guid) == Some(&timestamp) {
+                continue;
+            }
+            self.recent.insert(guid.to_string(), timestamp);
+            self.watermark = self.watermark.max(timestamp);
+            changes.guids.insert(guid.to_string());
+            let labels = row["labels"].as_array().into_iter().flatten();
+            changes
+                .labels
+                .extend(labels.filter_map(Value::as_str).map(str::to_string));
+        }
+
+        let horizon = self.watermark - COMMIT_LAG_MS;
+        self.recent.retain(|_, timestamp| *timestamp > horizon);
+        Ok(changes)
+    }
+
+    /// Re-evaluate the ViewNode scopes `changes` may concern, or all of them when
+    /// `full`, and publish what changed in them
+    async fn resync(&mut self, changes: &Changes, full: bool) -> Result<(), GatewayError> {
+        if full {
+            self.last_resync = Instant::now();
+        }
+        self.refresh_views().await?;
+
+        let modified = &changes.guids;
+        let mut left_nodes = HashSet::new();
+        let mut left_relationships = HashSet::new();
+
+        for (view_node_id, scope) in &mut self.scopes {
+            if !full && !scope.affected_by(changes) {
+                continue;
+            }
+            let (nodes, edges) =
+                match evaluate_scope(&self.neo4j, view_node_id, &scope.cypher).await {
+                    Ok(contents) => contents,
+                    Err(GatewayError::Forbidden { query_type }) => {
+                        warn!(
+                            "Query of ViewNode {} is {:?}, not a read, and is not tracked",
+                            view_node_id, query_type
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to evaluate query of ViewNode {}: {}",
+                            view_node_id, e
+                        );
+                        continue;
+                    }
+                };
+
+            let delta = scope.apply(view_node_id, nodes, edges, modified);
+            if let Some(delta) = delta.filter(|delta| !delta.is_empty()) {
+                left_nodes.extend(delta.nodes_deleted.iter().cloned());
+                left_relationships.extend(delta.relationships_deleted.iter().cloned());
+
+                let mut publisher = self.publisher.lock().await;
+                match publisher.publish(&delta).await {
+                    Ok(message_id) => debug!(
+                        "Published CDC delta for ViewNode {}: message_id={}",
+                        view_node_id, message_id
+                    ),
+                    Err(e) => error!(
+                        "Failed to publish CDC delta for ViewNode {}: {}",
+                        view_node_id, e
+                    ),
+                }
+            }
+        }
+
+        self.publish_changes(modified, &left_nodes, &left_relationships)
+            .await
+    }
+
+    /// Publish what changed in the graph as a whole to the `graph:delta` feed.
+    /// Deletions leave no trace but entities dropping out of ViewNode scopes, so
+    /// those that left a scope and no longer exist count as deleted; deletions
+    /// outside every scope go unnoticed.
+    async fn publish_changes(
+        &self,
+        modified: &HashSet<String>,
+        left_nodes: &HashSet<String>,
+        left_relationships: &HashSet<String>,
+    ) -> Result<(), GatewayError> {
+        if modified.is_empty() && left_nodes.is_empty() && left_relationships.is_empty() {
+            return Ok(());
+        }
+
+        let node_guids = modified.union(left_nodes).cloned().collect();
+        let (nodes, _) = fetch(&self.neo4j, FETCH_NODES_QUERY, node_guids).await?;
+        let relationship_guids = modified.union(left_relationships).cloned().collect();
+        let (_, edges) = fetch(&self.neo4j, FETCH_RELATIONSHIPS_QUERY, relationship_guids).await?;
+
+        let mut changes = GraphChanges::new();
+        let existing: HashSet<_> = nodes.iter().map(|node| node.GUID.clone()).collect();
+        changes.nodes_deleted = sorted_difference(left_nodes, &existing);
+        let existing: HashSet<_> = edges.iter().map(|edge| edge.GUID.clone()).collect();
+        changes.relationships_deleted = sorted_difference(left_relationships, &existing);
+        changes.nodes_updated = nodes
+            .into_iter()
+            .filter(|node| modified.contains(&node.GUID))
+            .collect();
+        changes.relationships_updated = edges
+            .into_iter()
+            .filter(|edge| modified.contains(&edge.GUID))
+            .collect();
+
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut publisher = self.publisher.lock().await;
+        if let Err(e) = publisher.publish_changes(&changes).await {
+            error!("Failed to publish CDC changes: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Track new ViewNodes, drop deleted ones and reset those whose query changed
+    async fn refresh_views(&mut self) -> Result<(), GatewayError> {
+        let result = self
+            .neo4j
+            .execute(
+                "graph-cdc-views",
+                VIEWS_QUERY,
+                &HashMap::new(),
+                CypherAccess::ReadOnly,
+            )
+            .await?;
+
+        let mut views = HashMap::new();
+        for row in rows(&result.raw_response) {
+            if let (Some(guid), Some(cypher)) = (row["guid"].as_str(), row["cypher"].as_str()) {
+                views.insert(guid.to_string(), cypher.to_string());
+            }
+        }
+
+        self.scopes
+            .retain(|guid, scope| views.get(guid) == Some(&scope.cypher));
+        for (guid, cypher) in views {
+            self.scopes.entry(guid).or_insert_with(|| ViewScope {
+                cypher,
+                contents: None,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ViewScope {
+    /// Whether `changes` may concern the scope: it was not evaluated yet, or shows
+    /// a changed entity or one with a changed label or type
+    fn affected_by(&self, changes: &Changes) -> bool {
+        let Some(contents) = &self.contents else {
+            return true;
+        };
+        changes
+            .guids
+            .iter()
+            .any(|guid| contents.nodes.contains(guid) || contents.relationships.contains(guid))
+            || changes
+                .labels
+                .iter()
+                .any(|label| contents.labels.contains(label))
+    }
+
+    /// Evaluates the query of `view_node_id`, or returns `None` if there is no such
+    /// ViewNode or it has no query
+    pub(super) async fn capture(
+        neo4j: &Neo4jGateway,
+        view_node_id: &str,
+    ) -> Result<Option<Self>, GatewayError> {
+        let parameters = HashMap::from([("guid".to_string(), json!(view_node_id))]);
+        let result = neo4j
+            .execute(
+                "graph-delta-view",
+                VIEW_QUERY,
+                &parameters,
+                CypherAccess::ReadOnly,
+            )
+            .await?;
+        let Some(cypher) = rows(&result.raw_response)
+            .first()
+            .and_then(|row| row["cypher"].as_str())
+        else {
+            return Ok(None);
+        };
+
+        let mut scope = ViewScope {
+            cypher: cypher.to_string(),
+            contents: None,
+        };
+        let (nodes, edges) = evaluate_scope(neo4j, view_node_id, &scope.cypher).await?;
+        scope.apply(view_node_id, nodes, edges, &HashSet::new());
+        Ok(Some(scope))
+    }
+
+    /// Evaluates the query again and returns the delta against the last evaluation
+    pub(super) async fn reevaluate(
+        &mut self,
+        neo4j: &Neo4jGateway,
+        view_node_id: &str,
+        modified: &HashSet<String>,
+    ) -> Result<Option<GraphDelta>, GatewayError> {
+        let (nodes, edges) = evaluate_scope(neo4j, view_node_id, &self.cypher).await?;
+        Ok(self.apply(view_node_id, nodes, edges, modified))
+    }
+
+    /// Replace the remembered contents and return the delta against them, or
+    /// `None` on the first evaluation.
+    fn apply(
+        &mut self,
+        view_node_id: &str,
+        nodes: Vec<CanvasNodeDto>,
+        edges: Vec<CanvasRelationshipDto>,
+        modified: &HashSet<String>,
+    ) -> Option<GraphDelta> {
+        let current = ScopeContents {
+            nodes: nodes.iter().map(|node| node.GUID.clone()).collect(),
+            relationships: edges.iter().map(|edge| edge.GUID.clone()).collect(),
+            labels: nodes
+                .iter()
+                .flat_map(|node| node.labels.iter().cloned())
+                .chain(edges.iter().map(|edge| edge.r#type.clone()))
+                .collect(),
+        };
+        let previous = self.contents.replace(current)?;
+
+        let mut delta = GraphDelta::new(view_node_id.to_string());
+        for node in nodes {
+            if !previous.nodes.contains(&node.GUID) {
+                delta.nodes_created.push(node);
+            } else if modified.contains(&node.GUID) {
+                delta.nodes_updated.push(NodeUpdate {
+                    guid: node.GUID,
+                    properties: node.properties,
+                });
+            }
+        }
+        for edge in edges {
+            if !previous.relationships.contains(&edge.GUID) {
+                delta.relationships_created.push(edge);
+            }
+        }
+
+        let current = self.contents.as_ref()?;
+        // Entities that left the scope are gone from the canvas, deleted or not
+        delta.nodes_deleted = sorted_difference(&previous.nodes, &current.nodes);
+        delta.relationships_deleted =
+            sorted_difference(&previous.relationships, &current.relationships);
+
+        Some(delta)
+    }
+}
+
+async fn evaluate_scope(
+    neo4j: &Neo4jGateway,
+    view_node_id: &str,
+    cypher: &str,
+) -> Result<(Vec<CanvasNodeDto>, Vec<CanvasRelationshipDto>), GatewayError> {
+    let result = neo4j
+        .execute(
+            &format!("graph-cdc-scope:{view_node_id}"),
+            cypher,
+            &HashMap::new(),
+            CypherAccess::ReadOnly,
+        )
+        .await?;
+
+    let mut harvester = CanvasHarvester::default();
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for row in rows(&result.raw_response) {
+        let (row_nodes, row_edges) = harvester.harvest(row);
+        nodes.extend(row_nodes);
+        edges.extend(row_edges);
+    }
+    Ok((nodes, edges))
+}
+
+/// Milliseconds since the epoch on the database clock, the clock `lastModified`
+/// is set from
+pub(super) async fn database_clock(neo4j: &Neo4jGateway) -> Result<i64, GatewayError> {
+    let result = neo4j
+        .execute(
+            "graph-delta-clock",
+            "RETURN timestamp() AS now",
+            &HashMap::new(),
+            CypherAccess::ReadOnly,
+        )
+        .await?;
+    rows(&result.raw_response)
+        .first()
+        .and_then(|row| row["now"].as_i64())
+        .ok_or_else(|| GatewayError::query("timestamp() returned no value"))
+}
+
+fn rows(raw_response: &Value) -> &[Value] {
+    raw_response["results"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+pub(super) fn sorted_difference(from: &HashSet<String>, to: &HashSet<String>) -> Vec<String> {
+    let mut guids: Vec<_> = from.difference(to).cloned().collect();
+    guids.sort();
+    guids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(guid: &str, name: &str) -> CanvasNodeDto {
+        CanvasNodeDto {
+            GUID: guid.to_string(),
+            labels: vec!["Node".to_string()],
+            parent_guid: None,
+            position: None,
+            display: None,
+            tags: HashMap::new(),
+            properties: HashMap::from([("name".to_string(), json!(name))]),
+        }
+    }
+
+    fn edge(guid: &str) -> CanvasRelationshipDto {
+        CanvasRelationshipDto {
+            GUID: guid.to_string(),
+            fromGUID: "a".to_string(),
+            toGUID: "b".to_string(),
+            r#type: "CONTAINS".to_string(),
+            display: None,
+            properties: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn first_evaluation_only_records_contents() {
+        let mut scope = ViewScope::default();
+
+        let delta = scope.apply("view", vec![node("a", "A")], vec![], &HashSet::new());
+
+        assert!(delta.is_none());
+        assert!(scope.contents.unwrap().nodes.contains("a"));
+    }
+
+    #[test]
+    fn diffs_scope_against_previous_evaluation() {
+        let mut scope = ViewScope::default();
+        scope.apply(
+            "view",
+            vec![node("a", "A"), node("b", "B"), node("c", "C")],
+            vec![edge("ab")],
+            &HashSet::new(),
+        );
+
+        let modified = HashSet::from(["b".to_string(), "d".to_string()]);
+        let delta = scope
+            .apply(
+                "view",
+                vec![node("a", "A"), node("b", "B2"), node("d", "D")],
+                vec![edge("ad")],
+                &modified,
+            )
+            .unwrap();
+
+        assert_eq!(delta.view_node_id, "view");
+        assert_eq!(delta.nodes_created.len(), 1);
+        assert_eq!(delta.nodes_created[0].GUID, "d");
+        assert_eq!(delta.nodes_updated.len(), 1);
+        assert_eq!(delta.nodes_updated[0].guid, "b");
+        assert_eq!(delta.nodes_updated[0].properties["name"], json!("B2"));
+        assert_eq!(delta.nodes_deleted, vec!["c".to_string()]);
+        assert_eq!(delta.relationships_created[0].GUID, "ad");
+        assert_eq!(delta.relationships_deleted, vec!["ab".to_string()]);
+    }
+
+    #[test]
+    fn looks_up_changes_per_indexed_label_and_type() {
+        let indexes = TimestampIndexes {
+            labels: vec!["CodeElement".to_string(), "View`Node".to_string()],
+            relationship_types: vec!["CONTAINS".to_string()],
+        };
+
+        assert_eq!(
+            indexes.query("e.GUID AS guid", "e.GUID AS guid").unwrap(),
+            "MATCH (e:`CodeElement`) WHERE e.lastModified >= $since AND e.GUID IS NOT NULL \
+             RETURN e.GUID AS guid UNION ALL \
+             MATCH (e:`View``Node`) WHERE e.lastModified >= $since AND e.GUID IS NOT NULL \
+             RETURN e.GUID AS guid UNION ALL \
+             MATCH ()-[e:`CONTAINS`]->() WHERE e.lastModified >= $since AND e.GUID IS NOT NULL \
+             RETURN e.GUID AS guid"
+        );
+        let none = TimestampIndexes {
+            labels: Vec::new(),
+            relationship_types: Vec::new(),
+        };
+        assert!(none.query("e", "e").is_none());
+    }
+
+    #[test]
+    fn only_changes_to_shown_entities_or_labels_affect_a_scope() {
+        let mut scope = ViewScope::default();
+        let changes = |guid: &str, label: &str| Changes {
+            guids: HashSet::from([guid.to_string()]),
+            labels: HashSet::from([label.to_string()]),
+        };
+        assert!(scope.affected_by(&changes("x", "Other")));
+
+        scope.apply(
+            "view",
+            vec![node("a", "A")],
+            vec![edge("ab")],
+            &HashSet::new(),
+        );
+
+        assert!(scope.affected_by(&changes("a", "Other")));
+        assert!(scope.affected_by(&changes("ab", "Other")));
+        assert!(scope.affected_by(&changes("x", "Node")));
+        assert!(scope.affected_by(&changes("x", "CONTAINS")));
+        assert!(!scope.affected_by(&changes("x", "Other")));
+    }
+}
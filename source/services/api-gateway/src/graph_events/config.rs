@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::cdc::TimestampIndexes;
+use super::redis_publisher::DEFAULT_STREAM_MAXLEN;
+
+/// How graph deltas are published, part of [`Config`](crate::config::Config)
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphDeltaConfig {
+    /// Whether graph changes are published at all
+    pub enabled: bool,
+    /// Entries kept per delta stream, approximately. Subscribers that fall
+    /// further behind have to resync.
+    pub stream_maxlen: usize,
+    /// Whether the CDC poller is the source of graph deltas. Writes through the
+    /// gateway then do not publish their own delta, which would reach the canvas twice.
+    pub cdc_enabled: bool,
+    pub cdc_poll_interval: Duration,
+    /// Every ViewNode scope is re-evaluated this often, see [`CdcPoller`](super::CdcPoller)
+    pub cdc_resync_interval: Duration,
+    pub timestamp_indexes: TimestampIndexes,
+}
+
+impl Default for GraphDeltaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stream_maxlen: DEFAULT_STREAM_MAXLEN,
+            cdc_enabled: false,
+            cdc_poll_interval: Duration::from_secs(5),
+            cdc_resync_interval: Duration::from_secs(30),
+            timestamp_indexes: TimestampIndexes::default(),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamId, StreamRangeReply, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Notify};
+use tracing::{debug, error, info, warn};
+
+use super::redis_publisher::{delta_stream_key, GRAPH_DELTA_STREAM};
+use super::GraphChanges;
+
+/// Messages buffered per stream for subscribers that are slow to send
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Entries read per stream and round trip
+const READ_BATCH: usize = 100;
+
+/// Wait before subscribing to the feed again after it failed, see [`DeltaDispatcher::follow_feed`]
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// A stream entry as forwarded to subscribers
+#[derive(Debug, Clone)]
+pub struct DeltaMessage {
+    pub stream_id: String,
+    /// Entry JSON including `streamId`, so that clients can resume after it
+    pub payload: String,
+}
+
+/// A new subscription: entries the client missed, followed by live ones
+pub struct DeltaSubscription {
+    pub replay: Vec<DeltaMessage>,
+    live: broadcast::Receiver<DeltaMessage>,
+    /// Last entry covered so far; live deltas up to it are duplicates
+    position: String,
+    /// The client's last-seen entry has been trimmed, so it has to reload its state
+    pub resync_required: bool,
+}
+
+impl DeltaSubscription {
+    /// Next live entry, skipping those already covered by the replay. Fails with
+    /// `Lagged` when the subscriber fell behind and entries were dropped.
+    pub async fn recv(&mut self) -> Result<DeltaMessage, broadcast::error::RecvError> {
+        loop {
+            let message = self.live.recv().await?;
+            if is_after(&message.stream_id, &self.position) {
+                self.position = message.stream_id.clone();
+                return Ok(message);
+            }
+        }
+    }
+}
+
+struct StreamChannel {
+    sender: broadcast::Sender<DeltaMessage>,
+    /// ID of the last entry read from the stream
+    position: String,
+}
+
+/// Reads the per-ViewNode delta streams and the `graph:delta` feed on one dedicated
+/// Redis connection and hands each entry to the subscribers of its stream. Only
+/// streams with at least one subscriber are read.
+pub struct DeltaDispatcher {
+    redis: ConnectionManager,
+    /// Keyed by stream
+    streams: Mutex<HashMap<String, StreamChannel>>,
+    /// Interrupts the blocking read when the set of streams changes
+    wake: Notify,
+}
+
+impl DeltaDispatcher {
+    /// Connects and spawns the reader task
+    pub async fn start(redis_url: &str) -> Result<Arc<Self>, redis::RedisError> {
+        let client = redis::Client::open(redis_url)?;
+        let dispatcher = Arc::new(Self {
+            redis: ConnectionManager::new(client.clone()).await?,
+            streams: Mutex::new(HashMap::new()),
+            wake: Notify::new(),
+        });
+
+        // Blocking reads get a connection of their own
+        let reader = ConnectionManager::new(client).await?;
+        tokio::spawn(dispatcher.clone().run(reader));
+
+        info!("Graph delta dispatcher started");
+        Ok(dispatcher)
+    }
+
+    /// Subscribe to the deltas of `view_node_id`. With `last_stream_id`, the deltas
+    /// published after that entry are returned for replay.
+    pub async fn subscribe(
+        &self,
+        view_node_id: &str,
+        last_stream_id: Option<&str>,
+    ) -> Result<DeltaSubscription, redis::RedisError> {
+        self.subscribe_stream(delta_stream_key(view_node_id), last_stream_id)
+            .await
+    }
+
+    /// Subscribe to the [`GraphChanges`](super::GraphChanges) of the whole graph
+    pub async fn subscribe_feed(
+        &self,
+        last_stream_id: Option<&str>,
+    ) -> Result<DeltaSubscription, redis::RedisError> {
+        self.subscribe_stream(GRAPH_DELTA_STREAM.to_string(), last_stream_id)
+            .await
+    }
+
+    /// Subscriptions to the `graph:delta` feed held in this process
+    pub fn feed_subscribers(&self) -> usize {
+        self.streams
+            .lock()
+            .unwrap()
+            .get(GRAPH_DELTA_STREAM)
+            .map_or(0, |channel| channel.sender.receiver_count())
+    }
+
+    /// Hands every [`GraphChanges`] of the `graph:delta` feed to `on_changes`,
+    /// subscribing again whenever the subscription fails. `on_gap` runs whenever
+    /// changes may have been missed: after every subscription, when `follower`
+    /// fell behind and for changes that cannot be read. Never returns.
+    pub async fn follow_feed<C, F>(
+        &self,
+        follower: &str,
+        mut on_changes: C,
+        mut on_gap: impl FnMut(),
+    ) where
+        C: FnMut(GraphChanges) -> F,
+        F: Future<Output = ()>,
+    {
+        loop {
+            let mut subscription = match self.subscribe_feed(None).await {
+                Ok(subscription) => subscription,
+                Err(e) => {
+                    warn!("{} could not follow graph changes: {}", follower, e);
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                    continue;
+                }
+            };
+            info!("{} following graph changes", follower);
+            on_gap();
+
+            loop {
+                match subscription.recv().await {
+                    Ok(message) => match serde_json::from_str::<GraphChanges>(&message.payload) {
+                        Ok(changes) => on_changes(changes).await,
+                        Err(e) => {
+                            warn!("Skipping malformed graph changes: {}", e);
+                            on_gap();
+                        }
+                    },
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "{} fell behind the graph changes", follower);
+                        on_gap();
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    async fn subscribe_stream(
+        &self,
+        key: String,
+        last_stream_id: Option<&str>,
+    ) -> Result<DeltaSubscription, redis::RedisError> {
+        let mut redis = self.redis.clone();
+
+        // Position the reader before looking for missed entries, so that every
+        // entry is either replayed or read live (duplicates are skipped by ID)
+        let latest: StreamRangeReply = redis.xrevrange_count(&key, "+", "-", 1).await?;
+        let latest = latest
+            .ids
+            .first()
+            .map_or_else(|| "0-0".to_string(), |entry| entry.id.clone());
+
+        let live = {
+            let mut streams = self.streams.lock().unwrap();
+            let channel = streams.entry(key.clone()).or_insert_with(|| StreamChannel {
+                sender: broadcast::channel(CHANNEL_CAPACITY).0,
+                position: latest.clone(),
+            });
+            channel.sender.subscribe()
+        };
+        self.wake.notify_one();
+
+        let Some(last_stream_id) = last_stream_id else {
+            return Ok(DeltaSubscription {
+                replay: Vec::new(),
+                live,
+                position: latest,
+                resync_required: false,
+            });
+        };
+
+        // Streams are trimmed from the oldest entry on, so nothing after the
+        // last-seen entry is gone as long as that entry is still there
+        let seen: Result<StreamRangeReply, _> = redis
+            .xrange_count(&key, last_stream_id, last_stream_id, 1)
+            .await;
+        if !seen.is_ok_and(|seen| !seen.ids.is_empty()) {
+            info!(
+                "Entry {} of stream {} is gone, client has to resync",
+                last_stream_id, key
+            );
+            return Ok(DeltaSubscription {
+                replay: Vec::new(),
+                live,
+                position: latest,
+                resync_required: true,
+            });
+        }
+
+        let missed: StreamRangeReply = redis
+            .xrange(&key, format!("({last_stream_id}"), "+")
+            .await?;
+        let position = missed
+            .ids
+            .last()
+            .map_or_else(|| last_stream_id.to_string(), |entry| entry.id.clone());
+        Ok(DeltaSubscription {
+            replay: missed.ids.iter().filter_map(delta_message).collect(),
+            live,
+            position,
+            resync_required: false,
+        })
+    }
+
+    async fn run(self: Arc<Self>, mut redis: ConnectionManager) {
+        loop {
+            // Forget streams whose last subscriber went away
+            let positions: Vec<(String, String)> = {
+                let mut streams = self.streams.lock().unwrap();
+                streams.retain(|_, channel| channel.sender.receiver_count() > 0);
+                streams
+                    .iter()
+                    .map(|(key, channel)| (key.clone(), channel.position.clone()))
+                    .collect()
+            };
+
+            if positions.is_empty() {
+                self.wake.notified().await;
+                continue;
+            }
+
+            let (keys, ids): (Vec<_>, Vec<_>) = positions.into_iter().unzip();
+            let opts = StreamReadOptions::default().block(5000).count(READ_BATCH);
+            let reply: Result<Option<StreamReadReply>, redis::RedisError> = tokio::select! {
+                reply = redis.xread_options(&keys, &ids, &opts) => reply,
+                _ = self.wake.notified() => continue,
+            };
+
+            match reply {
+                Ok(Some(reply)) => self.dispatch(reply),
+                // Nothing arrived within the block timeout
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Error reading graph delta streams: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    fn dispatch(&self, reply: StreamReadReply) {
+        let mut streams = self.streams.lock().unwrap();
+        for stream in reply.keys {
+            let Some(channel) = streams.get_mut(&stream.key) else {
+                continue;
+            };
+
+            for entry in &stream.ids {
+                channel.position = entry.id.clone();
+                if let Some(message) = delta_message(entry) {
+                    // Fails only when nobody listens anymore
+                    let _ = channel.sender.send(message);
+                }
+            }
+            debug!(
+                "Dispatched {} entries from {}",
+                stream.ids.len(),
+                stream.key
+            );
+        }
+    }
+}
+
+fn delta_message(entry: &StreamId) -> Option<DeltaMessage> {
+    let payload: String = entry.get("payload")?;
+    let mut delta = match serde_json::from_str::<serde_json::Value>(&payload) {
+        Ok(delta) => delta,
+        Err(e) => {
+            warn!("Skipping malformed graph delta {}: {}", entry.id, e);
+            return None;
+        }
+    };
+    delta["streamId"] = serde_json::Value::String(entry.id.clone());
+
+    Some(DeltaMessage {
+        stream_id: entry.id.clone(),
+        payload: delta.to_string(),
+    })
+}
+
+/// Whether stream entry `id` comes after `other`; IDs are `<millis>-<sequence>`
+fn is_after(id: &str, other: &str) -> bool {
+    fn parse(id: &str) -> Option<(u64, u64)> {
+        let (millis, sequence) = id.split_once('-').unwrap_or((id, "0"));
+        Some((millis.parse().ok()?, sequence.parse().ok()?))
+    }
+
+    match (parse(id), parse(other)) {
+        (Some(id), Some(other)) => id > other,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_stream_ids_numerically() {
+        assert!(is_after("1700000000001-0", "1700000000000-5"));
+        assert!(is_after("1700000000000-10", "1700000000000-9"));
+        assert!(!is_after("1700000000000-9", "1700000000000-9"));
+        assert!(!is_after("999-0", "1000-0"));
+        assert!(is_after("1000", "999-3"));
+    }
+}
@@ -1,146 +1,436 @@
-use crate::graph_events::{GraphDelta, GraphDeltaPublisher, NodeUpdate};
-use serde_json::Value;
-use std::collections::HashMap;
-use tracing::{error, warn};
-
-/// Detects if a Cypher query is a write operation
-/// Initial implementation: any query that doesn't start with MATCH is considered a write
-pub fn is_write_query(cypher: &str) -> bool {
-    let trimmed = cypher.trim().to_uppercase();
-
-    // List of write operations
-    let write_keywords = [
-        "CREATE",
-        "MERGE",
-        "SET",
-        "DELETE",
-        "REMOVE",
-        "DETACH DELETE",
-    ];
-
-    write_keywords
-        .iter()
-        .any(|keyword| trimmed.starts_with(keyword) || trimmed.contains(&format!(" {}", keyword)))
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde_json::{json, Value};
+use tracing::{debug, error, warn};
+
+use crate::database::access::CypherAccess;
+use crate::database::neo4j_gateway::{
+    GatewayError, GatewayQueryResult, Neo4jGateway, QueryCounters,
+};
+use crate::graph_events::cdc::{database_clock, TimestampIndexes, ViewScope};
+use crate::graph_events::{GraphChanges, GraphDelta, GraphDeltaConfig, GraphDeltaPublisher};
+use crate::runtime::canvas::CanvasHarvester;
+use crate::runtime::dto::{CanvasNodeDto, CanvasRelationshipDto};
+
+pub(super) const FETCH_NODES_QUERY: &str = "MATCH (n) WHERE n.GUID IN $guids RETURN n";
+pub(super) const FETCH_RELATIONSHIPS_QUERY: &str =
+    "MATCH ()-[r]->() WHERE r.GUID IN $guids RETURN r";
+
+/// State captured before a write, consumed by [`compute_delta`]
+pub struct DeltaCapture {
+    view_node_id: Option<String>,
+    /// Scope of the ViewNode before the write, `None` without a ViewNode query
+    scope: Option<ViewScope>,
+    /// Database clock before the write
+    since: i64,
+    /// Where changes since then are looked for
+    indexes: TimestampIndexes,
 }
 
-/// Attempts to emit a graph delta after a successful write
-/// Production implementation: extracts actual changed nodes from Neo4j result
-pub async fn try_emit_delta(
-    publisher: &mut GraphDeltaPublisher,
+/// Prepares for sending the changes of `statements` to the `graph:delta` feed and
+/// to `view_node_id`, if given, afterwards. Returns `None` when deltas are disabled,
+/// `access` does not permit the statements or none of them writes.
+///
+/// Whether a statement writes is the query type the server reports for its
+/// `EXPLAIN` plan, remembered per Cypher text, so running the statements
+/// afterwards does not ask again. Nothing is read for a write that will be refused. Otherwise the only reads are
+/// the database clock and the scope of the ViewNode, through `neo4j`, which should
+/// be the gateway of the writing user.
+pub async fn prepare_delta(
+    config: &GraphDeltaConfig,
+    neo4j: &Neo4jGateway,
     view_node_id: Option<String>,
-    cypher: &str,
-    neo4j_result: &Value,
-) -> Option<GraphDelta> {
-    // Feature flag check
-    let enabled = std::env::var("ENABLE_GRAPH_DELTA")
-        .unwrap_or_else(|_| "false".to_string())
-        .parse::<bool>()
-        .unwrap_or(false);
+    statements: &[(&str, &HashMap<String, Value>)],
+    access: CypherAccess,
+) -> Option<DeltaCapture> {
+    if !config.enabled {
+        debug!("Graph delta emission disabled (ENABLE_GRAPH_DELTA)");
+        return None;
+    }
 
-    warn!("🔍 try_emit_delta called: enabled={}, view_node_id={:?}, query_starts_with={}",
-        enabled, view_node_id, cypher.chars().take(50).collect::<String>());
+    if config.cdc_enabled {
+        // The CDC poller picks the write up like any other
+        return None;
+    }
 
-    if !enabled {
-        warn!("❌ Graph delta emission disabled (ENABLE_GRAPH_DELTA={})", enabled);
+    let mut writes = false;
+    for (cypher, parameters) in statements {
+        match neo4j.authorize_json(cypher, parameters, access).await {
+            Ok(query_type) => writes |= !query_type.is_read(),
+            // Running the statements reports the error
+            Err(_) => return None,
+        }
+    }
+    if !writes {
         return None;
     }
 
-    // Check if we have a view node ID
-    let view_node_id = match view_node_id {
-        Some(id) => {
-            warn!("✅ ViewNode ID provided: {}", id);
-            id
-        },
-        None => {
-            warn!("❌ No view_node_id provided, skipping delta emission");
+    let since = match database_clock(neo4j).await {
+        Ok(now) => now,
+        Err(e) => {
+            warn!(
+                "Failed to read the database clock, no delta will be emitted: {}",
+                e
+            );
             return None;
         }
     };
+    let scope = match view_node_id.as_deref() {
+        Some(view_node_id) => ViewScope::capture(neo4j, view_node_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(
+                    "Failed to evaluate the scope of ViewNode {} before write: {}",
+                    view_node_id, e
+                );
+                None
+            }),
+        None => None,
+    };
+
+    Some(DeltaCapture {
+        view_node_id,
+        scope,
+        since,
+        indexes: config.timestamp_indexes.clone(),
+    })
+}
+
+/// What a write changed, worked out by [`compute_delta`] and sent by [`publish_delta`]
+pub struct PendingDelta {
+    changes: GraphChanges,
+    /// For the ViewNode of the capture
+    delta: Option<GraphDelta>,
+}
+
+/// Works out what a successful write changed, for the `graph:delta` feed and as
+/// a delta for the ViewNode of the capture.
+///
+/// Only what the write touched is looked at: the entities it returned, those whose
+/// `lastModified` moved since the capture, and those that entered or left the
+/// ViewNode scope. The result summaries decide which kinds of change happened at
+/// all. Deletions leave nothing behind to find, so only those within the ViewNode
+/// scope are identified; any others mark the changes as requiring a resync.
+///
+/// This reads from Neo4j, so the publisher should only be locked afterwards.
+pub async fn compute_delta(
+    neo4j: &Neo4jGateway,
+    capture: Option<DeltaCapture>,
+    results: &[GatewayQueryResult],
+) -> Option<PendingDelta> {
+    let mut capture = capture?;
 
-    // Check if this is a write query
-    if !is_write_query(cypher) {
-        warn!("❌ Query is not a write operation, skipping delta emission");
+    let counters = write_counters(results);
+    if !counters.contains_graph_updates() {
+        debug!("Write did not change the graph, skipping delta emission");
         return None;
     }
 
-    warn!("✅ This IS a write query, proceeding with delta emission");
-
-    // Extract changed nodes from Neo4j result
-    // raw_response structure: { "results": [...], "count": N }
-    let mut delta = GraphDelta::new(view_node_id);
-
-    if let Some(results) = neo4j_result.get("results").and_then(|v| v.as_array()) {
-        warn!("📊 Extracting {} result(s) from Neo4j response", results.len());
-
-        for result in results {
-            // Each result is an object with variable names as keys (e.g., {"n": {...}})
-            if let Some(result_obj) = result.as_object() {
-                for (_var_name, node_value) in result_obj {
-                    // Check if this is a node object with GUID
-                    if let Some(guid) = node_value.get("GUID").and_then(|v| v.as_str()) {
-                        // Extract properties from the node
-                        let mut properties = HashMap::new();
-
-                        if let Some(props) = node_value.get("properties").and_then(|v| v.as_object()) {
-                            for (key, value) in props {
-                                // Skip GUID in properties since it's in the main object
-                                if key != "GUID" {
-                                    properties.insert(key.clone(), value.clone());
-                                }
-                            }
-                        }
-
-                        let node_update = NodeUpdate {
-                            guid: guid.to_string(),
-                            properties,
-                        };
-
-                        warn!("✅ Adding node update for GUID: {} with {} properties", guid, node_update.properties.len());
-                        delta.nodes_updated.push(node_update);
-                    }
-                }
-            }
+    let (changes, scope_delta) = match build_changes(neo4j, &mut capture, &counters, results).await
+    {
+        Ok(changes) => changes,
+        Err(e) => {
+            error!("Failed to compute graph delta: {}", e);
+            return None;
         }
+    };
+
+    let delta = match (scope_delta, capture.view_node_id.as_deref()) {
+        (Some(delta), _) => Some(delta),
+        (None, Some(view_node_id)) => Some(changes.delta_for(view_node_id)),
+        (None, None) => None,
     }
+    .filter(|delta| !delta.is_empty());
 
-    if delta.is_empty() {
-        warn!("⚠️  Delta is empty, no changes detected");
+    if changes.is_empty() && delta.is_none() {
+        debug!("Delta is empty, no changes detected");
         return None;
     }
+    Some(PendingDelta { changes, delta })
+}
+
+/// Publishes the changes to the `graph:delta` feed and the delta to the stream
+/// of its ViewNode, which is returned once published.
+pub async fn publish_delta(
+    publisher: &mut GraphDeltaPublisher,
+    pending: PendingDelta,
+) -> Option<GraphDelta> {
+    let PendingDelta { changes, delta } = pending;
+    if !changes.is_empty() {
+        if let Err(e) = publisher.publish_changes(&changes).await {
+            error!("Failed to publish graph changes to Redis: {}", e);
+        }
+    }
 
-    warn!("✅ Delta contains {} updated node(s)", delta.nodes_updated.len());
+    let delta = delta?;
+    debug!(
+        "Delta for ViewNode {}: {} created, {} updated, {} deleted node(s), {} created, {} deleted relationship(s)",
+        delta.view_node_id,
+        delta.nodes_created.len(),
+        delta.nodes_updated.len(),
+        delta.nodes_deleted.len(),
+        delta.relationships_created.len(),
+        delta.relationships_deleted.len()
+    );
 
     // Publish to Redis stream
     match publisher.publish(&delta).await {
         Ok(message_id) => {
-            warn!("✅ Published delta to Redis: message_id={}", message_id);
+            debug!("Published delta to Redis: message_id={}", message_id);
             Some(delta)
         }
         Err(e) => {
-            error!("❌ Failed to publish delta to Redis: {}", e);
+            error!("Failed to publish delta to Redis: {}", e);
             None
         }
     }
 }
 
+/// Graph changes counted over the statements that wrote
+fn write_counters(results: &[GatewayQueryResult]) -> QueryCounters {
+    let mut total = QueryCounters::default();
+    for result in results
+        .iter()
+        .filter(|result| !result.summary.query_type.is_read())
+    {
+        let counters = &result.summary.counters;
+        total.nodes_created += counters.nodes_created;
+        total.nodes_deleted += counters.nodes_deleted;
+        total.relationships_created += counters.relationships_created;
+        total.relationships_deleted += counters.relationships_deleted;
+        total.properties_set += counters.properties_set;
+        total.labels_added += counters.labels_added;
+        total.labels_removed += counters.labels_removed;
+    }
+    total
+}
+
+/// The changes for the `graph:delta` feed, and the delta of the ViewNode scope if
+/// the capture has one
+async fn build_changes(
+    neo4j: &Neo4jGateway,
+    capture: &mut DeltaCapture,
+    counters: &QueryCounters,
+    results: &[GatewayQueryResult],
+) -> Result<(GraphChanges, Option<GraphDelta>), GatewayError> {
+    // Entities returned by the write already carry their new state
+    let rows: Vec<&Value> = results
+        .iter()
+        .filter_map(|result| result.raw_response["results"].as_array())
+        .flatten()
+        .collect();
+    let (returned_nodes, returned_edges) = harvest(rows);
+    let mut nodes = BTreeMap::new();
+    let mut edges = BTreeMap::new();
+    for node in returned_nodes {
+        nodes.insert(node.GUID.clone(), node);
+    }
+    for edge in returned_edges {
+        edges.insert(edge.GUID.clone(), edge);
+    }
+
+    let created_or_modified = counters.nodes_created
+        + counters.relationships_created
+        + counters.properties_set
+        + counters.labels_added
+        + counters.labels_removed;
+    if created_or_modified > 0 {
+        let (modified_nodes, modified_edges) =
+            modified_since(neo4j, &capture.indexes, capture.since).await?;
+        for node in modified_nodes {
+            nodes.entry(node.GUID.clone()).or_insert(node);
+        }
+        for edge in modified_edges {
+            edges.entry(edge.GUID.clone()).or_insert(edge);
+        }
+    }
+
+    let touched: HashSet<String> = nodes.keys().chain(edges.keys()).cloned().collect();
+    let scope_delta = match (capture.view_node_id.as_deref(), capture.scope.as_mut()) {
+        (Some(view_node_id), Some(scope)) => {
+            scope.reevaluate(neo4j, view_node_id, &touched).await?
+        }
+        _ => None,
+    };
+
+    let mut entered_nodes = HashSet::new();
+    let mut entered_edges = HashSet::new();
+    let mut changes = GraphChanges::new();
+    if let Some(delta) = &scope_delta {
+        // Entering the scope is how writes that neither return nor stamp what they
+        // create are noticed
+        for node in &delta.nodes_created {
+            entered_nodes.insert(node.GUID.clone());
+            nodes
+                .entry(node.GUID.clone())
+                .or_insert_with(|| node.clone());
+        }
+        for edge in &delta.relationships_created {
+            entered_edges.insert(edge.GUID.clone());
+            edges
+                .entry(edge.GUID.clone())
+                .or_insert_with(|| edge.clone());
+        }
+
+        if counters.nodes_deleted > 0 {
+            changes.nodes_deleted = deleted(neo4j, FETCH_NODES_QUERY, &delta.nodes_deleted).await?;
+        }
+        if counters.relationships_deleted > 0 {
+            changes.relationships_deleted = deleted(
+                neo4j,
+                FETCH_RELATIONSHIPS_QUERY,
+                &delta.relationships_deleted,
+            )
+            .await?;
+        }
+    }
+
+    // Deletions outside the ViewNode scope, or without one, cannot be identified
+    changes.resync_required = (changes.nodes_deleted.len() as u64) < counters.nodes_deleted
+        || (changes.relationships_deleted.len() as u64) < counters.relationships_deleted;
+
+    (changes.nodes_created, changes.nodes_updated) = split_created(
+        nodes.into_values().collect(),
+        |node| &node.GUID,
+        counters.nodes_created,
+        &entered_nodes,
+    );
+    (changes.relationships_created, changes.relationships_updated) = split_created(
+        edges.into_values().collect(),
+        |edge| &edge.GUID,
+        counters.relationships_created,
+        &entered_edges,
+    );
+
+    Ok((changes, scope_delta))
+}
+
+/// Splits the entities a write touched into created and updated ones. Summaries
+/// only count creations, so when a write both created and updated entities, those
+/// that entered the ViewNode scope count as created and the rest as updated.
+fn split_created<T>(
+    entities: Vec<T>,
+    guid: impl Fn(&T) -> &String,
+    created: u64,
+    entered: &HashSet<String>,
+) -> (Vec<T>, Vec<T>) {
+    if created == 0 {
+        return (Vec::new(), entities);
+    }
+    if created as usize >= entities.len() {
+        return (entities, Vec::new());
+    }
+    entities
+        .into_iter()
+        .partition(|entity| entered.contains(guid(entity)))
+}
+
+/// Nodes and relationships whose `lastModified` is at or after `since`, among
+/// the `indexes`
+async fn modified_since(
+    neo4j: &Neo4jGateway,
+    indexes: &TimestampIndexes,
+    since: i64,
+) -> Result<(Vec<CanvasNodeDto>, Vec<CanvasRelationshipDto>), GatewayError> {
+    let Some(cypher) = indexes.query("e AS entity", "e AS entity") else {
+        return Ok((Vec::new(), Vec::new()));
+    };
+    let parameters = HashMap::from([("since".to_string(), json!(since))]);
+    let result = neo4j
+        .execute(
+            "graph-delta-modified",
+            &cypher,
+            &parameters,
+            CypherAccess::ReadOnly,
+        )
+        .await?;
+
+    Ok(harvest(
+        result.raw_response["results"]
+            .as_array()
+            .into_iter()
+            .flatten(),
+    ))
+}
+
+/// Those of `guids` that no longer exist
+async fn deleted(
+    neo4j: &Neo4jGateway,
+    cypher: &str,
+    guids: &[String],
+) -> Result<Vec<String>, GatewayError> {
+    let (nodes, edges) = fetch(neo4j, cypher, guids.to_vec()).await?;
+    let existing: HashSet<String> = nodes
+        .into_iter()
+        .map(|node| node.GUID)
+        .chain(edges.into_iter().map(|edge| edge.GUID))
+        .collect();
+    Ok(guids
+        .iter()
+        .filter(|guid| !existing.contains(*guid))
+        .cloned()
+        .collect())
+}
+
+pub(super) async fn fetch(
+    neo4j: &Neo4jGateway,
+    cypher: &str,
+    guids: Vec<String>,
+) -> Result<(Vec<CanvasNodeDto>, Vec<CanvasRelationshipDto>), GatewayError> {
+    if guids.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let parameters = HashMap::from([("guids".to_string(), json!(guids))]);
+    let result = neo4j
+        .execute(
+            "graph-delta-fetch",
+            cypher,
+            &parameters,
+            CypherAccess::ReadOnly,
+        )
+        .await?;
+
+    Ok(harvest(
+        result.raw_response["results"]
+            .as_array()
+            .into_iter()
+            .flatten(),
+    ))
+}
+
+fn harvest<'a>(
+    rows: impl IntoIterator<Item = &'a Value>,
+) -> (Vec<CanvasNodeDto>, Vec<CanvasRelationshipDto>) {
+    let mut harvester = CanvasHarvester::default();
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for row in rows {
+        let (row_nodes, row_edges) = harvester.harvest(row);
+        nodes.extend(row_nodes);
+        edges.extend(row_edges);
+    }
+    (nodes, edges)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_is_write_query() {
-        assert!(is_write_query("CREATE (n:Node {name: 'test'})"));
-        assert!(is_write_query("MERGE (n:Node {id: '123'})"));
-        assert!(is_write_query("MATCH (n) SET n.name = 'updated'"));
-        assert!(is_write_query("MATCH (n) DELETE n"));
-        assert!(is_write_query("MATCH (n) DETACH DELETE n"));
-        assert!(is_write_query("MATCH (n) REMOVE n.property"));
-
-        assert!(!is_write_query("MATCH (n) RETURN n"));
-        assert!(!is_write_query("MATCH (n:Node) WHERE n.id = '123' RETURN n"));
-
-        // Edge cases
-        assert!(is_write_query("  CREATE (n)"));
-        assert!(is_write_query("create (n)"));
+    fn splits_created_from_updated_entities() {
+        let guids = || vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let entered = HashSet::from(["b".to_string()]);
+        let split = |created| split_created(guids(), |guid| guid, created, &entered);
+
+        assert_eq!(split(0), (vec![], guids()));
+        assert_eq!(split(3), (guids(), vec![]));
+        assert_eq!(
+            split(1),
+            (
+                vec!["b".to_string()],
+                vec!["a".to_string(), "c".to_string()]
+            )
+        );
     }
 }
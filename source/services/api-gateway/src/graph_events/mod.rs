@@ -1,3 +1,6 @@
+mod cdc;
+mod config;
+mod dispatcher;
 mod emit;
 mod redis_publisher;
 mod types;
@@ -5,6 +8,9 @@ mod types;
 #[cfg(test)]
 mod tests;
 
-pub use emit::try_emit_delta;
+pub use cdc::{CdcPoller, TimestampIndexes};
+pub use config::GraphDeltaConfig;
+pub use dispatcher::{DeltaDispatcher, DeltaMessage, DeltaSubscription};
+pub use emit::{compute_delta, prepare_delta, publish_delta};
 pub use redis_publisher::GraphDeltaPublisher;
-pub use types::{GraphDelta, NodeUpdate};
+pub use types::{GraphChanges, GraphDelta, NodeUpdate};
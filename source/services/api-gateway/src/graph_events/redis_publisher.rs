@@ -1,29 +1,46 @@
 use redis::aio::ConnectionManager;
+use redis::streams::StreamMaxlen;
 use redis::AsyncCommands;
 use tracing::{debug, error, info};
 
-use super::GraphDelta;
+use super::{GraphChanges, GraphDelta};
 
-const GRAPH_DELTA_STREAM: &str = "graph:delta";
+/// Feed of [`GraphChanges`] for consumers that are not tied to a ViewNode
+pub const GRAPH_DELTA_STREAM: &str = "graph:delta";
+
+/// Every ViewNode has its own stream, `graph_events:{view_node_id}`
+pub const DELTA_STREAM_PREFIX: &str = "graph_events:";
+
+/// Entries kept per stream unless `GRAPH_DELTA_STREAM_MAXLEN` says otherwise
+pub(super) const DEFAULT_STREAM_MAXLEN: usize = 10_000;
+
+pub fn delta_stream_key(view_node_id: &str) -> String {
+    format!("{DELTA_STREAM_PREFIX}{view_node_id}")
+}
 
 /// Redis Stream publisher for graph deltas
 /// Uses a dedicated ConnectionManager to avoid blocking other Redis operations
 pub struct GraphDeltaPublisher {
     redis: ConnectionManager,
+    maxlen: usize,
 }
 
 impl GraphDeltaPublisher {
-    /// Creates a new GraphDeltaPublisher with a dedicated Redis connection
-    pub async fn new(redis_url: &str) -> Result<Self, redis::RedisError> {
+    /// Creates a new GraphDeltaPublisher with a dedicated Redis connection, keeping
+    /// about `maxlen` entries per stream
+    pub async fn new(redis_url: &str, maxlen: usize) -> Result<Self, redis::RedisError> {
         let client = redis::Client::open(redis_url)?;
         let redis = ConnectionManager::new(client).await?;
 
-        info!("GraphDeltaPublisher initialized with stream: {}", GRAPH_DELTA_STREAM);
+        info!(
+            "GraphDeltaPublisher initialized with streams: {}* (maxlen ~{})",
+            DELTA_STREAM_PREFIX, maxlen
+        );
 
-        Ok(Self { redis })
+        Ok(Self { redis, maxlen })
     }
 
-    /// Publishes a GraphDelta to the Redis stream
+    /// Publishes a GraphDelta to the stream of its ViewNode
     /// Returns the message ID assigned by Redis
     pub async fn publish(&mut self, delta: &GraphDelta) -> Result<String, redis::RedisError> {
         // Serialize the delta to JSON
@@ -38,62 +55,54 @@ impl GraphDeltaPublisher {
             }
         };
 
-        debug!(
-            "Publishing delta to stream {}: view_node_id={}",
-            GRAPH_DELTA_STREAM, delta.view_node_id
-        );
+        let stream = delta_stream_key(&delta.view_node_id);
+        debug!("Publishing delta to stream {}", stream);
 
-        // Add to Redis stream with XADD
-        // Format: XADD graph:delta * payload <json>
+        // Add to Redis stream with XADD, trimming old entries
+        // Format: XADD graph_events:<view_node_id> MAXLEN ~ <maxlen> * payload <json>
         let message_id: String = self
             .redis
-            .xadd(
-                GRAPH_DELTA_STREAM,
+            .xadd_maxlen(
+                &stream,
+                StreamMaxlen::Approx(self.maxlen),
                 "*", // Auto-generate ID
                 &[("payload", json.as_str())],
             )
             .await?;
 
         info!(
-            "Published delta to stream {}: message_id={}, view_node_id={}",
-            GRAPH_DELTA_STREAM, message_id, delta.view_node_id
+            "Published delta to stream {}: message_id={}",
+            stream, message_id
         );
 
         Ok(message_id)
     }
 
-    /// Creates or ensures a consumer group exists for the stream
-    /// This should be called by WebSocket handlers before consuming
-    #[allow(dead_code)]
-    pub async fn ensure_consumer_group(
+    /// Publishes the changes of one write to the `graph:delta` feed
+    pub async fn publish_changes(
         &mut self,
-        group_name: &str,
-    ) -> Result<(), redis::RedisError> {
-        match self
+        changes: &GraphChanges,
+    ) -> Result<String, redis::RedisError> {
+        let json = serde_json::to_string(changes).map_err(|e| {
+            error!("Failed to serialize GraphChanges: {}", e);
+            redis::RedisError::from((redis::ErrorKind::IoError, "Serialization failed"))
+        })?;
+
+        let message_id: String = self
             .redis
-            .xgroup_create_mkstream::<_, _, _, String>(GRAPH_DELTA_STREAM, group_name, "0")
-            .await
-        {
-            Ok(_) => {
-                info!(
-                    "Created consumer group '{}' for stream {}",
-                    group_name, GRAPH_DELTA_STREAM
-                );
-                Ok(())
-            }
-            Err(e) => {
-                if e.to_string().contains("BUSYGROUP") {
-                    debug!(
-                        "Consumer group '{}' already exists for stream {}",
-                        group_name, GRAPH_DELTA_STREAM
-                    );
-                    Ok(())
-                } else {
-                    error!("Failed to create consumer group '{}': {}", group_name, e);
-                    Err(e)
-                }
-            }
-        }
+            .xadd_maxlen(
+                GRAPH_DELTA_STREAM,
+                StreamMaxlen::Approx(self.maxlen),
+                "*",
+                &[("payload", json.as_str())],
+            )
+            .await?;
+
+        debug!(
+            "Published changes to stream {}: message_id={}",
+            GRAPH_DELTA_STREAM, message_id
+        );
+        Ok(message_id)
     }
 }
 
@@ -105,7 +114,7 @@ mod tests {
     #[ignore] // Requires Redis to be running
     async fn test_publisher_initialization() {
         let redis_url = "redis://127.0.0.1:6379";
-        let result = GraphDeltaPublisher::new(redis_url).await;
+        let result = GraphDeltaPublisher::new(redis_url, DEFAULT_STREAM_MAXLEN).await;
         assert!(result.is_ok());
     }
 
@@ -113,7 +122,9 @@ mod tests {
     #[ignore] // Requires Redis to be running
     async fn test_publish_delta() {
         let redis_url = "redis://127.0.0.1:6379";
-        let mut publisher = GraphDeltaPublisher::new(redis_url).await.unwrap();
+        let mut publisher = GraphDeltaPublisher::new(redis_url, DEFAULT_STREAM_MAXLEN)
+            .await
+            .unwrap();
 
         let delta = GraphDelta::new("test-view-node-123".to_string());
         let result = publisher.publish(&delta).await;
@@ -21,7 +21,7 @@ mod integration_tests {
 
         // Add a created node
         delta.nodes_created.push(CanvasNodeDto {
-            guid: "node-1".to_string(),
+            GUID: "node-1".to_string(),
             labels: vec!["TestNode".to_string()],
             parent_guid: None,
             position: None,
@@ -83,40 +83,3 @@ mod integration_tests {
         assert_eq!(delta.nodes_deleted[0], "node-123");
     }
 }
-
-#[cfg(test)]
-mod emit_tests {
-    use super::*;
-
-    #[test]
-    fn test_is_write_query_create() {
-        assert!(is_write_query("CREATE (n:Node {name: 'test'})"));
-        assert!(is_write_query("create (n)"));
-        assert!(is_write_query("  CREATE (n)  "));
-    }
-
-    #[test]
-    fn test_is_write_query_merge() {
-        assert!(is_write_query("MERGE (n:Node {id: '123'})"));
-        assert!(is_write_query("MATCH (a) MERGE (b)"));
-    }
-
-    #[test]
-    fn test_is_write_query_set() {
-        assert!(is_write_query("MATCH (n) SET n.name = 'updated'"));
-        assert!(is_write_query("MATCH (n) WHERE n.id = '1' SET n.prop = 'value'"));
-    }
-
-    #[test]
-    fn test_is_write_query_delete() {
-        assert!(is_write_query("MATCH (n) DELETE n"));
-        assert!(is_write_query("MATCH (n) DETACH DELETE n"));
-    }
-
-    #[test]
-    fn test_is_not_write_query() {
-        assert!(!is_write_query("MATCH (n) RETURN n"));
-        assert!(!is_write_query("MATCH (n:Node) WHERE n.id = '123' RETURN n"));
-        assert!(!is_write_query("CALL db.labels()"));
-    }
-}
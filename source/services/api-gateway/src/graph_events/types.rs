@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::runtime::dto::{CanvasNodeDto, CanvasRelationshipDto};
 
@@ -55,6 +55,159 @@ impl GraphDelta {
             && self.relationships_created.is_empty()
             && self.relationships_deleted.is_empty()
     }
+
+    /// Number of entities the delta touches
+    pub fn len(&self) -> usize {
+        self.nodes_created.len()
+            + self.nodes_updated.len()
+            + self.nodes_deleted.len()
+            + self.relationships_created.len()
+            + self.relationships_deleted.len()
+    }
+
+    /// Fold a later delta of the same ViewNode into this one, so that applying the
+    /// result equals applying both in order. Canvases apply updates, deletions and
+    /// then creations, which a deletion followed by a re-creation relies on.
+    pub fn merge(&mut self, later: GraphDelta) {
+        self.timestamp = later.timestamp;
+
+        // A creation carries the full node, superseding earlier states
+        let created: HashSet<String> = later
+            .nodes_created
+            .iter()
+            .map(|node| node.GUID.clone())
+            .collect();
+        self.nodes_created
+            .retain(|node| !created.contains(&node.GUID));
+        self.nodes_updated
+            .retain(|update| !created.contains(&update.guid));
+        self.nodes_created.extend(later.nodes_created);
+
+        for update in later.nodes_updated {
+            if let Some(node) = self
+                .nodes_created
+                .iter_mut()
+                .find(|node| node.GUID == update.guid)
+            {
+                node.properties.extend(update.properties);
+            } else if let Some(pending) = self
+                .nodes_updated
+                .iter_mut()
+                .find(|pending| pending.guid == update.guid)
+            {
+                pending.properties.extend(update.properties);
+            } else if !self.nodes_deleted.contains(&update.guid) {
+                self.nodes_updated.push(update);
+            }
+        }
+
+        let created: HashSet<String> = self
+            .nodes_created
+            .iter()
+            .map(|node| node.GUID.clone())
+            .collect();
+        let deleted: HashSet<&String> = later.nodes_deleted.iter().collect();
+        self.nodes_created
+            .retain(|node| !deleted.contains(&node.GUID));
+        self.nodes_updated
+            .retain(|update| !deleted.contains(&update.guid));
+        for guid in later.nodes_deleted.iter() {
+            // A node created and deleted again never reaches the canvas
+            if !created.contains(guid) && !self.nodes_deleted.contains(guid) {
+                self.nodes_deleted.push(guid.clone());
+            }
+        }
+
+        let created: HashSet<String> = later
+            .relationships_created
+            .iter()
+            .map(|edge| edge.GUID.clone())
+            .collect();
+        self.relationships_created
+            .retain(|edge| !created.contains(&edge.GUID));
+        self.relationships_created
+            .extend(later.relationships_created);
+
+        let created: HashSet<String> = self
+            .relationships_created
+            .iter()
+            .map(|edge| edge.GUID.clone())
+            .collect();
+        let deleted: HashSet<&String> = later.relationships_deleted.iter().collect();
+        self.relationships_created
+            .retain(|edge| !deleted.contains(&edge.GUID));
+        for guid in later.relationships_deleted.iter() {
+            if !created.contains(guid) && !self.relationships_deleted.contains(guid) {
+                self.relationships_deleted.push(guid.clone());
+            }
+        }
+    }
+}
+
+/// What changed in the graph as a whole, independent of any ViewNode. Published
+/// once per write to the `graph:delta` feed, however many ViewNodes it touches.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphChanges {
+    pub timestamp: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes_created: Vec<CanvasNodeDto>,
+    /// The CDC poller cannot tell creations from updates and reports both here, as
+    /// do gateway writes that create and update at once outside a ViewNode scope
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes_updated: Vec<CanvasNodeDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nodes_deleted: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relationships_created: Vec<CanvasRelationshipDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relationships_updated: Vec<CanvasRelationshipDto>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relationships_deleted: Vec<String>,
+    /// Entities were deleted that are not listed, because nothing was left to
+    /// identify them by. Consumers reload whatever they derived from the graph.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resync_required: bool,
+}
+
+impl GraphChanges {
+    /// Creates an empty change set with the current timestamp
+    pub fn new() -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes_created.is_empty()
+            && self.nodes_updated.is_empty()
+            && self.nodes_deleted.is_empty()
+            && self.relationships_created.is_empty()
+            && self.relationships_updated.is_empty()
+            && self.relationships_deleted.is_empty()
+            && !self.resync_required
+    }
+
+    /// The delta a canvas showing `view_node_id` applies for these changes.
+    /// Canvases do not track relationship properties, so updated relationships
+    /// are left out.
+    pub fn delta_for(&self, view_node_id: &str) -> GraphDelta {
+        let mut delta = GraphDelta::new(view_node_id.to_string());
+        delta.nodes_created = self.nodes_created.clone();
+        delta.nodes_updated = self
+            .nodes_updated
+            .iter()
+            .map(|node| NodeUpdate {
+                guid: node.GUID.clone(),
+                properties: node.properties.clone(),
+            })
+            .collect();
+        delta.nodes_deleted = self.nodes_deleted.clone();
+        delta.relationships_created = self.relationships_created.clone();
+        delta.relationships_deleted = self.relationships_deleted.clone();
+        delta
+    }
 }
 
 #[cfg(test)]
@@ -80,7 +233,10 @@ mod tests {
                 guid: "node-123".to_string(),
                 properties: {
                     let mut map = HashMap::new();
-                    map.insert("name".to_string(), Value::String("Updated Name".to_string()));
+                    map.insert(
+                        "name".to_string(),
+                        Value::String("Updated Name".to_string()),
+                    );
                     map
                 },
             }],
@@ -112,4 +268,63 @@ mod tests {
         assert_eq!(json["guid"], "test-guid");
         assert_eq!(json["properties"]["foo"], "bar");
     }
+
+    fn created_node(guid: &str) -> CanvasNodeDto {
+        serde_json::from_value(serde_json::json!({
+            "GUID": guid,
+            "labels": ["Class"],
+            "properties": {"name": guid}
+        }))
+        .unwrap()
+    }
+
+    fn update(guid: &str, key: &str, value: &str) -> NodeUpdate {
+        NodeUpdate {
+            guid: guid.to_string(),
+            properties: HashMap::from([(key.to_string(), Value::String(value.to_string()))]),
+        }
+    }
+
+    #[test]
+    fn merge_folds_updates_of_the_same_node() {
+        let mut delta = GraphDelta::new("view".to_string());
+        delta.nodes_updated = vec![update("a", "name", "first"), update("b", "name", "b")];
+        delta.nodes_created = vec![created_node("c")];
+
+        let mut later = GraphDelta::new("view".to_string());
+        later.nodes_updated = vec![
+            update("a", "name", "second"),
+            update("a", "status", "done"),
+            update("c", "name", "renamed"),
+        ];
+        delta.merge(later);
+
+        assert_eq!(delta.nodes_updated.len(), 2);
+        assert_eq!(delta.nodes_updated[0].properties["name"], "second");
+        assert_eq!(delta.nodes_updated[0].properties["status"], "done");
+        assert_eq!(delta.nodes_created[0].properties["name"], "renamed");
+    }
+
+    #[test]
+    fn merge_collapses_create_then_delete() {
+        let mut delta = GraphDelta::new("view".to_string());
+        delta.nodes_created = vec![created_node("new")];
+        delta.nodes_updated = vec![update("old", "name", "x")];
+
+        let mut later = GraphDelta::new("view".to_string());
+        later.nodes_deleted = vec!["new".to_string(), "old".to_string()];
+        delta.merge(later);
+
+        assert!(delta.nodes_created.is_empty());
+        assert!(delta.nodes_updated.is_empty());
+        assert_eq!(delta.nodes_deleted, vec!["old".to_string()]);
+
+        // Deleting and re-creating keeps both, the canvas deletes before creating
+        let mut later = GraphDelta::new("view".to_string());
+        later.nodes_created = vec![created_node("old")];
+        delta.merge(later);
+
+        assert_eq!(delta.nodes_deleted, vec!["old".to_string()]);
+        assert_eq!(delta.nodes_created.len(), 1);
+    }
 }
@@ -1,11 +1,16 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::{Path, State}, http::StatusCode, response::IntoResponse, Extension, Json};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::database::neo4j_gateway::GatewayError;
-use crate::graph_events::try_emit_delta;
+use crate::database::neo4j_gateway::{GatewayError, GatewayStatement, QuerySummary, QueryType};
+use crate::database::plan::{PlanMode, QueryPlan};
+use crate::database::schema::GraphSchema;
+use crate::database::stored_queries::StoredQueryDefinition;
+use crate::graph_events::{compute_delta, prepare_delta, publish_delta};
+use crate::middleware::auth::AuthUser;
+use crate::security_logging::SecurityLogger;
 use crate::state::AppState;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 /// Unified request structure for all Cypher queries
@@ -17,6 +22,9 @@ pub struct UnifiedCypherRequest {
     /// Optional ViewNode ID for graph delta emission (feature-flagged)
     #[serde(default)]
     pub view_node_id: Option<String>,
+    /// Run the query with `EXPLAIN` or `PROFILE` and return its plan
+    #[serde(default)]
+    pub plan: Option<PlanMode>,
 }
 
 /// Unified response structure for all Cypher results
@@ -28,13 +36,63 @@ pub struct UnifiedCypherResponse {
     pub execution_time_ms: u64,
     pub query: String,
     pub rows_returned: usize,
+    /// Counters, notifications and plan reported by Neo4j for a completed query
+    pub summary: Option<QuerySummary>,
+    /// Neo4j status code of a failed query, e.g. `Neo.ClientError.Statement.SyntaxError`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// Operator tree of an `EXPLAIN` or `PROFILE` query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<QueryPlan>,
+}
+
+/// Request for a stored query, which is invoked by id instead of shipping Cypher
+#[derive(Debug, Deserialize)]
+pub struct StoredCypherRequest {
+    #[serde(default)]
+    pub parameters: HashMap<String, serde_json::Value>,
+    /// Optional ViewNode ID for graph delta emission (feature-flagged)
+    #[serde(default)]
+    pub view_node_id: Option<String>,
+    /// Run the query with `EXPLAIN` or `PROFILE` and return its plan
+    #[serde(default)]
+    pub plan: Option<PlanMode>,
+}
+
+/// Request for running several Cypher statements atomically
+#[derive(Debug, Deserialize)]
+pub struct UnifiedCypherTransactionRequest {
+    pub statements: Vec<GatewayStatement>,
+    /// Optional ViewNode ID for graph delta emission (feature-flagged)
+    #[serde(default)]
+    pub view_node_id: Option<String>,
+}
+
+/// Result of one statement within a committed transaction
+#[derive(Debug, Serialize)]
+pub struct UnifiedCypherStatementResult {
+    pub query: String,
+    pub data: serde_json::Value,
+    pub rows_returned: usize,
+    pub summary: QuerySummary,
+}
+
+/// Response for a multi-statement transaction; `results` is empty unless it committed
+#[derive(Debug, Serialize)]
+pub struct UnifiedCypherTransactionResponse {
+    pub success: bool,
+    pub message: String,
+    pub results: Vec<UnifiedCypherStatementResult>,
+    pub execution_time_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
 }
 
 const SKIP_FIELDS: &[&str] = &["elementId", "element_id", "neo4jId", "neo4j_id", "identity", "startNodeId", "endNodeId"];
 
 /// Transform raw Neo4j response into standardized graph format
 /// Fully dynamic - passes through all fields except internal Neo4j IDs
-fn transform_to_graph_format(raw_response: &serde_json::Value) -> serde_json::Value {
+pub(crate) fn transform_to_graph_format(raw_response: &serde_json::Value) -> serde_json::Value {
     let mut nodes_map: HashMap<String, serde_json::Value> = HashMap::new();
     let mut edges_map: HashMap<String, serde_json::Value> = HashMap::new();
 
@@ -128,8 +186,11 @@ fn extract_graph_elements_dynamic(
 /// Replaces: /v0/cypher/run, /v0/cypher/public, /v0/glen/cypher
 pub async fn execute_unified_cypher(
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
     Json(request): Json<UnifiedCypherRequest>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    reject_adhoc_cypher(&state, &user, "/v0/cypher/unified")?;
+
     // Basic validation
     if request.query.trim().is_empty() {
         return Ok(Json(UnifiedCypherResponse {
@@ -139,9 +200,33 @@ pub async fn execute_unified_cypher(
             execution_time_ms: 0,
             query: request.query,
             rows_returned: 0,
+            summary: None,
+            error_code: None,
+            plan: None,
         }));
     }
 
+    // EXPLAIN only plans the query, PROFILE runs it and measures every operator
+    let cypher = match request.plan {
+        Some(mode) => match mode.apply(&request.query) {
+            Some(cypher) => cypher,
+            None => {
+                return Ok(Json(UnifiedCypherResponse {
+                    success: false,
+                    message: "Query already starts with EXPLAIN or PROFILE".to_string(),
+                    data: None,
+                    execution_time_ms: 0,
+                    query: request.query,
+                    rows_returned: 0,
+                    summary: None,
+                    error_code: None,
+                    plan: None,
+                }));
+            }
+        },
+        None => request.query.clone(),
+    };
+
     let query_id = Uuid::new_v4().to_string();
 
     // Extract trace_id from parameters if present for latency tracking
@@ -166,15 +251,17 @@ pub async fn execute_unified_cypher(
         "Executing unified Cypher request"
     );
 
-    // Log the query being sent to Neo4j for debugging
-    warn!(
-        target: "kalisi_gateway::handlers::cypher_unified",
-        "📤 Query sent to Neo4j:\n{}\nParameters: {:?}", request.query, request.parameters
-    );
+    // An explained query changes nothing
+    let view_node_id = request
+        .view_node_id
+        .clone()
+        .filter(|_| request.plan != Some(PlanMode::Explain));
+    let neo4j = state.neo4j.for_user(user.user_id.to_string());
+    let access = state.config.cypher_roles.access_for(&user.role);
+    let capture = prepare_delta(&state.config.graph_deltas, &neo4j, view_node_id, &[(cypher.as_str(), &request.parameters)], access).await;
 
-    match state
-        .neo4j
-        .execute(&query_id, &request.query, &request.parameters)
+    match neo4j
+        .execute(&query_id, &cypher, &request.parameters, access)
         .await
     {
         Ok(result) => {
@@ -186,16 +273,12 @@ pub async fn execute_unified_cypher(
                     .as_millis();
                 warn!("[TIMING:{}:T2:{}] Neo4j response received", trace_id, t2);
             }
-            // Attempt to emit graph delta if this was a write operation
-            // Production implementation: pass actual Neo4j result data
+            // Emit a graph delta if this was a write operation
+            if let Some(pending) =
+                compute_delta(&neo4j, capture, std::slice::from_ref(&result)).await
             {
                 let mut publisher = state.graph_delta_publisher.lock().await;
-                if let Some(_delta) = try_emit_delta(
-                    &mut publisher,
-                    request.view_node_id.clone(),
-                    &request.query,
-                    &result.raw_response
-                ).await {
+                if let Some(_delta) = publish_delta(&mut publisher, pending).await {
                     // [TIMING T3] Delta published to Redis stream
                     if !trace_id.is_empty() {
                         let t3 = std::time::SystemTime::now()
@@ -215,14 +298,13 @@ pub async fn execute_unified_cypher(
             // Transform raw response to standardized graph format
             let graph_data = transform_to_graph_format(&result.raw_response);
 
-            // Log the response for debugging
-            let json_response = serde_json::to_string_pretty(&graph_data).unwrap_or_else(|_| "Failed to serialize".to_string());
-            warn!(
+            debug!(
                 target: "kalisi_gateway::handlers::cypher_unified",
                 query_id = %query_id,
                 elapsed_ms = result.metrics.elapsed_ms,
                 rows_returned = result.metrics.result_count,
-                "✅ Query completed - returning response:\n{}", json_response
+                response = %graph_data,
+                "Query completed"
             );
 
             let response = UnifiedCypherResponse {
@@ -235,39 +317,329 @@ pub async fn execute_unified_cypher(
                 execution_time_ms: result.metrics.elapsed_ms,
                 query: request.query.clone(),
                 rows_returned: result.metrics.result_count,
+                plan: result.summary.plan.as_ref().and_then(QueryPlan::from_summary),
+                summary: Some(result.summary),
+                error_code: None,
             };
 
             Ok(Json(response))
         }
+        Err(GatewayError::Forbidden { query_type }) => {
+            log_cypher_denial(&user, query_type, "/v0/cypher/unified");
+            Err(StatusCode::FORBIDDEN)
+        }
+        Err(GatewayError::Busy { .. }) => Err(StatusCode::TOO_MANY_REQUESTS),
         Err(error) => {
-            let message = match error {
-                GatewayError::Connection(reason) => {
-                    error!(
+            let (message, error_code) = describe_error(&query_id, error);
+
+            Ok(Json(UnifiedCypherResponse {
+                success: false,
+                message,
+                data: None,
+                execution_time_ms: 0,
+                query: request.query.clone(),
+                rows_returned: 0,
+                summary: None,
+                error_code,
+                plan: None,
+            }))
+        }
+    }
+}
+
+/// Log a gateway error and turn it into a client message plus Neo4j status code
+fn describe_error(query_id: &str, error: GatewayError) -> (String, Option<String>) {
+    match error {
+        GatewayError::Connection(reason) => {
+            error!(
+                target: "kalisi_gateway::handlers::cypher_unified",
+                query_id = %query_id,
+                %reason,
+                "Neo4j connection failed"
+            );
+            (format!("Neo4j connection failed: {reason}"), None)
+        }
+        GatewayError::Query { code, message } => {
+            error!(
+                target: "kalisi_gateway::handlers::cypher_unified",
+                query_id = %query_id,
+                code = code.as_deref().unwrap_or(""),
+                reason = %message,
+                "Neo4j query error"
+            );
+            (format!("Query failed: {message}"), code)
+        }
+        GatewayError::Forbidden { .. }
+        | GatewayError::Busy { .. }
+        | GatewayError::Unpageable { .. } => (error.to_string(), None),
+    }
+}
+
+/// Record a query rejected by the caller's role policy for the audit trail
+pub(crate) fn log_cypher_denial(user: &AuthUser, query_type: QueryType, resource: &str) {
+    warn!(
+        target: "kalisi_gateway::handlers::cypher_unified",
+        user_id = %user.user_id,
+        role = %user.role,
+        ?query_type,
+        resource,
+        "Cypher query denied by role policy"
+    );
+    SecurityLogger::log_privileged_operation(
+        &user.user_id.to_string(),
+        &format!("cypher:{query_type:?}"),
+        resource,
+        None,
+        false,
+    );
+}
+
+/// Run a list of Cypher statements in a single transaction, retried as a whole
+/// on transient Neo4j failures. Either every statement commits or none does.
+pub async fn execute_cypher_transaction(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(request): Json<UnifiedCypherTransactionRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    reject_adhoc_cypher(&state, &user, "/v0/cypher/transaction")?;
+
+    if request.statements.is_empty() || request.statements.iter().any(|s| s.query.trim().is_empty()) {
+        return Ok(Json(UnifiedCypherTransactionResponse {
+            success: false,
+            message: "Transaction must contain only non-empty statements".to_string(),
+            results: Vec::new(),
+            execution_time_ms: 0,
+            error_code: None,
+        }));
+    }
+
+    let query_id = Uuid::new_v4().to_string();
+
+    info!(
+        target: "kalisi_gateway::handlers::cypher_unified",
+        query_id = %query_id,
+        statements = request.statements.len(),
+        "Executing Cypher transaction"
+    );
+
+    let neo4j = state.neo4j.for_user(user.user_id.to_string());
+    let access = state.config.cypher_roles.access_for(&user.role);
+    let statements: Vec<_> = request.statements.iter().map(|s| (s.query.as_str(), &s.parameters)).collect();
+    let capture = prepare_delta(&state.config.graph_deltas, &neo4j, request.view_node_id.clone(), &statements, access).await;
+
+    let start = std::time::Instant::now();
+    match neo4j
+        .execute_transaction(&query_id, &request.statements, access)
+        .await
+    {
+        Ok(results) => {
+            let execution_time_ms = start.elapsed().as_millis() as u64;
+
+            if let Some(pending) = compute_delta(&neo4j, capture, &results).await {
+                let mut publisher = state.graph_delta_publisher.lock().await;
+                if publish_delta(&mut publisher, pending).await.is_some() {
+                    info!(
                         target: "kalisi_gateway::handlers::cypher_unified",
                         query_id = %query_id,
-                        %reason,
-                        "Neo4j connection failed"
+                        "Graph delta published to Redis stream"
                     );
-                    format!("Neo4j connection failed: {reason}")
                 }
-                GatewayError::Query(reason) => {
-                    error!(
+            }
+
+            let mut statement_results = Vec::with_capacity(results.len());
+            for (statement, result) in request.statements.iter().zip(results) {
+                statement_results.push(UnifiedCypherStatementResult {
+                    query: statement.query.clone(),
+                    data: transform_to_graph_format(&result.raw_response),
+                    rows_returned: result.metrics.result_count,
+                    summary: result.summary,
+                });
+            }
+
+            Ok(Json(UnifiedCypherTransactionResponse {
+                success: true,
+                message: format!(
+                    "Transaction with {} statements committed in {}ms",
+                    statement_results.len(),
+                    execution_time_ms
+                ),
+                results: statement_results,
+                execution_time_ms,
+                error_code: None,
+            }))
+        }
+        Err(GatewayError::Forbidden { query_type }) => {
+            log_cypher_denial(&user, query_type, "/v0/cypher/transaction");
+            Err(StatusCode::FORBIDDEN)
+        }
+        Err(GatewayError::Busy { .. }) => Err(StatusCode::TOO_MANY_REQUESTS),
+        Err(error) => {
+            let (message, error_code) = describe_error(&query_id, error);
+
+            Ok(Json(UnifiedCypherTransactionResponse {
+                success: false,
+                message,
+                results: Vec::new(),
+                execution_time_ms: start.elapsed().as_millis() as u64,
+                error_code,
+            }))
+        }
+    }
+}
+
+/// Outside development only stored queries may run; raw Cypher from clients is refused
+pub(crate) fn reject_adhoc_cypher(state: &AppState, user: &AuthUser, resource: &str) -> Result<(), StatusCode> {
+    if state.config.allow_adhoc_cypher {
+        return Ok(());
+    }
+
+    warn!(
+        target: "kalisi_gateway::handlers::cypher_unified",
+        user_id = %user.user_id,
+        resource,
+        "Ad-hoc Cypher rejected, only stored queries are allowed"
+    );
+    SecurityLogger::log_privileged_operation(
+        &user.user_id.to_string(),
+        "cypher:adhoc",
+        resource,
+        None,
+        false,
+    );
+    Err(StatusCode::FORBIDDEN)
+}
+
+/// List the stored queries the frontend may invoke, with their parameter schemas
+pub async fn list_stored_queries(State(state): State<AppState>) -> Json<Vec<StoredQueryDefinition>> {
+    Json(state.stored_queries.definitions().into_iter().cloned().collect())
+}
+
+/// Labels, relationship types, properties, constraints and indexes of the graph,
+/// for autocompleting and validating Cypher in the query builder and agents
+pub async fn get_graph_schema(State(state): State<AppState>) -> Result<Json<GraphSchema>, StatusCode> {
+    match state.schema_cache.get(&state.neo4j).await {
+        Ok(schema) => Ok(Json(GraphSchema::clone(&schema))),
+        Err(e) => {
+            error!("Failed to load graph schema: {}", e);
+            Err(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+/// Run a stored query by id after validating the parameters against its schema
+pub async fn execute_stored_cypher(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(request): Json<StoredCypherRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let stored = state.stored_queries.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let parameters = match stored.definition.bind(request.parameters) {
+        Ok(parameters) => parameters,
+        Err(reason) => {
+            return Ok(Json(UnifiedCypherResponse {
+                success: false,
+                message: format!("Invalid parameters for {id}: {reason}"),
+                data: None,
+                execution_time_ms: 0,
+                query: id,
+                rows_returned: 0,
+                summary: None,
+                error_code: None,
+                plan: None,
+            }));
+        }
+    };
+
+    let prepared = match request.plan {
+        Some(mode) => match mode.apply(stored.prepared.query()) {
+            Some(cypher) => stored.definition.prepare(cypher),
+            None => {
+                return Ok(Json(UnifiedCypherResponse {
+                    success: false,
+                    message: format!("Stored query {id} already starts with EXPLAIN or PROFILE"),
+                    data: None,
+                    execution_time_ms: 0,
+                    query: id,
+                    rows_returned: 0,
+                    summary: None,
+                    error_code: None,
+                    plan: None,
+                }));
+            }
+        },
+        None => stored.prepared.clone(),
+    };
+
+    let query_id = Uuid::new_v4().to_string();
+
+    info!(
+        target: "kalisi_gateway::handlers::cypher_unified",
+        query_id = %query_id,
+        stored_query = %id,
+        parameters = ?parameters,
+        "Executing stored Cypher query"
+    );
+
+    let view_node_id = request
+        .view_node_id
+        .filter(|_| request.plan != Some(PlanMode::Explain));
+    let neo4j = state.neo4j.for_user(user.user_id.to_string());
+    let access = state.config.cypher_roles.access_for(&user.role);
+    let capture = prepare_delta(&state.config.graph_deltas, &neo4j, view_node_id, &[(prepared.query(), &parameters)], access).await;
+
+    match neo4j
+        .execute_prepared(&query_id, &prepared, &parameters, access)
+        .await
+    {
+        Ok(result) => {
+            if let Some(pending) =
+                compute_delta(&neo4j, capture, std::slice::from_ref(&result)).await
+            {
+                let mut publisher = state.graph_delta_publisher.lock().await;
+                if publish_delta(&mut publisher, pending).await.is_some() {
+                    info!(
                         target: "kalisi_gateway::handlers::cypher_unified",
                         query_id = %query_id,
-                        %reason,
-                        "Neo4j query error"
+                        "Graph delta published to Redis stream"
                     );
-                    format!("Query failed: {reason}")
                 }
-            };
+            }
+
+            Ok(Json(UnifiedCypherResponse {
+                success: true,
+                message: format!(
+                    "Query executed successfully in {}ms",
+                    result.metrics.elapsed_ms
+                ),
+                data: Some(transform_to_graph_format(&result.raw_response)),
+                execution_time_ms: result.metrics.elapsed_ms,
+                query: id,
+                rows_returned: result.metrics.result_count,
+                plan: result.summary.plan.as_ref().and_then(QueryPlan::from_summary),
+                summary: Some(result.summary),
+                error_code: None,
+            }))
+        }
+        Err(GatewayError::Forbidden { query_type }) => {
+            log_cypher_denial(&user, query_type, &format!("/v0/cypher/stored/{id}"));
+            Err(StatusCode::FORBIDDEN)
+        }
+        Err(GatewayError::Busy { .. }) => Err(StatusCode::TOO_MANY_REQUESTS),
+        Err(error) => {
+            let (message, error_code) = describe_error(&query_id, error);
 
             Ok(Json(UnifiedCypherResponse {
                 success: false,
                 message,
                 data: None,
                 execution_time_ms: 0,
-                query: request.query.clone(),
+                query: id,
                 rows_returned: 0,
+                summary: None,
+                error_code,
+                plan: None,
             }))
         }
     }
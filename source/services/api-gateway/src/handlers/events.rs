@@ -1,17 +1,22 @@
+use axum::response::sse::Event;
 use axum::{
-    extract::State,
-    response::{IntoResponse, Response, Sse},
-    http::{header, StatusCode},
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Sse},
 };
-use axum::response::sse::{Event, KeepAlive};
-use serde::{Serialize};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
 use std::convert::Infallible;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio_stream::{Stream, StreamExt};
-use tracing::{info, error};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
 
+use crate::graph_events::{DeltaDispatcher, DeltaSubscription, GraphChanges};
 use crate::state::AppState;
 
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
 /// SSE event types for Neo4j changes
 #[derive(Debug, Serialize, Clone)]
 #[serde(tag = "type")]
@@ -19,14 +24,17 @@ pub enum DomainEvent {
     #[serde(rename = "nodeChanged")]
     NodeChanged {
         id: String,
+        labels: Vec<String>,
         timestamp: String,
         changes: Vec<String>,
     },
     #[serde(rename = "relChanged")]
     RelChanged {
         id: String,
-        from_id: String,
-        to_id: String,
+        /// Unknown for deleted relationships
+        rel_type: Option<String>,
+        from_id: Option<String>,
+        to_id: Option<String>,
         timestamp: String,
         changes: Vec<String>,
     },
@@ -35,70 +43,384 @@ pub enum DomainEvent {
         timestamp: String,
         active_connections: usize,
     },
+    /// Events were missed, consumers have to reload whatever they derived from them
+    #[serde(rename = "resyncRequired")]
+    ResyncRequired { timestamp: String },
+}
+
+/// Query parameters of `/events`
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// Comma separated node labels or relationship types
+    #[serde(default)]
+    pub labels: Option<String>,
+    /// Comma separated GUIDs; relationships also match by their end nodes
+    #[serde(default)]
+    pub guids: Option<String>,
+}
+
+/// Which events a consumer wants. Deleted entities carry no labels, so a label
+/// filter never matches them; filter by GUID to follow deletions.
+#[derive(Debug, Default)]
+struct EventFilter {
+    labels: HashSet<String>,
+    guids: HashSet<String>,
+}
+
+impl EventFilter {
+    fn from_query(query: &EventsQuery) -> Self {
+        fn split(value: &Option<String>) -> HashSet<String> {
+            value
+                .iter()
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        }
+
+        Self {
+            labels: split(&query.labels),
+            guids: split(&query.guids),
+        }
+    }
+
+    fn matches(&self, event: &DomainEvent) -> bool {
+        let (ids, labels): (Vec<&String>, Vec<&String>) = match event {
+            DomainEvent::NodeChanged { id, labels, .. } => (vec![id], labels.iter().collect()),
+            DomainEvent::RelChanged {
+                id,
+                rel_type,
+                from_id,
+                to_id,
+                ..
+            } => (
+                std::iter::once(id).chain(from_id).chain(to_id).collect(),
+                rel_type.iter().collect(),
+            ),
+            _ => return true,
+        };
+
+        (self.labels.is_empty() || labels.iter().any(|label| self.labels.contains(*label)))
+            && (self.guids.is_empty() || ids.iter().any(|id| self.guids.contains(*id)))
+    }
 }
 
 /// SSE stream for real-time domain events (FR-023)
+///
+/// Events come from the `graph:delta` feed. Browsers resume after the last event
+/// they received through `Last-Event-ID`.
 pub async fn events_stream(
     State(state): State<AppState>,
+    Query(query): Query<EventsQuery>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, StatusCode> {
-    info!("Starting SSE events stream for domain updates");
-
-    let stream = create_event_stream(state);
-    
-    Ok(Sse::new(stream)
-        .keep_alive(
-            KeepAlive::new()
-                .interval(Duration::from_secs(30))
-                .text("heartbeat")
-        ))
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok());
+    info!(
+        last_event_id = ?last_event_id,
+        "Starting SSE events stream for domain updates"
+    );
+
+    let dispatcher = state.graph_delta_dispatcher.clone();
+    let subscription = dispatcher
+        .subscribe_feed(last_event_id)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to subscribe to graph changes");
+            StatusCode::BAD_GATEWAY
+        })?;
+
+    let stream = create_event_stream(dispatcher, subscription, EventFilter::from_query(&query));
+
+    // The feed sends its own typed heartbeat, which also keeps the connection alive
+    Ok(Sse::new(stream))
 }
 
-/// Create the event stream
-fn create_event_stream(
-    _state: AppState,
-) -> impl Stream<Item = Result<Event, Infallible>> {
-    // For v0 implementation, simulate events with periodic heartbeats
-    // Future: Replace with real Neo4j change detection
-    
-    tokio_stream::wrappers::IntervalStream::new(
-        tokio::time::interval(Duration::from_secs(10))
-    )
-    .map(move |_| {
-        let event = DomainEvent::Heartbeat {
-            timestamp: chrono::Utc::now().to_rfc3339(),
-            active_connections: 1, // Mock value
-        };
-        
-        let event_json = match serde_json::to_string(&event) {
-            Ok(json) => json,
+struct EventFeed {
+    dispatcher: Arc<DeltaDispatcher>,
+    subscription: DeltaSubscription,
+    filter: EventFilter,
+    pending: VecDeque<Event>,
+    heartbeat: tokio::time::Interval,
+}
+
+impl EventFeed {
+    /// Queue the events of one feed entry that pass the filter. Only the last one
+    /// carries the entry ID, so a consumer that drops out halfway through an entry
+    /// gets all of it again.
+    fn queue_entry(&mut self, stream_id: &str, payload: &str) {
+        let changes = match serde_json::from_str::<GraphChanges>(payload) {
+            Ok(changes) => changes,
             Err(e) => {
-                error!(error = %e, "Failed to serialize SSE event");
-                r#"{"type":"heartbeat","timestamp":"2025-08-26T00:00:00Z","active_connections":0}"#.to_string()
+                warn!(error = %e, stream_id, "Skipping malformed graph changes");
+                return;
             }
         };
 
-        Ok(Event::default()
-            .event("domain-update")
-            .data(event_json))
+        let events: Vec<_> = domain_events(&changes)
+            .into_iter()
+            .filter(|event| self.filter.matches(event))
+            .collect();
+        let count = events.len();
+        for (index, event) in events.iter().enumerate() {
+            if let Some(sse_event) = sse_event(event) {
+                let sse_event = if index + 1 == count {
+                    sse_event.id(stream_id)
+                } else {
+                    sse_event
+                };
+                self.pending.push_back(sse_event);
+            }
+        }
+    }
+
+    fn queue(&mut self, event: &DomainEvent) {
+        if let Some(sse_event) = sse_event(event) {
+            self.pending.push_back(sse_event);
+        }
+    }
+}
+
+/// Create the event stream
+fn create_event_stream(
+    dispatcher: Arc<DeltaDispatcher>,
+    mut subscription: DeltaSubscription,
+    filter: EventFilter,
+) -> impl futures::Stream<Item = Result<Event, Infallible>> {
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.reset();
+
+    let replay = std::mem::take(&mut subscription.replay);
+    let resync_required = subscription.resync_required;
+    let mut feed = EventFeed {
+        dispatcher,
+        subscription,
+        filter,
+        pending: VecDeque::new(),
+        heartbeat,
+    };
+    if resync_required {
+        feed.queue(&resync_event());
+    }
+    for message in replay {
+        feed.queue_entry(&message.stream_id, &message.payload);
+    }
+
+    futures::stream::unfold(feed, |mut feed| async move {
+        loop {
+            if let Some(event) = feed.pending.pop_front() {
+                return Some((Ok(event), feed));
+            }
+
+            tokio::select! {
+                message = feed.subscription.recv() => match message {
+                    Ok(message) => feed.queue_entry(&message.stream_id, &message.payload),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "SSE consumer fell behind the graph changes");
+                        feed.queue(&resync_event());
+                    }
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = feed.heartbeat.tick() => {
+                    let event = DomainEvent::Heartbeat {
+                        timestamp: chrono::Utc::now().to_rfc3339(),
+                        active_connections: feed.dispatcher.feed_subscribers(),
+                    };
+                    feed.queue(&event);
+                }
+            }
+        }
     })
 }
 
-/// Mock function to simulate node change events (for future implementation)
-pub fn emit_node_changed(node_id: &str, changes: Vec<String>) -> DomainEvent {
+fn sse_event(event: &DomainEvent) -> Option<Event> {
+    match serde_json::to_string(event) {
+        Ok(json) => Some(Event::default().event("domain-update").data(json)),
+        Err(e) => {
+            error!(error = %e, "Failed to serialize SSE event");
+            None
+        }
+    }
+}
+
+fn resync_event() -> DomainEvent {
+    DomainEvent::ResyncRequired {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+/// One event per changed node and relationship, and a resync event when some
+/// deletions are not listed
+fn domain_events(changes: &GraphChanges) -> Vec<DomainEvent> {
+    let timestamp = chrono::DateTime::from_timestamp_millis(changes.timestamp)
+        .unwrap_or_else(chrono::Utc::now)
+        .to_rfc3339();
+    let kind = |change: &str| vec![change.to_string()];
+
+    let mut events = Vec::new();
+    if changes.resync_required {
+        events.push(DomainEvent::ResyncRequired {
+            timestamp: timestamp.clone(),
+        });
+    }
+    for (nodes, change) in [
+        (&changes.nodes_created, "created"),
+        (&changes.nodes_updated, "updated"),
+    ] {
+        events.extend(nodes.iter().map(|node| {
+            emit_node_changed(&node.GUID, node.labels.clone(), &timestamp, kind(change))
+        }));
+    }
+    events.extend(
+        changes
+            .nodes_deleted
+            .iter()
+            .map(|guid| emit_node_changed(guid, Vec::new(), &timestamp, kind("deleted"))),
+    );
+
+    for (relationships, change) in [
+        (&changes.relationships_created, "created"),
+        (&changes.relationships_updated, "updated"),
+    ] {
+        events.extend(relationships.iter().map(|rel| {
+            emit_rel_changed(
+                &rel.GUID,
+                Some(&rel.r#type),
+                Some(&rel.fromGUID),
+                Some(&rel.toGUID),
+                &timestamp,
+                kind(change),
+            )
+        }));
+    }
+    events.extend(
+        changes
+            .relationships_deleted
+            .iter()
+            .map(|guid| emit_rel_changed(guid, None, None, None, &timestamp, kind("deleted"))),
+    );
+
+    events
+}
+
+pub fn emit_node_changed(
+    node_id: &str,
+    labels: Vec<String>,
+    timestamp: &str,
+    changes: Vec<String>,
+) -> DomainEvent {
     DomainEvent::NodeChanged {
         id: node_id.to_string(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
+        labels,
+        timestamp: timestamp.to_string(),
         changes,
     }
 }
 
-/// Mock function to simulate relationship change events (for future implementation)  
-pub fn emit_rel_changed(rel_id: &str, from_id: &str, to_id: &str, changes: Vec<String>) -> DomainEvent {
+pub fn emit_rel_changed(
+    rel_id: &str,
+    rel_type: Option<&str>,
+    from_id: Option<&str>,
+    to_id: Option<&str>,
+    timestamp: &str,
+    changes: Vec<String>,
+) -> DomainEvent {
     DomainEvent::RelChanged {
         id: rel_id.to_string(),
-        from_id: from_id.to_string(),
-        to_id: to_id.to_string(),
-        timestamp: chrono::Utc::now().to_rfc3339(),
+        rel_type: rel_type.map(str::to_string),
+        from_id: from_id.map(str::to_string),
+        to_id: to_id.map(str::to_string),
+        timestamp: timestamp.to_string(),
         changes,
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::dto::{CanvasNodeDto, CanvasRelationshipDto};
+    use std::collections::HashMap;
+
+    fn changes() -> GraphChanges {
+        GraphChanges {
+            timestamp: 1_734_982_292_000,
+            nodes_created: vec![CanvasNodeDto {
+                GUID: "class-1".to_string(),
+                labels: vec!["Class".to_string()],
+                parent_guid: None,
+                position: None,
+                display: None,
+                tags: HashMap::new(),
+                properties: HashMap::new(),
+            }],
+            nodes_deleted: vec!["method-1".to_string()],
+            relationships_created: vec![CanvasRelationshipDto {
+                GUID: "rel-1".to_string(),
+                fromGUID: "package-1".to_string(),
+                toGUID: "class-1".to_string(),
+                r#type: "CONTAINS".to_string(),
+                display: None,
+                properties: HashMap::new(),
+            }],
+            ..GraphChanges::default()
+        }
+    }
+
+    fn matching(query: EventsQuery) -> Vec<String> {
+        let filter = EventFilter::from_query(&query);
+        domain_events(&changes())
+            .into_iter()
+            .filter(|event| filter.matches(event))
+            .map(|event| match event {
+                DomainEvent::NodeChanged { id, .. } | DomainEvent::RelChanged { id, .. } => id,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn converts_changes_to_domain_events() {
+        let events = serde_json::to_value(domain_events(&changes())).unwrap();
+
+        assert_eq!(events[0]["type"], "nodeChanged");
+        assert_eq!(events[0]["changes"][0], "created");
+        assert_eq!(events[0]["timestamp"], "2024-12-23T19:31:32+00:00");
+        assert_eq!(events[1]["id"], "method-1");
+        assert_eq!(events[1]["changes"][0], "deleted");
+        assert_eq!(events[2]["type"], "relChanged");
+        assert_eq!(events[2]["from_id"], "package-1");
+    }
+
+    #[test]
+    fn unlisted_deletions_require_a_resync() {
+        let changes = GraphChanges {
+            resync_required: true,
+            ..changes()
+        };
+        let events = domain_events(&changes);
+
+        assert!(matches!(events[0], DomainEvent::ResyncRequired { .. }));
+        assert_eq!(events.len(), 4);
+    }
+
+    #[test]
+    fn filters_by_label_and_guid() {
+        assert_eq!(matching(EventsQuery::default()).len(), 3);
+        assert_eq!(
+            matching(EventsQuery {
+                labels: Some("Class, CONTAINS".to_string()),
+                guids: None,
+            }),
+            vec!["class-1", "rel-1"]
+        );
+        assert_eq!(
+            matching(EventsQuery {
+                labels: None,
+                guids: Some("package-1,method-1".to_string()),
+            }),
+            vec!["method-1", "rel-1"]
+        );
+    }
+}
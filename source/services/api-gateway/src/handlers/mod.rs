@@ -3,6 +3,7 @@ pub mod auth_v2;
 pub mod chatgpt;
 pub mod csp;
 pub mod cypher_unified;
+pub mod events;
 pub mod logs;
 pub mod mfa_simple;
 pub mod mfa_simple_partial;
@@ -13,6 +14,7 @@ pub mod spa;
 pub mod static_files;
 pub mod templates;
 pub mod user;
+pub mod view_state;
 
 // Re-export commonly used types
 pub use crate::middleware::security_headers::CspNonce;
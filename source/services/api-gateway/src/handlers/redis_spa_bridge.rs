@@ -1,25 +1,72 @@
+use std::collections::HashSet;
+
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Query, State,
     },
+    http::HeaderMap,
     response::Response,
 };
 use futures::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamId, StreamRangeReply, StreamReadReply};
 use redis::AsyncCommands;
 use serde_json::Value;
-use tracing::{debug, error, info};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 use crate::state::AppState;
+use crate::websocket::auth::{self, PendingAuth, WsAuthQuery, WsSession};
+
+const AGENT_RESPONSES: &str = "agent:responses";
+/// Wait before reading the response stream again after a failed read
+const RESPONSE_RETRY_DELAY: Duration = Duration::from_secs(1);
 
 /// Pure SPA Redis bridge - handles direct Redis communication for frontend
-pub async fn redis_spa_bridge(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
-    ws.on_upgrade(|socket| handle_spa_redis_bridge(socket, state))
+pub async fn redis_spa_bridge(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<WsAuthQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let pending = match PendingAuth::from_request(&state, &headers, query).await {
+        Ok(pending) => pending,
+        Err(rejection) => return rejection,
+    };
+
+    ws.on_upgrade(|mut socket| async move {
+        if let Some(session) = pending.complete(&mut socket, &state).await {
+            handle_spa_redis_bridge(socket, state, session).await;
+        }
+    })
+}
+
+/// Whether the user may receive the Redis pub/sub `channel`: the shared UI channels
+/// of the configuration and the user's own `ui:user:<user id>:<name>` channels
+fn may_subscribe_channel(state: &AppState, session: &WsSession, channel: &str) -> bool {
+    channel_permitted(
+        &state.config.spa_bridge_ui_channels,
+        session.user.user_id,
+        channel,
+    )
+}
+
+fn channel_permitted(shared: &[String], user_id: Uuid, channel: &str) -> bool {
+    if shared.iter().any(|shared| shared == channel) {
+        return true;
+    }
+
+    channel
+        .strip_prefix(&format!("ui:user:{user_id}:"))
+        .is_some_and(|name| !name.is_empty() && !name.contains(['*', '?', '[']))
 }
 
 /// Handle SPA Redis bridge WebSocket connection
-async fn handle_spa_redis_bridge(mut socket: WebSocket, _state: AppState) {
-    info!("🔌 SPA Redis bridge connected");
+async fn handle_spa_redis_bridge(mut socket: WebSocket, state: AppState, session: WsSession) {
+    info!("🔌 SPA Redis bridge connected for {}", session.user.email);
 
     // Set up Redis connections
     let redis_url =
@@ -60,104 +107,76 @@ async fn handle_spa_redis_bridge(mut socket: WebSocket, _state: AppState) {
     };
 
     // Set up pub/sub for UI state updates with error handling
-    let mut response_pubsub = match response_client.get_async_pubsub().await {
+    let response_pubsub = match response_client.get_async_pubsub().await {
         Ok(pubsub) => pubsub,
         Err(e) => {
             error!("Failed to create Redis pubsub connection: {}", e);
             return;
         }
     };
-    let _ = response_pubsub.subscribe("ui:logs_panel").await;
-
-    // Create consumer group for reliable stream processing
-    let consumer_group = "spa_bridge_group";
-    let consumer_name = "spa_bridge_consumer";
-
-    // Create consumer group and stream if they don't exist
-    match response_redis
-        .xgroup_create_mkstream::<_, _, _, String>("agent:responses", consumer_group, "0")
-        .await
+    let (mut pubsub_sink, mut pubsub_stream) = response_pubsub.split();
+    let mut ui_channels = HashSet::new();
+    if may_subscribe_channel(&state, &session, "ui:logs_panel")
+        && pubsub_sink.subscribe("ui:logs_panel").await.is_ok()
     {
-        Ok(_) => {
-            info!("✅ Created consumer group: {}", consumer_group);
-        }
+        ui_channels.insert("ui:logs_panel".to_string());
+    }
+
+    // Every bridge reads the whole response stream and forwards only the responses
+    // to its own requests, so users never see each other's agent traffic
+    let last_response_id = match last_stream_id(&mut response_redis, AGENT_RESPONSES).await {
+        Ok(id) => id,
         Err(e) => {
-            if e.to_string().contains("BUSYGROUP") {
-                debug!("✅ Consumer group already exists: {}", consumer_group);
-            } else {
-                error!("Failed to create consumer group: {}", e);
-                return;
-            }
+            error!("Failed to read the end of {}: {}", AGENT_RESPONSES, e);
+            return;
         }
-    }
+    };
+    let (response_tx, mut responses) = mpsc::channel(16);
+    let response_reader = tokio::spawn(read_responses(
+        response_redis,
+        last_response_id,
+        response_tx,
+    ));
+    let mut own_requests: HashSet<String> = HashSet::new();
 
-    info!(
-        "📡 SPA bridge ready with consumer group: {}",
-        consumer_group
-    );
+    info!("📡 SPA bridge ready for {}", session.user.email);
 
-    let mut pubsub_stream = response_pubsub.on_message();
+    // Close the socket once the session expires or is revoked
+    let mut revalidate = auth::revalidate_interval(state.config.ws_session_check_interval);
 
     loop {
         tokio::select! {
-            // Handle Redis streams using consumer group - proper Redis mechanics
-            result = async {
-                redis::cmd("XREADGROUP")
-                    .arg("GROUP")
-                    .arg(consumer_group)
-                    .arg(consumer_name)
-                    .arg("BLOCK")
-                    .arg(0)  // Wait indefinitely until messages arrive
-                    .arg("COUNT")
-                    .arg(10)
-                    .arg("STREAMS")
-                    .arg("agent:responses")
-                    .arg(">")
-                    .query_async::<redis::streams::StreamReadReply>(&mut response_redis)
-                    .await
-            } => {
-                match result {
-                    Ok(streams) => {
-                        for stream in streams.keys {
-                            for entry in stream.ids {
-                                if let Some(redis::Value::BulkString(data)) = entry.map.get("data") {
-                                    if let Ok(json_str) = String::from_utf8(data.clone()) {
-                                        debug!("📤 Forwarding agent response: {}",
-                                            json_str.chars().take(100).collect::<String>());
-
-                                        let ws_message = serde_json::json!({
-                                            "type": "agent_response",
-                                            "channel": "agent:responses",
-                                            "data": json_str
-                                        });
-
-                                        if let Ok(message_text) = serde_json::to_string(&ws_message) {
-                                            if let Err(e) = socket.send(Message::Text(message_text.into())).await {
-                                                // Handle broken pipe gracefully - client disconnected
-                                                if e.to_string().contains("Broken pipe") || e.to_string().contains("Connection reset") {
-                                                    info!("🔌 SPA client disconnected during response forward");
-                                                } else {
-                                                    error!("Failed to forward agent response: {}", e);
-                                                }
-                                                break;
-                                            } else {
-                                                // Acknowledge message processing
-                                                let _: Result<i32, _> = response_redis.xack("agent:responses", consumer_group, &[&entry.id]).await;
-                                                debug!("✅ Response forwarded and acknowledged");
-                                            }
-                                        }
-                                    }
+            // Handle Redis stream of agent responses
+            Some(entry) = responses.recv() => {
+                if let Some(redis::Value::BulkString(data)) = entry.map.get("data") {
+                    if let Ok(json_str) = String::from_utf8(data.clone()) {
+                        let request_id = serde_json::from_str::<Value>(&json_str)
+                            .ok()
+                            .and_then(|response| response["request_id"].as_str().map(str::to_string));
+                        if !request_id.is_some_and(|id| own_requests.remove(&id)) {
+                            continue;
+                        }
+
+                        debug!("📤 Forwarding agent response: {}",
+                            json_str.chars().take(100).collect::<String>());
+
+                        let ws_message = serde_json::json!({
+                            "type": "agent_response",
+                            "channel": AGENT_RESPONSES,
+                            "data": json_str
+                        });
+
+                        if let Ok(message_text) = serde_json::to_string(&ws_message) {
+                            if let Err(e) = socket.send(Message::Text(message_text.into())).await {
+                                // Handle broken pipe gracefully - client disconnected
+                                if e.to_string().contains("Broken pipe") || e.to_string().contains("Connection reset") {
+                                    info!("🔌 SPA client disconnected during response forward");
+                                } else {
+                                    error!("Failed to forward agent response: {}", e);
                                 }
+                                break;
                             }
-                        }
-                    }
-                    Err(e) => {
-                        let error_msg = e.to_string();
-                        if error_msg.contains("NOGROUP") {
-                            // Stream or consumer group doesn't exist yet - this is normal during startup
-                            // Silently continue without logging to prevent spam
-                        } else {
-                            debug!("⏳ No new responses (timeout): {}", e);
+                            debug!("✅ Response forwarded");
                         }
                     }
                 }
@@ -188,6 +207,14 @@ async fn handle_spa_redis_bridge(mut socket: WebSocket, _state: AppState) {
                 }
             }
 
+            _ = revalidate.tick() => {
+                if let Err(reason) = session.revalidate(&state).await {
+                    warn!("Closing SPA Redis bridge of {}: {}", session.user.email, reason);
+                    auth::close_unauthorized(&mut socket, reason).await;
+                    break;
+                }
+            }
+
             // Handle messages from frontend SPA
             Some(msg) = socket.recv() => {
                 match msg {
@@ -198,8 +225,16 @@ async fn handle_spa_redis_bridge(mut socket: WebSocket, _state: AppState) {
                             match spa_msg.get("type").and_then(|t| t.as_str()) {
                                 Some("agent_request") => {
                                     // Forward agent request to Redis with error handling
-                                    if let Some(request_data) = spa_msg.get("data") {
-                                        match serde_json::to_string(request_data) {
+                                    if let Some(mut request_data) = spa_msg.get("data").cloned() {
+                                        // Agents learn who asked; the response is matched by request ID
+                                        if let Some(request) = request_data.as_object_mut() {
+                                            request.insert("user_id".to_string(), Value::String(session.user.user_id.to_string()));
+                                        }
+                                        if let Some(request_id) = request_data["request_id"].as_str() {
+                                            own_requests.insert(request_id.to_string());
+                                        }
+
+                                        match serde_json::to_string(&request_data) {
                                             Ok(request_json) => {
                                                 match request_redis.xadd::<_, _, _, _, String>("agent:requests", "*", &[("data", &request_json)]).await {
                                                     Ok(_) => {
@@ -227,8 +262,22 @@ async fn handle_spa_redis_bridge(mut socket: WebSocket, _state: AppState) {
                                 Some("subscribe_ui") => {
                                     // Handle UI subscription requests
                                     if let Some(channel) = spa_msg.get("channel").and_then(|c| c.as_str()) {
-                                        info!("📡 SPA subscribing to UI channel: {}", channel);
-                                        // Already subscribed to main channels above
+                                        if !may_subscribe_channel(&state, &session, channel) {
+                                            warn!("🚫 {} may not subscribe to UI channel: {}", session.user.email, channel);
+                                            let error_response = serde_json::json!({
+                                                "type": "agent_error",
+                                                "error": format!("Not permitted to subscribe to {}", channel)
+                                            });
+                                            let _ = socket.send(Message::Text(error_response.to_string().into())).await;
+                                        } else if !ui_channels.contains(channel) {
+                                            info!("📡 SPA subscribing to UI channel: {}", channel);
+                                            match pubsub_sink.subscribe(channel).await {
+                                                Ok(()) => {
+                                                    ui_channels.insert(channel.to_string());
+                                                }
+                                                Err(e) => error!("Failed to subscribe to UI channel {}: {}", channel, e),
+                                            }
+                                        }
                                     }
                                 }
                                 _ => {
@@ -253,5 +302,120 @@ async fn handle_spa_redis_bridge(mut socket: WebSocket, _state: AppState) {
         }
     }
 
+    response_reader.abort();
     info!("🔌 SPA Redis bridge disconnected");
 }
+
+/// ID of the last entry of `stream`, `0-0` while it is empty
+async fn last_stream_id(
+    redis: &mut MultiplexedConnection,
+    stream: &str,
+) -> redis::RedisResult<String> {
+    let reply: StreamRangeReply = redis::cmd("XREVRANGE")
+        .arg(stream)
+        .arg("+")
+        .arg("-")
+        .arg("COUNT")
+        .arg(1)
+        .query_async(redis)
+        .await?;
+    Ok(reply
+        .ids
+        .first()
+        .map(|entry| entry.id.clone())
+        .unwrap_or_else(|| "0-0".to_string()))
+}
+
+/// Reads the agent responses after `last_id` into `tx`. A blocking XREAD runs in
+/// a task of its own: dropped halfway by the socket loop's `select!`, it would
+/// lose the entries Redis returns for it.
+async fn read_responses(
+    mut redis: MultiplexedConnection,
+    mut last_id: String,
+    tx: mpsc::Sender<StreamId>,
+) {
+    loop {
+        let reply = redis::cmd("XREAD")
+            .arg("BLOCK")
+            .arg(0) // Wait indefinitely until messages arrive
+            .arg("COUNT")
+            .arg(10)
+            .arg("STREAMS")
+            .arg(AGENT_RESPONSES)
+            .arg(&last_id)
+            .query_async::<StreamReadReply>(&mut redis)
+            .await;
+        match reply {
+            Ok(reply) => {
+                for entry in reply.keys.into_iter().flat_map(|stream| stream.ids) {
+                    last_id = entry.id.clone();
+                    if tx.send(entry).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("Failed to read {}: {}", AGENT_RESPONSES, e);
+                tokio::time::sleep(RESPONSE_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared() -> Vec<String> {
+        vec!["ui:logs_panel".to_string(), "ui:status".to_string()]
+    }
+
+    #[test]
+    fn permits_shared_channels() {
+        let user = Uuid::new_v4();
+
+        assert!(channel_permitted(&shared(), user, "ui:logs_panel"));
+        assert!(channel_permitted(&shared(), user, "ui:status"));
+        assert!(!channel_permitted(&shared(), user, "ui:admin"));
+    }
+
+    #[test]
+    fn permits_only_the_users_own_channels() {
+        let user = Uuid::new_v4();
+        let other = Uuid::new_v4();
+
+        assert!(channel_permitted(
+            &shared(),
+            user,
+            &format!("ui:user:{user}:layout")
+        ));
+        assert!(!channel_permitted(
+            &shared(),
+            user,
+            &format!("ui:user:{user}:")
+        ));
+        assert!(!channel_permitted(
+            &shared(),
+            user,
+            &format!("ui:user:{other}:layout")
+        ));
+        assert!(!channel_permitted(
+            &shared(),
+            user,
+            &format!("ui:user:{user}")
+        ));
+    }
+
+    #[test]
+    fn rejects_channel_patterns() {
+        let user = Uuid::new_v4();
+
+        for pattern in ["*", "ui:*", "ui:user:*", "ui:logs_panel*"] {
+            assert!(!channel_permitted(&shared(), user, pattern), "{pattern}");
+        }
+        for name in ["*", "layout?", "[a-z]"] {
+            let channel = format!("ui:user:{user}:{name}");
+            assert!(!channel_permitted(&shared(), user, &channel), "{channel}");
+        }
+    }
+}
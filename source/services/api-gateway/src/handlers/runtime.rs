@@ -1,31 +1,136 @@
 use std::collections::HashMap;
+use std::convert::Infallible;
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
-use serde::Deserialize;
+use axum::{
+    body::Body,
+    extract::{Query as UrlQuery, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use bytes::Bytes;
+use neo4rs::Query;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
-use tracing::info;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
 
-use crate::{runtime::canvas::build_canvas_response, state::AppState};
+use crate::{
+    database::{
+        access::CypherAccess,
+        neo4j_gateway::{GatewayError, GatewayRowStream},
+    },
+    handlers::{
+        cypher_unified::{log_cypher_denial, reject_adhoc_cypher},
+        view_state::check_view_node_id,
+    },
+    middleware::auth::AuthUser,
+    runtime::{
+        canvas::{build_canvas_response, merge_saved_positions, CanvasHarvester},
+        cursor,
+        display::DisplaySpec,
+        dto::{CanvasStreamEvent, NodePosition, QueryMetadataDto},
+        export::{self, ExportFormat},
+        graph_response::CanonicalGraphResponse,
+        layout::{apply_layout, LayoutMode},
+    },
+    state::AppState,
+    storage::ViewStateStorage,
+};
+
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+const CANVAS_DATA_ENDPOINT: &str = "/runtime/canvas/data";
+const CANVAS_EXPORT_ENDPOINT: &str = "/runtime/canvas/export";
+
+/// Largest page a client may request
+const MAX_PAGE_SIZE: usize = 10_000;
+
+/// Serialized stream events buffered ahead of a slow client
+const STREAM_BUFFER: usize = 64;
 
 #[derive(Debug, Deserialize)]
 pub struct RuntimeGraphRequest {
+    /// Ad-hoc Cypher, only accepted when the gateway allows it
+    #[serde(default)]
     pub query: String,
+    /// Id of a stored query to run instead of `query`
+    #[serde(default)]
+    pub stored_query: Option<String>,
     #[serde(default)]
     pub parameters: HashMap<String, Value>,
     #[serde(default)]
     pub include_raw_rows: bool,
+    /// Rows per page; defaults to the driver fetch size when only `cursor` is given.
+    /// Only read queries can be paged, and pages only line up for queries with a
+    /// complete `ORDER BY`.
+    #[serde(default)]
+    pub page_size: Option<usize>,
+    /// `telemetry_cursor` of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Respond with NDJSON events, also selected by `Accept: application/x-ndjson`
+    #[serde(default)]
+    pub stream: bool,
+    /// ViewNode whose `styleSpec` fills in the display of nodes and edges, and whose
+    /// saved ViewState of the caller places the nodes
+    #[serde(default)]
+    pub view_node_id: Option<String>,
+    #[serde(default)]
+    pub format: ResponseFormat,
+    /// Position and size the nodes server-side; canvas responses only, not streamed
+    #[serde(default)]
+    pub layout: Option<LayoutMode>,
+}
+
+/// What the requested ViewNode adds to the entities of the query
+#[derive(Default)]
+struct ViewPresentation {
+    display: Option<DisplaySpec>,
+    saved_positions: HashMap<String, NodePosition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Canvas DTOs, styled for rendering
+    #[default]
+    Canvas,
+    /// The canonical graph with its containment hierarchy, not available streamed
+    Canonical,
 }
 
 pub async fn fetch_canvas_data(
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
     Json(request): Json<RuntimeGraphRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    if request.query.trim().is_empty() {
+) -> Result<Response, StatusCode> {
+    let stream = request.stream || accepts_ndjson(&headers);
+    if stream && request.format == ResponseFormat::Canonical {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Layout needs the whole graph, and canonical nodes have no position
+    if request.layout.is_some() && (stream || request.format == ResponseFormat::Canonical) {
         return Err(StatusCode::BAD_REQUEST);
     }
+    let (prepared, parameters) = resolve_query(
+        &state,
+        &user,
+        request.stored_query.as_deref(),
+        request.query,
+        request.parameters,
+        CANVAS_DATA_ENDPOINT,
+    )?;
+    let cypher = prepared.query().to_string();
 
-    let query_id = derive_query_id(&request.query, &request.parameters);
+    let query_id = derive_query_id(&cypher, &parameters);
 
     info!(
         target: "kalisi_gateway::handlers::runtime",
@@ -33,24 +138,441 @@ pub async fn fetch_canvas_data(
         "Runtime canvas data request",
     );
 
+    let offset = match request.cursor.as_deref() {
+        Some(value) => cursor::decode(&query_id, value).ok_or(StatusCode::BAD_REQUEST)?,
+        None => 0,
+    };
+    // Pagination is opt-in so that existing callers keep receiving the full graph
+    let page_size = match (request.page_size, &request.cursor) {
+        (Some(size), _) => Some(size.clamp(1, MAX_PAGE_SIZE)),
+        (None, Some(_)) => Some(state.neo4j.fetch_size().min(MAX_PAGE_SIZE)),
+        (None, None) => None,
+    };
+
+    let access = state.config.cypher_roles.access_for(&user.role);
+    let map_error = |error| gateway_status(error, &user, CANVAS_DATA_ENDPOINT);
+    let view = load_view(&state, &user, request.view_node_id.as_deref(), access).await?;
+    let neo4j = state.neo4j.for_user(user.user_id.to_string());
+
+    if stream {
+        let rows = if page_size.is_some() {
+            neo4j
+                .stream_page(&query_id, &prepared, &parameters, access)
+                .await
+        } else {
+            neo4j
+                .stream_prepared(&query_id, &prepared, &parameters, access)
+                .await
+        }
+        .map_err(map_error)?;
+
+        let header = CanvasStreamEvent::Header {
+            query_id: query_id.clone(),
+            cypher,
+            parameters,
+        };
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(pump_canvas_stream(
+            rows, query_id, offset, page_size, view, header, tx,
+        ));
+
+        let body = futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|line| (Ok::<_, Infallible>(line), rx))
+        });
+        return Ok((
+            [(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)],
+            Body::from_stream(body),
+        )
+            .into_response());
+    }
+
     let include_raw =
         request.include_raw_rows || state.config.environment.eq_ignore_ascii_case("development");
 
-    let result = state
-        .neo4j
-        .execute(&query_id, &request.query, &request.parameters)
-        .await
-        .map_err(|_| StatusCode::BAD_GATEWAY)?;
+    let (result, next_cursor) = match page_size {
+        Some(limit) => {
+            let page = neo4j
+                .execute_page(&query_id, &prepared, &parameters, access, offset, limit)
+                .await
+                .map_err(map_error)?;
+            let next_offset = page.offset + page.result.metrics.result_count;
+            let next_cursor = page
+                .has_more
+                .then(|| cursor::encode(&query_id, next_offset));
+            (page.result, next_cursor)
+        }
+        None => {
+            let result = state
+                .result_cache
+                .fetch(&neo4j, &query_id, &prepared, &parameters, access)
+                .await
+                .map_err(map_error)?;
+            (result, None)
+        }
+    };
 
-    let response = build_canvas_response(
+    if request.format == ResponseFormat::Canonical {
+        let mut response = CanonicalGraphResponse::from_gateway_result(
+            query_id,
+            cypher,
+            parameters,
+            &result,
+            include_raw,
+        );
+        response.metadata.telemetry_cursor = next_cursor;
+        return Ok(tagged_response(&headers, &response));
+    }
+
+    let mut response = build_canvas_response(
         query_id,
+        cypher,
+        parameters,
+        result,
+        include_raw,
+        &view.saved_positions,
+    );
+    response.telemetry_cursor = next_cursor;
+    if let Some(display) = &view.display {
+        display.apply(&mut response);
+    }
+    // After the display spec, whose sizes the layout keeps, as it keeps saved positions
+    if let Some(mode) = request.layout {
+        apply_layout(&mut response, mode);
+    }
+
+    Ok(tagged_response(&headers, &response))
+}
+
+/// The canvas graph of a query as a file for other tools, see [`ExportFormat`].
+/// Takes the body of `/runtime/canvas/data` and always exports the whole graph.
+/// SVG needs positions, so nodes without one get the containment layout unless
+/// the request asks for another.
+pub async fn export_canvas(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    UrlQuery(params): UrlQuery<ExportParams>,
+    Json(request): Json<RuntimeGraphRequest>,
+) -> Result<Response, StatusCode> {
+    let paged = request.page_size.is_some() || request.cursor.is_some();
+    if request.stream || paged || request.format != ResponseFormat::Canvas {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let (prepared, parameters) = resolve_query(
+        &state,
+        &user,
+        request.stored_query.as_deref(),
         request.query,
         request.parameters,
+        CANVAS_EXPORT_ENDPOINT,
+    )?;
+    let cypher = prepared.query().to_string();
+    let query_id = derive_query_id(&cypher, &parameters);
+
+    info!(
+        target: "kalisi_gateway::handlers::runtime",
+        query_id = %query_id,
+        format = ?params.format,
+        "Runtime canvas export request",
+    );
+
+    let access = state.config.cypher_roles.access_for(&user.role);
+    let view = load_view(&state, &user, request.view_node_id.as_deref(), access).await?;
+    let neo4j = state.neo4j.for_user(user.user_id.to_string());
+    let result = state
+        .result_cache
+        .fetch(&neo4j, &query_id, &prepared, &parameters, access)
+        .await
+        .map_err(|error| gateway_status(error, &user, CANVAS_EXPORT_ENDPOINT))?;
+
+    let mut graph = build_canvas_response(
+        query_id,
+        cypher,
+        parameters,
         result,
-        include_raw,
+        false,
+        &view.saved_positions,
     );
+    if let Some(display) = &view.display {
+        display.apply(&mut graph);
+    }
+    let layout = request
+        .layout
+        .or((params.format == ExportFormat::Svg).then_some(LayoutMode::Containment));
+    if let Some(mode) = layout {
+        apply_layout(&mut graph, mode);
+    }
+
+    let filename = format!(
+        "attachment; filename=\"canvas-{}.{}\"",
+        &graph.query_id[..graph.query_id.len().min(12)],
+        params.format.extension()
+    );
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                params.format.content_type().to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        export::render(&graph, params.format),
+    )
+        .into_response())
+}
+
+/// The stored query named by the request with its bound parameters, or the
+/// ad-hoc Cypher where the gateway accepts it
+fn resolve_query(
+    state: &AppState,
+    user: &AuthUser,
+    stored_query: Option<&str>,
+    query: String,
+    parameters: HashMap<String, Value>,
+    endpoint: &str,
+) -> Result<(Query, HashMap<String, Value>), StatusCode> {
+    match stored_query {
+        Some(id) => {
+            let stored = state.stored_queries.get(id).ok_or(StatusCode::NOT_FOUND)?;
+            let parameters = stored.definition.bind(parameters).map_err(|reason| {
+                warn!(
+                    target: "kalisi_gateway::handlers::runtime",
+                    stored_query = id,
+                    %reason,
+                    "Invalid stored query parameters",
+                );
+                StatusCode::BAD_REQUEST
+            })?;
+            Ok((stored.prepared.clone(), parameters))
+        }
+        None => {
+            reject_adhoc_cypher(state, user, endpoint)?;
+            if query.trim().is_empty() {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            Ok((Query::new(query), parameters))
+        }
+    }
+}
+
+fn gateway_status(error: GatewayError, user: &AuthUser, endpoint: &str) -> StatusCode {
+    match error {
+        GatewayError::Forbidden { query_type } => {
+            log_cypher_denial(user, query_type, endpoint);
+            StatusCode::FORBIDDEN
+        }
+        GatewayError::Busy { .. } => StatusCode::TOO_MANY_REQUESTS,
+        GatewayError::Unpageable { .. } => StatusCode::BAD_REQUEST,
+        error if error.is_timeout() => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// Style spec and saved positions of the ViewNode; either one failing to load
+/// only costs the presentation, not the graph. A malformed ViewNode ID is a
+/// `400 Bad Request`.
+async fn load_view(
+    state: &AppState,
+    user: &AuthUser,
+    view_node_id: Option<&str>,
+    access: CypherAccess,
+) -> Result<ViewPresentation, StatusCode> {
+    let Some(view_node_id) = view_node_id else {
+        return Ok(ViewPresentation::default());
+    };
+    check_view_node_id(view_node_id)?;
+
+    let display = match DisplaySpec::load(&state.neo4j, view_node_id, access).await {
+        Ok(spec) => spec,
+        Err(error) => {
+            warn!(
+                target: "kalisi_gateway::handlers::runtime",
+                view_node_id,
+                %error,
+                "Failed to load ViewNode style spec",
+            );
+            None
+        }
+    };
+    let mut storage = ViewStateStorage::new(state.redis.clone());
+    let saved_positions = match storage.load(user.user_id, view_node_id).await {
+        Ok(view_state) => view_state.positions,
+        Err(error) => {
+            warn!(
+                target: "kalisi_gateway::handlers::runtime",
+                view_node_id,
+                %error,
+                "Failed to load ViewState",
+            );
+            HashMap::new()
+        }
+    };
+
+    Ok(ViewPresentation {
+        display,
+        saved_positions,
+    })
+}
+
+/// JSON response with an ETag over its body, or `304 Not Modified` when the
+/// client's `If-None-Match` already names it. The tag leaves out the query's
+/// elapsed time, which differs between executions of an unchanged result.
+fn tagged_response<T: Serialize>(headers: &HeaderMap, body: &T) -> Response {
+    let mut value = match serde_json::to_value(body) {
+        Ok(value) => value,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let elapsed_ms = value
+        .get_mut("metadata")
+        .and_then(Value::as_object_mut)
+        .and_then(|metadata| metadata.remove("elapsed_ms"));
+    let etag = format!("\"{:x}\"", Sha256::digest(value.to_string()));
+    if let Some(elapsed_ms) = elapsed_ms {
+        value["metadata"]["elapsed_ms"] = elapsed_ms;
+    }
+
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "private, no-cache".to_string()),
+    ];
+    if matches_etag(headers, &etag) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (cache_headers, Json(value)).into_response()
+}
+
+fn matches_etag(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim())
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+fn accepts_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains(NDJSON_CONTENT_TYPE))
+}
+
+/// Forward canvas entities to the client as rows arrive. Once the client goes
+/// away, also while the server is still working on the next rows, the stream is
+/// dropped instead of being drained, which resets its connection and stops the
+/// query on the server.
+async fn pump_canvas_stream(
+    mut rows: GatewayRowStream,
+    query_id: String,
+    offset: usize,
+    page_size: Option<usize>,
+    view: ViewPresentation,
+    header: CanvasStreamEvent,
+    tx: mpsc::Sender<Bytes>,
+) {
+    let mut harvester = CanvasHarvester::default();
+    let mut returned = 0;
+    let mut has_more = false;
+
+    let pump = async {
+        if !send_event(&tx, &header).await {
+            return Ok(false);
+        }
+        rows.skip(offset).await?;
+        loop {
+            if page_size.is_some_and(|limit| returned == limit) {
+                has_more = rows.next_row().await?.is_some();
+                return Ok(true);
+            }
+            let Some(row) = rows.next_row().await? else {
+                return Ok(true);
+            };
+            returned += 1;
+
+            let (mut nodes, mut edges) = harvester.harvest(&row);
+            merge_saved_positions(&mut nodes, &view.saved_positions);
+            if let Some(display) = &view.display {
+                nodes
+                    .iter_mut()
+                    .for_each(|node| display.apply_to_node(node));
+                edges
+                    .iter_mut()
+                    .for_each(|edge| display.apply_to_edge(edge));
+            }
+            let events = nodes
+                .into_iter()
+                .map(CanvasStreamEvent::Node)
+                .chain(edges.into_iter().map(CanvasStreamEvent::Edge));
+            for event in events {
+                if !send_event(&tx, &event).await {
+                    return Ok(false);
+                }
+            }
+        }
+    };
+    let outcome: Result<bool, GatewayError> = tokio::select! {
+        outcome = pump => outcome,
+        _ = tx.closed() => Ok(false),
+    };
+
+    let event = match outcome {
+        Ok(false) => {
+            info!(
+                target: "kalisi_gateway::handlers::runtime",
+                query_id = %query_id,
+                rows_sent = returned,
+                "Canvas stream closed by client"
+            );
+            return;
+        }
+        Ok(true) => {
+            let elapsed_ms = rows.elapsed_ms();
+            match rows.finish(Vec::new()).await {
+                Ok(_) => CanvasStreamEvent::End {
+                    metadata: QueryMetadataDto {
+                        elapsed_ms,
+                        rows_returned: returned,
+                    },
+                    telemetry_cursor: has_more
+                        .then(|| cursor::encode(&query_id, offset + returned)),
+                },
+                Err(error) => stream_error(error),
+            }
+        }
+        Err(error) => stream_error(error),
+    };
+
+    send_event(&tx, &event).await;
+}
+
+fn stream_error(error: GatewayError) -> CanvasStreamEvent {
+    match error {
+        GatewayError::Query { code, message } => CanvasStreamEvent::Error {
+            message: format!("Query failed: {message}"),
+            error_code: code,
+        },
+        other => CanvasStreamEvent::Error {
+            message: other.to_string(),
+            error_code: None,
+        },
+    }
+}
 
-    Ok(Json(response))
+/// Send one NDJSON line, returning false once the client is gone.
+async fn send_event(tx: &mpsc::Sender<Bytes>, event: &CanvasStreamEvent) -> bool {
+    let mut line = match serde_json::to_vec(event) {
+        Ok(line) => line,
+        Err(error) => {
+            warn!(
+                target: "kalisi_gateway::handlers::runtime",
+                %error,
+                "Failed to serialize canvas stream event"
+            );
+            return true;
+        }
+    };
+    line.push(b'\n');
+    tx.send(Bytes::from(line)).await.is_ok()
 }
 
 fn derive_query_id(cypher: &str, params: &HashMap<String, Value>) -> String {
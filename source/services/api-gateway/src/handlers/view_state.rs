@@ -0,0 +1,93 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use tracing::{error, info};
+
+use crate::{
+    middleware::auth::AuthUser,
+    state::AppState,
+    storage::{
+        view_state::{is_view_node_id, SaveOutcome, ViewState},
+        ViewStateStorage,
+    },
+};
+
+/// `400 Bad Request` for ViewNode IDs that would leave the namespace of their Redis key
+pub(crate) fn check_view_node_id(view_node_id: &str) -> Result<(), StatusCode> {
+    if is_view_node_id(view_node_id) {
+        Ok(())
+    } else {
+        Err(StatusCode::BAD_REQUEST)
+    }
+}
+
+/// The caller's state of a ViewNode, empty at version 0 when never saved
+pub async fn get_view_state(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(view_node_id): Path<String>,
+) -> Result<Json<ViewState>, StatusCode> {
+    check_view_node_id(&view_node_id)?;
+
+    let mut storage = ViewStateStorage::new(state.redis.clone());
+    storage
+        .load(user.user_id, &view_node_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("Failed to load view state of {}: {}", view_node_id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+/// Replace the caller's state of a ViewNode. `version` has to be the version the
+/// client last loaded; otherwise nothing is saved and the response is
+/// `409 Conflict` with the current state.
+pub async fn save_view_state(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(view_node_id): Path<String>,
+    Json(mut view_state): Json<ViewState>,
+) -> Result<Response, StatusCode> {
+    check_view_node_id(&view_node_id)?;
+    view_state.view_node_id = view_node_id;
+
+    let mut storage = ViewStateStorage::new(state.redis.clone());
+    match storage.save(user.user_id, view_state).await {
+        Ok(SaveOutcome::Saved(saved)) => {
+            info!(
+                "Saved view state of {} for {} at version {}",
+                saved.view_node_id, user.email, saved.version
+            );
+            Ok(Json(saved).into_response())
+        }
+        Ok(SaveOutcome::Conflict(current)) => {
+            Ok((StatusCode::CONFLICT, Json(current)).into_response())
+        }
+        Err(e) => {
+            error!("Failed to save view state: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Forget the caller's state of a ViewNode, back to the query's own layout
+pub async fn delete_view_state(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(view_node_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    check_view_node_id(&view_node_id)?;
+
+    let mut storage = ViewStateStorage::new(state.redis.clone());
+    match storage.delete(user.user_id, &view_node_id).await {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => {
+            error!("Failed to delete view state of {}: {}", view_node_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
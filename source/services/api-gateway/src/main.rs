@@ -23,6 +23,9 @@ mod middleware;
 mod runtime;
 // mod secure_config;
 mod email;
+// Only the privileged-operation audit events are used by the binary
+#[allow(dead_code)]
+mod security_logging;
 mod security_metrics;
 mod state;
 mod static_files;
@@ -157,6 +160,18 @@ async fn async_main() -> anyhow::Result<()> {
     let state = AppState::new(config).await?;
     info!("✅ AppState initialized successfully");
 
+    // Publish deltas for writes made outside the gateway (GRAPH_CDC_ENABLED)
+    if graph_events::CdcPoller::new(
+        &state.config.graph_deltas,
+        state.neo4j.clone(),
+        state.graph_delta_publisher.clone(),
+    )
+    .spawn_if_enabled()
+    .is_some()
+    {
+        info!("✅ Graph CDC poller started");
+    }
+
     // Public routes
     #[allow(unused_mut)]
     let mut public_routes = Router::new()
@@ -200,15 +215,6 @@ async fn async_main() -> anyhow::Result<()> {
         .route(
             "/api/v1/chat/gpt",
             post(handlers::chatgpt::handle_chat_request),
-        )
-        // FR-027 Unified Cypher endpoint - THE ONLY Cypher endpoint for entire app
-        .route(
-            "/v0/cypher/unified",
-            post(handlers::cypher_unified::execute_unified_cypher),
-        )
-        .route(
-            "/runtime/canvas/data",
-            post(handlers::runtime::fetch_canvas_data),
         );
 
     // Add development-only routes
@@ -294,6 +300,46 @@ async fn async_main() -> anyhow::Result<()> {
         .route("/v2/user/account", delete(handlers::user::delete_account))
         .route("/v2/user/settings", get(handlers::user::get_settings))
         .route("/v2/user/settings", post(handlers::user::update_settings))
+        // Per-user presentation of a ViewNode: positions, collapsed containers, viewport
+        .route(
+            "/v2/user/views/{view_node_id}/state",
+            get(handlers::view_state::get_view_state)
+                .put(handlers::view_state::save_view_state)
+                .delete(handlers::view_state::delete_view_state),
+        )
+        // FR-027 Unified Cypher endpoint - THE ONLY Cypher endpoint for entire app
+        // Authenticated: the caller's role decides which query types may run
+        .route(
+            "/v0/cypher/unified",
+            post(handlers::cypher_unified::execute_unified_cypher),
+        )
+        .route(
+            "/v0/cypher/transaction",
+            post(handlers::cypher_unified::execute_cypher_transaction),
+        )
+        .route(
+            "/v0/cypher/schema",
+            get(handlers::cypher_unified::get_graph_schema),
+        )
+        .route(
+            "/v0/cypher/stored",
+            get(handlers::cypher_unified::list_stored_queries),
+        )
+        .route(
+            "/v0/cypher/stored/{id}",
+            post(handlers::cypher_unified::execute_stored_cypher),
+        )
+        .route(
+            "/runtime/canvas/data",
+            post(handlers::runtime::fetch_canvas_data),
+        )
+        // The same graph as GraphML, GEXF, DOT, Mermaid or SVG, see runtime::export
+        .route(
+            "/runtime/canvas/export",
+            post(handlers::runtime::export_canvas),
+        )
+        // SSE feed of graph changes for scripts and dashboards
+        .route("/events", get(handlers::events::events_stream))
         // ViewNode functionality uses existing /v0/cypher/unified endpoint (FR-030)
         // Logging API routes (read-only for financial services compliance)
         .route("/api/logs", get(handlers::logs::get_logs))
@@ -6,7 +6,7 @@ use axum::{
     response::Response,
     Json,
 };
-use kalisi_core::types::ApiResponse;
+use kalisi_core::types::{ApiResponse, Claims};
 use uuid::Uuid;
 
 #[derive(Clone, Debug)]
@@ -14,6 +14,7 @@ pub struct AuthUser {
     pub user_id: Uuid,
     pub email: String,
     pub session_id: Uuid,
+    pub role: String,
 }
 
 pub async fn auth_middleware(
@@ -22,8 +23,31 @@ pub async fn auth_middleware(
     mut req: Request<axum::body::Body>,
     next: Next,
 ) -> Result<Response, (StatusCode, Json<ApiResponse<()>>)> {
-    // Try to get token from Authorization header first
-    let token = headers
+    let token = match extract_token(&headers) {
+        Some(t) => t,
+        None => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(ApiResponse::error("Authentication required")),
+            ));
+        }
+    };
+
+    match verify_session(&state, &token).await {
+        Ok(claims) => {
+            // Session is valid, add user info to request extensions
+            req.extensions_mut().insert(AuthUser::from(claims));
+
+            let response = next.run(req).await;
+            Ok(response)
+        }
+        Err(message) => Err((StatusCode::UNAUTHORIZED, Json(ApiResponse::error(message)))),
+    }
+}
+
+/// Bearer token from the Authorization header, falling back to the `token` cookie
+pub fn extract_token(headers: &HeaderMap) -> Option<String> {
+    headers
         .get("authorization")
         .and_then(|auth| auth.to_str().ok())
         .and_then(|auth| auth.strip_prefix("Bearer "))
@@ -43,50 +67,34 @@ pub async fn auth_middleware(
                         }
                     })
                 })
-        });
-
-    let token = match token {
-        Some(t) => t,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(ApiResponse::error("Authentication required")),
-            ));
-        }
-    };
+        })
+}
 
-    // Verify JWT token
-    let claims = match state.jwt_auth.verify_token(&token) {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(ApiResponse::error("Invalid authentication token")),
-            ));
-        }
-    };
+/// Verify the JWT and that its session still exists for the same user. The error
+/// is the message to show the client.
+pub async fn verify_session(state: &AppState, token: &str) -> Result<Claims, &'static str> {
+    let claims = state
+        .jwt_auth
+        .verify_token(token)
+        .map_err(|_| "Invalid authentication token")?;
 
-    // Verify session exists
     let mut session_storage = SessionStorage::new(state.redis.clone());
     match session_storage
         .get_session(&claims.session_id.to_string())
         .await
     {
-        Ok(Some(session)) if session.user_id == claims.sub => {
-            // Session is valid, add user info to request extensions
-            let auth_user = AuthUser {
-                user_id: claims.sub,
-                email: claims.email,
-                session_id: claims.session_id,
-            };
-            req.extensions_mut().insert(auth_user);
+        Ok(Some(session)) if session.user_id == claims.sub => Ok(claims),
+        _ => Err("Session expired or invalid"),
+    }
+}
 
-            let response = next.run(req).await;
-            Ok(response)
+impl From<Claims> for AuthUser {
+    fn from(claims: Claims) -> Self {
+        AuthUser {
+            user_id: claims.sub,
+            email: claims.email,
+            session_id: claims.session_id,
+            role: claims.role,
         }
-        _ => Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ApiResponse::error("Session expired or invalid")),
-        )),
     }
 }
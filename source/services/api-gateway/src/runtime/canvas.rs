@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use chrono::Utc;
 use serde_json::{Map, Value};
+use tracing::debug;
 
 use crate::database::neo4j_gateway::GatewayQueryResult;
 
@@ -9,6 +9,7 @@ use super::dto::{
     BadgeDisplay, CanvasGraphDto, CanvasNodeDto, CanvasRelationshipDto, NodeDisplay, NodePosition,
     QueryMetadataDto, RelationshipDisplay,
 };
+use super::graph_response::{ContainmentHierarchy, PARENT_PROPERTIES};
 
 const SKIP_FIELDS: &[&str] = &["elementId", "element_id", "neo4jId", "neo4j_id", "identity", "startNodeId", "endNodeId"];
 
@@ -18,10 +19,8 @@ pub fn build_canvas_response(
     parameters: HashMap<String, Value>,
     result: GatewayQueryResult,
     _include_raw_rows: bool,
+    saved_positions: &HashMap<String, NodePosition>,
 ) -> CanvasGraphDto {
-    // Log 1: Query sent to database
-    eprintln!("[{}] Query sent to Neo4j:\n{}\n", Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"), cypher);
-
     let rows = result
         .raw_response
         .get("results")
@@ -29,31 +28,82 @@ pub fn build_canvas_response(
         .cloned()
         .unwrap_or_default();
 
-    let (nodes, relationships) = harvest_graph_entities(&rows);
+    let (mut nodes, relationships) = harvest_graph_entities(&rows);
+
+    let hierarchy = ContainmentHierarchy::resolve(
+        nodes.iter().map(|node| (node.GUID.as_str(), node.parent_guid.as_deref())),
+        relationships.iter().map(|edge| (edge.r#type.as_str(), edge.fromGUID.as_str(), edge.toGUID.as_str())),
+    );
+    for node in &mut nodes {
+        node.parent_guid = hierarchy.parent_of(&node.GUID).map(str::to_string);
+    }
+    merge_saved_positions(&mut nodes, saved_positions);
 
     let metadata = QueryMetadataDto {
         elapsed_ms: result.metrics.elapsed_ms,
         rows_returned: result.metrics.result_count,
     };
 
-    let response = CanvasGraphDto {
+    debug!(
+        target: "kalisi_gateway::runtime::canvas",
+        query_id = %query_id,
+        rows = metadata.rows_returned,
+        nodes = nodes.len(),
+        edges = relationships.len(),
+        "Canvas response built"
+    );
+
+    CanvasGraphDto {
         query_id,
         cypher,
         parameters,
         nodes,
         edges: relationships,
+        hierarchy: Some(hierarchy),
         metadata,
         telemetry_cursor: None,
         raw_rows: None,
-    };
+    }
+}
 
-    // Log 2: Response to frontend
-    eprintln!("[{}] Response to frontend:\n{}\n",
-        Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-        serde_json::to_string_pretty(&response).unwrap_or_else(|_| "Failed to serialize".to_string())
-    );
+/// Place nodes where the user left them, see `ViewState.positions`. Nodes the
+/// user never moved keep the position they came with.
+pub fn merge_saved_positions(nodes: &mut [CanvasNodeDto], saved_positions: &HashMap<String, NodePosition>) {
+    if saved_positions.is_empty() {
+        return;
+    }
+    for node in nodes {
+        if let Some(position) = saved_positions.get(&node.GUID) {
+            node.position = Some(position.clone());
+        }
+    }
+}
+
+/// Extracts canvas entities row by row for streamed results, returning each
+/// node and relationship only the first time it is seen.
+#[derive(Debug, Default)]
+pub struct CanvasHarvester {
+    seen_nodes: HashSet<String>,
+    seen_edges: HashSet<String>,
+}
 
-    response
+impl CanvasHarvester {
+    pub fn harvest(&mut self, row: &Value) -> (Vec<CanvasNodeDto>, Vec<CanvasRelationshipDto>) {
+        let mut node_map = HashMap::new();
+        let mut rel_map = HashMap::new();
+        visit_value(row, &mut node_map, &mut rel_map);
+
+        let nodes = node_map
+            .into_values()
+            .filter(|node| self.seen_nodes.insert(node.GUID.clone()))
+            .collect();
+        let edges = rel_map
+            .into_values()
+            .filter(|edge| self.seen_edges.insert(edge.GUID.clone()))
+            .collect();
+
+        (nodes, edges)
+    }
 }
 
 fn harvest_graph_entities(rows: &[Value]) -> (Vec<CanvasNodeDto>, Vec<CanvasRelationshipDto>) {
@@ -64,10 +114,12 @@ fn harvest_graph_entities(rows: &[Value]) -> (Vec<CanvasNodeDto>, Vec<CanvasRela
         visit_value(row, &mut node_map, &mut rel_map);
     }
 
-    (
-        node_map.into_values().collect(),
-        rel_map.into_values().collect(),
-    )
+    // Sorted like the canonical graph, so that equal results serialize equally
+    let mut nodes: Vec<_> = node_map.into_values().collect();
+    nodes.sort_by(|a, b| a.GUID.cmp(&b.GUID));
+    let mut relationships: Vec<_> = rel_map.into_values().collect();
+    relationships.sort_by(|a, b| a.GUID.cmp(&b.GUID));
+    (nodes, relationships)
 }
 
 fn visit_value(
@@ -125,6 +177,12 @@ fn build_node(object: &Map<String, Value>) -> Option<CanvasNodeDto> {
         })
         .unwrap_or_default();
 
+    let parent_guid = PARENT_PROPERTIES
+        .iter()
+        .find_map(|key| properties.get(*key))
+        .and_then(|value| value.as_str())
+        .map(|s| s.to_string());
+
     // Pass through ALL properties dynamically, skip only internal Neo4j IDs
     let cleaned_properties: HashMap<String, Value> = properties
         .into_iter()
@@ -134,7 +192,7 @@ fn build_node(object: &Map<String, Value>) -> Option<CanvasNodeDto> {
     Some(CanvasNodeDto {
         GUID: guid,
         labels,
-        parent_guid: None,
+        parent_guid,
         position: None,
         display: None,
         tags: HashMap::new(),
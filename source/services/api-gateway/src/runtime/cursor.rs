@@ -0,0 +1,54 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+/// Length of the query id prefix embedded in a cursor
+const FINGERPRINT_LEN: usize = 16;
+
+/// Opaque position in the result of one query. The cursor carries a prefix of
+/// the query id so that it cannot be replayed against a different query.
+/// Each page runs the query again, so an offset only points at the same row
+/// when the query orders its rows completely with `ORDER BY`.
+pub fn encode(query_id: &str, offset: usize) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}:{offset}", fingerprint(query_id)))
+}
+
+/// Offset encoded in `cursor`, or `None` if the cursor is malformed or was
+/// issued for another query.
+pub fn decode(query_id: &str, cursor: &str) -> Option<usize> {
+    let decoded = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let decoded = std::str::from_utf8(&decoded).ok()?;
+    let (prefix, offset) = decoded.split_once(':')?;
+    if prefix != fingerprint(query_id) {
+        return None;
+    }
+    offset.parse().ok()
+}
+
+fn fingerprint(query_id: &str) -> &str {
+    let end = query_id
+        .char_indices()
+        .nth(FINGERPRINT_LEN)
+        .map_or(query_id.len(), |(index, _)| index);
+    &query_id[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY_ID: &str = "3f2a9c0e5b7d41a8c6e0f9b2d4a7c1e3";
+
+    #[test]
+    fn round_trips_offset() {
+        let cursor = encode(QUERY_ID, 1500);
+        assert_eq!(decode(QUERY_ID, &cursor), Some(1500));
+    }
+
+    #[test]
+    fn rejects_foreign_or_malformed_cursors() {
+        let cursor = encode(QUERY_ID, 10);
+        assert_eq!(decode("0000000000000000ffff", &cursor), None);
+        assert_eq!(decode(QUERY_ID, "not a cursor"), None);
+        assert_eq!(decode(QUERY_ID, &URL_SAFE_NO_PAD.encode("abc")), None);
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::database::access::CypherAccess;
+use crate::database::neo4j_gateway::{GatewayError, Neo4jGateway};
+
+use super::dto::{
+    BadgeDisplay, CanvasGraphDto, CanvasNodeDto, CanvasRelationshipDto, NodeDisplay,
+    RelationshipDisplay,
+};
+
+const STYLE_SPEC_QUERY: &str = "\
+    MATCH (vn:ViewNode) WHERE vn.GUID = $id OR vn.id = $id \
+    RETURN vn.styleSpec AS spec LIMIT 1";
+
+/// Declarative display mapping of a ViewNode, stored as JSON in its `styleSpec`
+/// property:
+///
+/// ```json
+/// {
+///   "labels": { "Service": { "color": "#1f77b4", "icon": "pi-server" } },
+///   "badges": [{ "property": "status", "colors": { "failed": "#d62728" } }],
+///   "relationships": { "CALLS": { "width": 2, "dash": [4, 2] } }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplaySpec {
+    /// Node style per label. A node takes the style of the first of its labels found here.
+    #[serde(default)]
+    pub labels: HashMap<String, LabelStyle>,
+    /// Badges derived from node properties, in display order
+    #[serde(default)]
+    pub badges: Vec<BadgeRule>,
+    /// Edge style per relationship type
+    #[serde(default)]
+    pub relationships: HashMap<String, RelationshipStyle>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelStyle {
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub border_color: Option<String>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub label_visible: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BadgeRule {
+    pub property: String,
+    /// Only nodes with this label get the badge
+    pub label: Option<String>,
+    /// Badge text, `{value}` is replaced by the property value. Defaults to the value.
+    pub text: Option<String>,
+    pub color: Option<String>,
+    /// Colour per property value, taking precedence over `color`
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationshipStyle {
+    pub color: Option<String>,
+    pub width: Option<f64>,
+    pub dash: Option<Vec<f64>>,
+    pub label: Option<String>,
+    pub label_visible: Option<bool>,
+}
+
+impl DisplaySpec {
+    /// Load the spec of a ViewNode, addressed by `GUID` or `id`. Returns `None` when
+    /// the ViewNode does not exist or has no valid spec.
+    pub async fn load(
+        neo4j: &Neo4jGateway,
+        view_node_id: &str,
+        access: CypherAccess,
+    ) -> Result<Option<Self>, GatewayError> {
+        let parameters = HashMap::from([("id".to_string(), json!(view_node_id))]);
+        let result = neo4j
+            .execute(
+                "view-node-style-spec",
+                STYLE_SPEC_QUERY,
+                &parameters,
+                access,
+            )
+            .await?;
+
+        let spec = match &result.raw_response["results"][0]["spec"] {
+            Value::String(text) => serde_json::from_str(text),
+            Value::Null => return Ok(None),
+            other => serde_json::from_value(other.clone()),
+        };
+        match spec {
+            Ok(spec) => Ok(Some(spec)),
+            Err(e) => {
+                // A broken spec only costs the styling, not the canvas
+                warn!(
+                    "Ignoring invalid styleSpec of ViewNode {}: {}",
+                    view_node_id, e
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn apply(&self, graph: &mut CanvasGraphDto) {
+        for node in &mut graph.nodes {
+            self.apply_to_node(node);
+        }
+        for edge in &mut graph.edges {
+            self.apply_to_edge(edge);
+        }
+    }
+
+    pub fn apply_to_node(&self, node: &mut CanvasNodeDto) {
+        let style = node.labels.iter().find_map(|label| self.labels.get(label));
+
+        let badges: Vec<BadgeDisplay> = self
+            .badges
+            .iter()
+            .filter(|rule| {
+                rule.label
+                    .as_ref()
+                    .is_none_or(|label| node.labels.contains(label))
+            })
+            .filter_map(|rule| rule.badge_for(&node.properties))
+            .collect();
+
+        if style.is_none() && badges.is_empty() {
+            return;
+        }
+        let style = style.cloned().unwrap_or_default();
+        node.display = Some(NodeDisplay {
+            width: style.width,
+            height: style.height,
+            color: style.color,
+            icon: style.icon,
+            border_color: style.border_color,
+            badges,
+            label_visible: style.label_visible,
+        });
+    }
+
+    pub fn apply_to_edge(&self, edge: &mut CanvasRelationshipDto) {
+        let Some(style) = self.relationships.get(&edge.r#type) else {
+            return;
+        };
+        edge.display = Some(RelationshipDisplay {
+            color: style.color.clone(),
+            width: style.width,
+            label: style.label.clone(),
+            label_visible: style.label_visible,
+            dash: style.dash.clone(),
+        });
+    }
+}
+
+impl BadgeRule {
+    fn badge_for(&self, properties: &HashMap<String, Value>) -> Option<BadgeDisplay> {
+        let value = match properties.get(&self.property)? {
+            Value::Null => return None,
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+
+        Some(BadgeDisplay {
+            text: match &self.text {
+                Some(template) => template.replace("{value}", &value),
+                None => value.clone(),
+            },
+            color: self.colors.get(&value).or(self.color.as_ref()).cloned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(labels: &[&str], properties: Value) -> CanvasNodeDto {
+        serde_json::from_value(json!({
+            "GUID": "node-1",
+            "labels": labels,
+            "properties": properties,
+        }))
+        .unwrap()
+    }
+
+    fn spec() -> DisplaySpec {
+        serde_json::from_value(json!({
+            "labels": {
+                "Service": { "color": "#1f77b4", "icon": "pi-server" },
+                "Component": { "color": "#2ca02c" }
+            },
+            "badges": [
+                { "property": "status", "colors": { "failed": "#d62728" }, "color": "#999" },
+                { "property": "version", "label": "Service", "text": "v{value}" }
+            ],
+            "relationships": { "CALLS": { "width": 2, "dash": [4, 2] } }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn styles_nodes_by_first_mapped_label_and_derives_badges() {
+        let mut service = node(
+            &["Component", "Service"],
+            json!({ "status": "failed", "version": 3 }),
+        );
+        spec().apply_to_node(&mut service);
+
+        let display = service.display.unwrap();
+        assert_eq!(display.color.as_deref(), Some("#2ca02c"));
+        assert_eq!(display.icon, None);
+        let badges: Vec<_> = display
+            .badges
+            .iter()
+            .map(|badge| (badge.text.as_str(), badge.color.as_deref()))
+            .collect();
+        assert_eq!(badges, [("failed", Some("#d62728")), ("v3", None)]);
+    }
+
+    #[test]
+    fn leaves_unmapped_entities_alone() {
+        let mut other = node(&["Other"], json!({ "version": 1 }));
+        spec().apply_to_node(&mut other);
+        assert!(other.display.is_none());
+
+        let mut edge: CanvasRelationshipDto = serde_json::from_value(json!({
+            "GUID": "rel-1", "fromGUID": "a", "toGUID": "b", "type": "OWNS"
+        }))
+        .unwrap();
+        spec().apply_to_edge(&mut edge);
+        assert!(edge.display.is_none());
+
+        edge.r#type = "CALLS".to_string();
+        spec().apply_to_edge(&mut edge);
+        assert_eq!(edge.display.unwrap().dash, Some(vec![4.0, 2.0]));
+    }
+}
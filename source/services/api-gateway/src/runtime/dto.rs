@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+use super::graph_response::ContainmentHierarchy;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CanvasNodeDto {
     pub GUID: String,
@@ -27,7 +29,7 @@ pub struct NodePosition {
     pub z: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NodeDisplay {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub width: Option<f64>,
@@ -92,9 +94,59 @@ pub struct CanvasGraphDto {
     pub parameters: HashMap<String, Value>,
     pub nodes: Vec<CanvasNodeDto>,
     pub edges: Vec<CanvasRelationshipDto>,
+    /// Containment tree resolved from the nodes and edges above; not sent with
+    /// streamed responses, where nodes only carry explicit parents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hierarchy: Option<ContainmentHierarchy>,
     pub metadata: QueryMetadataDto,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub telemetry_cursor: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub raw_rows: Option<Vec<Value>>,
 }
+
+#[cfg(test)]
+impl CanvasGraphDto {
+    /// A graph of nodes and edges given as JSON, for tests
+    pub(crate) fn from_json(nodes: Value, edges: Value) -> Self {
+        Self {
+            query_id: "q".to_string(),
+            cypher: String::new(),
+            parameters: HashMap::new(),
+            nodes: serde_json::from_value(nodes).unwrap(),
+            edges: serde_json::from_value(edges).unwrap(),
+            hierarchy: None,
+            metadata: QueryMetadataDto {
+                elapsed_ms: 0,
+                rows_returned: 0,
+            },
+            telemetry_cursor: None,
+            raw_rows: None,
+        }
+    }
+}
+
+/// One line of the NDJSON canvas stream. The stream starts with `header`, carries
+/// each node and edge once, and ends with either `end` or `error`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CanvasStreamEvent {
+    Header {
+        query_id: String,
+        cypher: String,
+        #[serde(skip_serializing_if = "HashMap::is_empty")]
+        parameters: HashMap<String, Value>,
+    },
+    Node(CanvasNodeDto),
+    Edge(CanvasRelationshipDto),
+    End {
+        metadata: QueryMetadataDto,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        telemetry_cursor: Option<String>,
+    },
+    Error {
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error_code: Option<String>,
+    },
+}
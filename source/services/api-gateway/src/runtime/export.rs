@@ -0,0 +1,657 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::dto::{CanvasGraphDto, CanvasNodeDto, CanvasRelationshipDto};
+use super::graph_response::{ContainmentHierarchy, CONTAINMENT_TYPES};
+use super::layout::{NODE_HEIGHT, NODE_WIDTH};
+
+/// Node properties tried, in order, for the text a node is drawn with
+const NAME_PROPERTIES: &[&str] = &["name", "label", "title"];
+/// Space around the drawing in SVG exports
+const SVG_MARGIN: f64 = 20.0;
+const DEFAULT_FILL: &str = "#ffffff";
+const DEFAULT_STROKE: &str = "#4a5568";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Every node and edge with its properties, for yEd, Gephi and networkx
+    Graphml,
+    /// Like GraphML, with the hierarchy as `pid` and positions as `viz:position`
+    Gexf,
+    /// Graphviz, containers as clusters
+    Dot,
+    /// Mermaid flowchart, containers as subgraphs
+    Mermaid,
+    /// Static drawing at the nodes' positions
+    Svg,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Graphml => "application/graphml+xml",
+            Self::Gexf => "application/gexf+xml",
+            Self::Dot => "text/vnd.graphviz",
+            Self::Mermaid => "text/vnd.mermaid",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Graphml => "graphml",
+            Self::Gexf => "gexf",
+            Self::Dot => "dot",
+            Self::Mermaid => "mmd",
+            Self::Svg => "svg",
+        }
+    }
+}
+
+/// Serialize a canvas graph. Nodes and edges come out in GUID order, children
+/// after their parent, so the same graph always exports to the same text. Node
+/// positions are taken as relative to the parent, as the layout leaves them; the
+/// SVG draws nodes without a position at their parent's corner.
+pub fn render(graph: &CanvasGraphDto, format: ExportFormat) -> String {
+    let export = Export::new(graph);
+    match format {
+        ExportFormat::Graphml => export.graphml(),
+        ExportFormat::Gexf => export.gexf(),
+        ExportFormat::Dot => export.dot(),
+        ExportFormat::Mermaid => export.mermaid(),
+        ExportFormat::Svg => export.svg(),
+    }
+}
+
+struct Export<'a> {
+    graph: &'a CanvasGraphDto,
+    nodes: HashMap<&'a str, &'a CanvasNodeDto>,
+    hierarchy: ContainmentHierarchy,
+    edges: Vec<&'a CanvasRelationshipDto>,
+}
+
+impl<'a> Export<'a> {
+    fn new(graph: &'a CanvasGraphDto) -> Self {
+        let nodes = graph
+            .nodes
+            .iter()
+            .map(|node| (node.GUID.as_str(), node))
+            .collect();
+        // The parents on the nodes are already resolved; this only orders them
+        let hierarchy = ContainmentHierarchy::resolve(
+            graph
+                .nodes
+                .iter()
+                .map(|node| (node.GUID.as_str(), node.parent_guid.as_deref())),
+            std::iter::empty(),
+        );
+        let mut edges: Vec<_> = graph.edges.iter().collect();
+        edges.sort_by(|a, b| a.GUID.cmp(&b.GUID));
+        Self {
+            graph,
+            nodes,
+            hierarchy,
+            edges,
+        }
+    }
+
+    /// All nodes, parents before their children, with their depth
+    fn tree(&self) -> Vec<(&'a CanvasNodeDto, usize)> {
+        let mut ordered = Vec::with_capacity(self.nodes.len());
+        let mut pending: Vec<(&str, usize)> = self
+            .hierarchy
+            .roots
+            .iter()
+            .rev()
+            .map(|guid| (guid.as_str(), 0))
+            .collect();
+        while let Some((guid, depth)) = pending.pop() {
+            let Some(node) = self.nodes.get(guid) else {
+                continue;
+            };
+            ordered.push((*node, depth));
+            if let Some(children) = self.hierarchy.children.get(guid) {
+                pending.extend(
+                    children
+                        .iter()
+                        .rev()
+                        .map(|child| (child.as_str(), depth + 1)),
+                );
+            }
+        }
+        ordered
+    }
+
+    fn has_children(&self, guid: &str) -> bool {
+        self.hierarchy.children.contains_key(guid)
+    }
+
+    /// Relationships drawn as lines; containment is shown by nesting instead
+    fn links(&self) -> Vec<&'a CanvasRelationshipDto> {
+        self.edges
+            .iter()
+            .copied()
+            .filter(|edge| !CONTAINMENT_TYPES.contains(&edge.r#type.as_str()))
+            .filter(|edge| {
+                self.nodes.contains_key(edge.fromGUID.as_str())
+                    && self.nodes.contains_key(edge.toGUID.as_str())
+            })
+            .collect()
+    }
+
+    /// Top left corner of every node in graph coordinates
+    fn absolute_positions(&self) -> HashMap<&'a str, (f64, f64)> {
+        let mut absolute: HashMap<&str, (f64, f64)> = HashMap::new();
+        for (node, _) in self.tree() {
+            let (origin_x, origin_y) = self
+                .hierarchy
+                .parent_of(&node.GUID)
+                .and_then(|parent| absolute.get(parent))
+                .copied()
+                .unwrap_or((0.0, 0.0));
+            let (x, y) = node
+                .position
+                .as_ref()
+                .map_or((0.0, 0.0), |position| (position.x, position.y));
+            absolute.insert(node.GUID.as_str(), (origin_x + x, origin_y + y));
+        }
+        absolute
+    }
+
+    fn graphml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, domain, name, kind) in [
+            ("labels", "node", "labels", "string"),
+            ("name", "node", "name", "string"),
+            ("parent", "node", "parent_guid", "string"),
+            ("x", "node", "x", "double"),
+            ("y", "node", "y", "double"),
+            ("node_properties", "node", "properties", "string"),
+            ("type", "edge", "type", "string"),
+            ("edge_properties", "edge", "properties", "string"),
+        ] {
+            let _ = writeln!(
+                out,
+                "  <key id=\"{id}\" for=\"{domain}\" attr.name=\"{name}\" attr.type=\"{kind}\"/>"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "  <graph id=\"{}\" edgedefault=\"directed\">",
+            xml_escape(&self.graph.query_id)
+        );
+        for (node, _) in self.tree() {
+            let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(&node.GUID));
+            graphml_data(&mut out, "labels", &node.labels.join(","));
+            graphml_data(&mut out, "name", &node_name(node));
+            if let Some(parent) = self.hierarchy.parent_of(&node.GUID) {
+                graphml_data(&mut out, "parent", parent);
+            }
+            if let Some(position) = &node.position {
+                graphml_data(&mut out, "x", &position.x.to_string());
+                graphml_data(&mut out, "y", &position.y.to_string());
+            }
+            if !node.properties.is_empty() {
+                graphml_data(&mut out, "node_properties", &sorted_json(&node.properties));
+            }
+            out.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+                xml_escape(&edge.GUID),
+                xml_escape(&edge.fromGUID),
+                xml_escape(&edge.toGUID)
+            );
+            graphml_data(&mut out, "type", &edge.r#type);
+            if !edge.properties.is_empty() {
+                graphml_data(&mut out, "edge_properties", &sorted_json(&edge.properties));
+            }
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn gexf(&self) -> String {
+        let absolute = self.absolute_positions();
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str(
+            "<gexf xmlns=\"http://gexf.net/1.3\" xmlns:viz=\"http://gexf.net/1.3/viz\" version=\"1.3\">\n",
+        );
+        out.push_str("  <graph defaultedgetype=\"directed\" mode=\"static\">\n");
+        out.push_str("    <attributes class=\"node\">\n");
+        out.push_str("      <attribute id=\"labels\" title=\"labels\" type=\"string\"/>\n");
+        out.push_str("      <attribute id=\"properties\" title=\"properties\" type=\"string\"/>\n");
+        out.push_str("    </attributes>\n");
+        out.push_str("    <attributes class=\"edge\">\n");
+        out.push_str("      <attribute id=\"properties\" title=\"properties\" type=\"string\"/>\n");
+        out.push_str("    </attributes>\n");
+        out.push_str("    <nodes>\n");
+        for (node, _) in self.tree() {
+            let _ = write!(
+                out,
+                "      <node id=\"{}\" label=\"{}\"",
+                xml_escape(&node.GUID),
+                xml_escape(&node_name(node))
+            );
+            if let Some(parent) = self.hierarchy.parent_of(&node.GUID) {
+                let _ = write!(out, " pid=\"{}\"", xml_escape(parent));
+            }
+            out.push_str(">\n        <attvalues>\n");
+            gexf_attvalue(&mut out, "labels", &node.labels.join(","));
+            gexf_attvalue(&mut out, "properties", &sorted_json(&node.properties));
+            out.push_str("        </attvalues>\n");
+            if node.position.is_some() {
+                let (x, y) = absolute[node.GUID.as_str()];
+                let _ = writeln!(out, "        <viz:position x=\"{x}\" y=\"{y}\" z=\"0\"/>");
+            }
+            if let Some((r, g, b)) = node
+                .display
+                .as_ref()
+                .and_then(|display| display.color.as_deref())
+                .and_then(hex_rgb)
+            {
+                let _ = writeln!(out, "        <viz:color r=\"{r}\" g=\"{g}\" b=\"{b}\"/>");
+            }
+            out.push_str("      </node>\n");
+        }
+        out.push_str("    </nodes>\n    <edges>\n");
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\" label=\"{}\">",
+                xml_escape(&edge.GUID),
+                xml_escape(&edge.fromGUID),
+                xml_escape(&edge.toGUID),
+                xml_escape(&edge.r#type)
+            );
+            out.push_str("        <attvalues>\n");
+            gexf_attvalue(&mut out, "properties", &sorted_json(&edge.properties));
+            out.push_str("        </attvalues>\n      </edge>\n");
+        }
+        out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        out
+    }
+
+    fn dot(&self) -> String {
+        let mut out = String::from("digraph canvas {\n  node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\"];\n");
+        let tree = self.tree();
+        for (i, (node, depth)) in tree.iter().enumerate() {
+            let indent = "  ".repeat(depth + 1);
+            if self.has_children(&node.GUID) {
+                let _ = writeln!(
+                    out,
+                    "{indent}subgraph \"cluster_{}\" {{\n{indent}  label=\"{}\";",
+                    dot_escape(&node.GUID),
+                    dot_escape(&node_name(node))
+                );
+            }
+            // The container itself stays a node so that edges can reach it
+            let indent = "  ".repeat(depth + 1 + usize::from(self.has_children(&node.GUID)));
+            let _ = write!(
+                out,
+                "{indent}\"{}\" [label=\"{}\"",
+                dot_escape(&node.GUID),
+                dot_escape(&node_name(node))
+            );
+            if let Some(color) = node.display.as_ref().and_then(|d| d.color.as_deref()) {
+                let _ = write!(out, ", fillcolor=\"{}\"", dot_escape(color));
+            }
+            out.push_str("];\n");
+            // Close every cluster the next node is no longer inside
+            let next_depth = tree.get(i + 1).map_or(0, |(_, depth)| *depth);
+            let open = depth + usize::from(self.has_children(&node.GUID));
+            for level in (next_depth..open).rev() {
+                let _ = writeln!(out, "{}}}", "  ".repeat(level + 1));
+            }
+        }
+        for edge in self.links() {
+            let _ = write!(
+                out,
+                "  \"{}\" -> \"{}\" [label=\"{}\"",
+                dot_escape(&edge.fromGUID),
+                dot_escape(&edge.toGUID),
+                dot_escape(&edge_label(edge))
+            );
+            if let Some(color) = edge.display.as_ref().and_then(|d| d.color.as_deref()) {
+                let _ = write!(out, ", color=\"{}\"", dot_escape(color));
+            }
+            out.push_str("];\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn mermaid(&self) -> String {
+        let tree = self.tree();
+        // GUIDs are not valid Mermaid identifiers, number the nodes instead
+        let ids: HashMap<&str, String> = tree
+            .iter()
+            .enumerate()
+            .map(|(i, (node, _))| (node.GUID.as_str(), format!("n{i}")))
+            .collect();
+
+        let mut out = String::from("flowchart TD\n");
+        for (i, (node, depth)) in tree.iter().enumerate() {
+            let indent = "  ".repeat(depth + 1);
+            let id = &ids[node.GUID.as_str()];
+            let name = mermaid_escape(&node_name(node));
+            if self.has_children(&node.GUID) {
+                let _ = writeln!(out, "{indent}subgraph {id} [\"{name}\"]");
+            } else {
+                let _ = writeln!(out, "{indent}{id}[\"{name}\"]");
+            }
+            let next_depth = tree.get(i + 1).map_or(0, |(_, depth)| *depth);
+            let open = depth + usize::from(self.has_children(&node.GUID));
+            for level in (next_depth..open).rev() {
+                let _ = writeln!(out, "{}end", "  ".repeat(level + 1));
+            }
+        }
+        for edge in self.links() {
+            let _ = writeln!(
+                out,
+                "  {} -->|\"{}\"| {}",
+                ids[edge.fromGUID.as_str()],
+                mermaid_escape(&edge_label(edge)),
+                ids[edge.toGUID.as_str()]
+            );
+        }
+        for (node, _) in &tree {
+            if let Some(color) = node.display.as_ref().and_then(|d| d.color.as_deref()) {
+                if hex_rgb(color).is_some() {
+                    let _ = writeln!(out, "  style {} fill:{color}", ids[node.GUID.as_str()]);
+                }
+            }
+        }
+        out
+    }
+
+    fn svg(&self) -> String {
+        let absolute = self.absolute_positions();
+        let rect = |node: &CanvasNodeDto| {
+            let (x, y) = absolute[node.GUID.as_str()];
+            let display = node.display.as_ref();
+            let width = display.and_then(|d| d.width).unwrap_or(NODE_WIDTH);
+            let height = display.and_then(|d| d.height).unwrap_or(NODE_HEIGHT);
+            (x, y, width, height)
+        };
+
+        let tree = self.tree();
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (node, _) in &tree {
+            let (x, y, width, height) = rect(node);
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x + width);
+            max_y = max_y.max(y + height);
+        }
+        if tree.is_empty() {
+            (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+        }
+        let (left, top) = (min_x - SVG_MARGIN, min_y - SVG_MARGIN);
+        let (width, height) = (
+            max_x - min_x + 2.0 * SVG_MARGIN,
+            max_y - min_y + 2.0 * SVG_MARGIN,
+        );
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{left} {top} {width} {height}\" width=\"{width}\" height=\"{height}\" font-family=\"sans-serif\" font-size=\"14\">"
+        );
+        let _ = writeln!(
+            out,
+            "  <defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"8\" markerHeight=\"8\" orient=\"auto-start-reverse\"><path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"{DEFAULT_STROKE}\"/></marker></defs>"
+        );
+        // Parents first, so that children are painted over them
+        for (node, _) in &tree {
+            let (x, y, width, height) = rect(node);
+            let display = node.display.as_ref();
+            let fill = display
+                .and_then(|d| d.color.as_deref())
+                .unwrap_or(DEFAULT_FILL);
+            let stroke = display
+                .and_then(|d| d.border_color.as_deref())
+                .unwrap_or(DEFAULT_STROKE);
+            let _ = writeln!(
+                out,
+                "  <g data-guid=\"{}\">\n    <rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" rx=\"6\" fill=\"{}\" stroke=\"{}\"/>",
+                xml_escape(&node.GUID),
+                xml_escape(fill),
+                xml_escape(stroke)
+            );
+            if display.and_then(|d| d.label_visible) != Some(false) {
+                let _ = writeln!(
+                    out,
+                    "    <text x=\"{}\" y=\"{}\">{}</text>",
+                    x + 12.0,
+                    y + 24.0,
+                    xml_escape(&node_name(node))
+                );
+            }
+            out.push_str("  </g>\n");
+        }
+        for edge in self.links() {
+            let (from_x, from_y, from_width, from_height) =
+                rect(self.nodes[edge.fromGUID.as_str()]);
+            let (to_x, to_y, to_width, to_height) = rect(self.nodes[edge.toGUID.as_str()]);
+            let start = (from_x + from_width / 2.0, from_y + from_height / 2.0);
+            let end = (to_x + to_width / 2.0, to_y + to_height / 2.0);
+            let end = clip_to_rect(start, end, (to_x, to_y, to_width, to_height));
+            let start = clip_to_rect(end, start, (from_x, from_y, from_width, from_height));
+
+            let display = edge.display.as_ref();
+            let stroke = display
+                .and_then(|d| d.color.as_deref())
+                .unwrap_or(DEFAULT_STROKE);
+            let stroke_width = display.and_then(|d| d.width).unwrap_or(1.5);
+            let _ = write!(
+                out,
+                "  <line data-guid=\"{}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{stroke_width}\" marker-end=\"url(#arrow)\"",
+                xml_escape(&edge.GUID),
+                start.0,
+                start.1,
+                end.0,
+                end.1,
+                xml_escape(stroke)
+            );
+            if let Some(dash) = display.and_then(|d| d.dash.as_ref()) {
+                let dash: Vec<String> = dash.iter().map(f64::to_string).collect();
+                let _ = write!(out, " stroke-dasharray=\"{}\"", dash.join(" "));
+            }
+            out.push_str("/>\n");
+            if display.and_then(|d| d.label_visible) == Some(true) {
+                let _ = writeln!(
+                    out,
+                    "  <text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"middle\">{}</text>",
+                    (start.0 + end.0) / 2.0,
+                    (start.1 + end.1) / 2.0 - 4.0,
+                    xml_escape(&edge_label(edge))
+                );
+            }
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+/// Where the line from `from` to the centre `to` of `rect` crosses its border
+fn clip_to_rect(from: (f64, f64), to: (f64, f64), rect: (f64, f64, f64, f64)) -> (f64, f64) {
+    let (_, _, width, height) = rect;
+    let (dx, dy) = (from.0 - to.0, from.1 - to.1);
+    if dx == 0.0 && dy == 0.0 {
+        return to;
+    }
+    let scale_x = if dx == 0.0 {
+        f64::INFINITY
+    } else {
+        (width / 2.0) / dx.abs()
+    };
+    let scale_y = if dy == 0.0 {
+        f64::INFINITY
+    } else {
+        (height / 2.0) / dy.abs()
+    };
+    let scale = scale_x.min(scale_y).min(1.0);
+    (to.0 + dx * scale, to.1 + dy * scale)
+}
+
+fn node_name(node: &CanvasNodeDto) -> String {
+    NAME_PROPERTIES
+        .iter()
+        .find_map(|key| node.properties.get(*key)?.as_str())
+        .unwrap_or(node.GUID.as_str())
+        .to_string()
+}
+
+fn edge_label(edge: &CanvasRelationshipDto) -> String {
+    edge.display
+        .as_ref()
+        .and_then(|display| display.label.clone())
+        .unwrap_or_else(|| edge.r#type.clone())
+}
+
+/// Properties as JSON with sorted keys
+fn sorted_json(properties: &HashMap<String, Value>) -> String {
+    let sorted: BTreeMap<&String, &Value> = properties.iter().collect();
+    serde_json::to_string(&sorted).unwrap_or_default()
+}
+
+fn graphml_data(out: &mut String, key: &str, value: &str) {
+    let _ = writeln!(
+        out,
+        "      <data key=\"{key}\">{}</data>",
+        xml_escape(value)
+    );
+}
+
+fn gexf_attvalue(out: &mut String, attribute: &str, value: &str) {
+    let _ = writeln!(
+        out,
+        "          <attvalue for=\"{attribute}\" value=\"{}\"/>",
+        xml_escape(value)
+    );
+}
+
+fn hex_rgb(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn system() -> CanvasGraphDto {
+        CanvasGraphDto::from_json(
+            json!([
+                { "GUID": "svc", "parent_guid": "sys", "properties": { "name": "Orders <v2>" },
+                  "position": { "x": 24.0, "y": 40.0 } },
+                { "GUID": "sys", "properties": { "name": "Shop" },
+                  "position": { "x": 100.0, "y": 50.0 },
+                  "display": { "width": 300.0, "height": 200.0, "color": "#1f77b4" } },
+                { "GUID": "db", "properties": { "name": "Postgres" },
+                  "position": { "x": 500.0, "y": 50.0 } },
+            ]),
+            json!([
+                { "GUID": "r1", "fromGUID": "svc", "toGUID": "db", "type": "USES" },
+                { "GUID": "r0", "fromGUID": "sys", "toGUID": "svc", "type": "CONTAINS" },
+            ]),
+        )
+    }
+
+    #[test]
+    fn output_is_independent_of_entity_order() {
+        let mut shuffled = system();
+        shuffled.nodes.reverse();
+        shuffled.edges.reverse();
+        for format in [
+            ExportFormat::Graphml,
+            ExportFormat::Gexf,
+            ExportFormat::Dot,
+            ExportFormat::Mermaid,
+            ExportFormat::Svg,
+        ] {
+            assert_eq!(render(&system(), format), render(&shuffled, format));
+        }
+    }
+
+    #[test]
+    fn graphml_keeps_every_edge_and_escapes_names() {
+        let graphml = render(&system(), ExportFormat::Graphml);
+        assert!(graphml.contains("<data key=\"name\">Orders &lt;v2&gt;</data>"));
+        assert!(graphml.contains("<data key=\"parent\">sys</data>"));
+        assert!(graphml.contains("<edge id=\"r0\" source=\"sys\" target=\"svc\">"));
+        assert!(graphml.contains("<edge id=\"r1\" source=\"svc\" target=\"db\">"));
+    }
+
+    #[test]
+    fn nests_containers_and_drops_containment_edges() {
+        let mermaid = render(&system(), ExportFormat::Mermaid);
+        assert_eq!(
+            mermaid,
+            "flowchart TD\n  \
+             n0[\"Postgres\"]\n  \
+             subgraph n1 [\"Shop\"]\n    \
+             n2[\"Orders <v2>\"]\n  \
+             end\n  \
+             n2 -->|\"USES\"| n0\n  \
+             style n1 fill:#1f77b4\n"
+        );
+
+        let dot = render(&system(), ExportFormat::Dot);
+        assert!(dot.contains("subgraph \"cluster_sys\""));
+        assert!(!dot.contains("CONTAINS"));
+    }
+
+    #[test]
+    fn svg_places_children_relative_to_their_parent() {
+        let svg = render(&system(), ExportFormat::Svg);
+        // sys at (100, 50) plus svc's own offset
+        assert!(svg.contains("<rect x=\"124\" y=\"90\""));
+        assert!(svg.contains("viewBox=\"80 30 660 240\""));
+        assert_eq!(svg.matches("<line ").count(), 1);
+    }
+}
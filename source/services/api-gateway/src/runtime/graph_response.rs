@@ -1,10 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use serde::Serialize;
 use serde_json::Value;
 
 use crate::database::neo4j_gateway::{GatewayQueryResult, QueryMetrics};
 
+/// Relationship types that nest their target inside their source
+pub const CONTAINMENT_TYPES: &[&str] = &["CONTAINS", "HAS_CHILD", "HAS_COMPONENT", "PARENT_OF"];
+
+/// Node properties naming a parent explicitly, used when no containment
+/// relationship in the result decides
+pub const PARENT_PROPERTIES: &[&str] = &["parentGUID", "parent_guid", "parentId"];
+
 /// Canonical representation of a node in the runtime data contract.
 #[derive(Debug, Clone, Serialize)]
 pub struct CanonicalNode {
@@ -48,6 +55,7 @@ pub struct CanonicalGraphResponse {
     pub parameters: HashMap<String, Value>,
     pub nodes: Vec<CanonicalNode>,
     pub relationships: Vec<CanonicalRelationship>,
+    pub hierarchy: ContainmentHierarchy,
     pub metadata: GraphMetadata,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub raw_rows: Option<Vec<Value>>,
@@ -61,15 +69,12 @@ impl CanonicalGraphResponse {
         result: &GatewayQueryResult,
         include_raw_rows: bool,
     ) -> Self {
-        let raw_rows = if include_raw_rows {
-            result
-                .raw_response
-                .get("results")
-                .and_then(|value| value.as_array())
-                .map(|rows| rows.to_vec())
-        } else {
-            None
-        };
+        let rows = result
+            .raw_response
+            .get("results")
+            .and_then(|value| value.as_array())
+            .map(|rows| rows.as_slice())
+            .unwrap_or_default();
 
         Self {
             query_id,
@@ -77,19 +82,179 @@ impl CanonicalGraphResponse {
             parameters,
             nodes: Vec::new(),
             relationships: Vec::new(),
+            hierarchy: ContainmentHierarchy::default(),
             metadata: GraphMetadata::from_metrics(&result.metrics),
-            raw_rows,
+            raw_rows: include_raw_rows.then(|| rows.to_vec()),
         }
+        .with_rows(rows)
     }
 
+    /// Replace the graph with the entities found in `rows` and resolve their
+    /// containment hierarchy
     pub fn with_rows(mut self, rows: &[Value]) -> Self {
-        let (nodes, relationships) = canonicalise_rows(rows);
+        let (mut nodes, mut relationships) = canonicalise_rows(rows);
+        nodes.sort_by(|a, b| a.guid.cmp(&b.guid));
+        relationships.sort_by(|a, b| a.guid.cmp(&b.guid));
+
+        let hierarchy = ContainmentHierarchy::resolve(
+            nodes
+                .iter()
+                .map(|node| (node.guid.as_str(), node.parent_guid.as_deref())),
+            relationships.iter().map(|rel| {
+                (
+                    rel.r#type.as_str(),
+                    rel.source_guid.as_str(),
+                    rel.target_guid.as_str(),
+                )
+            }),
+        );
+        for node in &mut nodes {
+            node.parent_guid = hierarchy.parent_of(&node.guid).map(str::to_string);
+        }
+
         self.nodes = nodes;
         self.relationships = relationships;
+        self.hierarchy = hierarchy;
         self
     }
 }
 
+/// Containment tree of a graph. Every node has at most one parent; conflicts and
+/// cycles in the source data are resolved deterministically and reported.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContainmentHierarchy {
+    /// Nodes without a parent in the graph, sorted by GUID
+    pub roots: Vec<String>,
+    /// Children of each parent, sorted by GUID
+    pub children: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<ParentConflict>,
+    /// Containment cycles, each listed from child to parent starting at the node
+    /// with the smallest GUID, which was made a root to break the cycle
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cycles: Vec<Vec<String>>,
+    #[serde(skip)]
+    parents: HashMap<String, String>,
+}
+
+/// A node that more than one containment relationship places inside a parent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParentConflict {
+    pub child_guid: String,
+    pub parent_guids: Vec<String>,
+    /// The explicit parent property if it names one of the candidates, otherwise
+    /// the smallest GUID
+    pub chosen_guid: String,
+}
+
+impl ContainmentHierarchy {
+    /// Resolve the hierarchy from `(guid, explicit parent)` nodes and
+    /// `(type, source, target)` relationships. Containment relationships take
+    /// precedence over explicit parents; parents outside the graph are ignored.
+    pub fn resolve<'a>(
+        nodes: impl IntoIterator<Item = (&'a str, Option<&'a str>)>,
+        relationships: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+    ) -> Self {
+        let nodes: BTreeMap<&str, Option<&str>> = nodes.into_iter().collect();
+
+        let mut candidates: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (rel_type, source, target) in relationships {
+            if CONTAINMENT_TYPES.contains(&rel_type)
+                && source != target
+                && nodes.contains_key(source)
+                && nodes.contains_key(target)
+            {
+                candidates.entry(target).or_default().insert(source);
+            }
+        }
+
+        let mut parents = HashMap::new();
+        let mut conflicts = Vec::new();
+        for (&guid, &explicit) in &nodes {
+            let parent = match candidates.get(guid) {
+                Some(edge_parents) => {
+                    let chosen = explicit
+                        .filter(|parent| edge_parents.contains(parent))
+                        .or_else(|| edge_parents.first().copied());
+                    if let (true, Some(chosen)) = (edge_parents.len() > 1, chosen) {
+                        conflicts.push(ParentConflict {
+                            child_guid: guid.to_string(),
+                            parent_guids: edge_parents.iter().map(|p| p.to_string()).collect(),
+                            chosen_guid: chosen.to_string(),
+                        });
+                    }
+                    chosen
+                }
+                None => explicit.filter(|parent| *parent != guid && nodes.contains_key(parent)),
+            };
+            if let Some(parent) = parent {
+                parents.insert(guid.to_string(), parent.to_string());
+            }
+        }
+
+        let cycles = break_cycles(nodes.keys().copied(), &mut parents);
+
+        let mut children: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (child, parent) in &parents {
+            children
+                .entry(parent.clone())
+                .or_default()
+                .push(child.clone());
+        }
+        for siblings in children.values_mut() {
+            siblings.sort();
+        }
+
+        Self {
+            roots: nodes
+                .keys()
+                .filter(|guid| !parents.contains_key(**guid))
+                .map(|guid| guid.to_string())
+                .collect(),
+            children,
+            conflicts,
+            cycles,
+            parents,
+        }
+    }
+
+    pub fn parent_of(&self, guid: &str) -> Option<&str> {
+        self.parents.get(guid).map(String::as_str)
+    }
+}
+
+/// Follow each node's parent chain and cut every cycle found at its smallest GUID
+fn break_cycles<'a>(
+    guids: impl Iterator<Item = &'a str>,
+    parents: &mut HashMap<String, String>,
+) -> Vec<Vec<String>> {
+    let mut cycles = Vec::new();
+    let mut finished: HashSet<String> = HashSet::new();
+
+    for guid in guids {
+        let mut path: Vec<String> = Vec::new();
+        let mut current = Some(guid.to_string());
+        while let Some(node) = current {
+            if finished.contains(&node) {
+                break;
+            }
+            if let Some(start) = path.iter().position(|seen| *seen == node) {
+                let mut cycle = path[start..].to_vec();
+                let smallest = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+                cycle.rotate_left(smallest);
+                parents.remove(&cycle[0]);
+                cycles.push(cycle);
+                break;
+            }
+            current = parents.get(&node).cloned();
+            path.push(node);
+        }
+        finished.extend(path);
+    }
+
+    cycles
+}
+
 impl GraphMetadata {
     pub fn from_metrics(metrics: &QueryMetrics) -> Self {
         Self {
@@ -142,10 +307,9 @@ fn parse_node(value: &Value) -> Option<CanonicalNode> {
         })
         .unwrap_or_default();
 
-    let parent_guid = properties
-        .get("parentGUID")
-        .or_else(|| properties.get("parent_guid"))
-        .or_else(|| properties.get("parentId"))
+    let parent_guid = PARENT_PROPERTIES
+        .iter()
+        .find_map(|key| properties.get(*key))
         .and_then(|value| value.as_str().map(|s| s.to_string()));
 
     let mut cleaned_properties = HashMap::new();
@@ -225,3 +389,71 @@ fn extract_guid(
             _ => None,
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(
+        nodes: &[(&'static str, Option<&'static str>)],
+        relationships: &[(&'static str, &'static str, &'static str)],
+    ) -> ContainmentHierarchy {
+        ContainmentHierarchy::resolve(nodes.iter().copied(), relationships.iter().copied())
+    }
+
+    #[test]
+    fn builds_tree_from_containment_edges_and_parent_properties() {
+        let hierarchy = resolve(
+            &[
+                ("a", None),
+                ("b", None),
+                ("c", Some("b")),
+                ("d", Some("missing")),
+            ],
+            &[("CONTAINS", "a", "b"), ("CALLS", "d", "a")],
+        );
+
+        assert_eq!(hierarchy.parent_of("b"), Some("a"));
+        assert_eq!(hierarchy.parent_of("c"), Some("b"));
+        assert_eq!(hierarchy.roots, ["a", "d"]);
+        assert_eq!(hierarchy.children["a"], ["b"]);
+        assert!(hierarchy.conflicts.is_empty() && hierarchy.cycles.is_empty());
+    }
+
+    #[test]
+    fn resolves_multiple_parents() {
+        let hierarchy = resolve(
+            &[("a", None), ("b", None), ("c", Some("b"))],
+            &[("HAS_CHILD", "a", "c"), ("PARENT_OF", "b", "c")],
+        );
+
+        // The explicit parent settles the conflict
+        assert_eq!(hierarchy.parent_of("c"), Some("b"));
+        assert_eq!(
+            hierarchy.conflicts,
+            [ParentConflict {
+                child_guid: "c".to_string(),
+                parent_guids: vec!["a".to_string(), "b".to_string()],
+                chosen_guid: "b".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn breaks_cycles_at_the_smallest_guid() {
+        let hierarchy = resolve(
+            &[("a", None), ("b", None), ("c", None), ("d", None)],
+            &[
+                ("CONTAINS", "c", "b"),
+                ("CONTAINS", "b", "a"),
+                ("CONTAINS", "a", "c"),
+                ("CONTAINS", "c", "d"),
+            ],
+        );
+
+        assert_eq!(hierarchy.cycles, [["a", "b", "c"]]);
+        assert_eq!(hierarchy.roots, ["a"]);
+        assert_eq!(hierarchy.parent_of("c"), Some("a"));
+        assert_eq!(hierarchy.children["c"], ["b", "d"]);
+    }
+}
@@ -0,0 +1,499 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::Deserialize;
+
+use super::dto::{CanvasGraphDto, NodeDisplay, NodePosition};
+use super::graph_response::{ContainmentHierarchy, CONTAINMENT_TYPES};
+
+/// Size of nodes without a display size, matching the frontend defaults
+pub(super) const NODE_WIDTH: f64 = 220.0;
+pub(super) const NODE_HEIGHT: f64 = 120.0;
+/// Space between siblings
+const GAP: f64 = 40.0;
+/// Space between a container's border and its children
+const PADDING: f64 = 24.0;
+/// Room for a container's title above its children
+const HEADER: f64 = 40.0;
+/// Width to height ratio containment packing aims for
+const TARGET_ASPECT: f64 = 1.6;
+/// Barycenter passes of the layered layout
+const ORDERING_SWEEPS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutMode {
+    /// Children in equal cells, row by row
+    Grid,
+    /// Children packed in rows by height, sized to fit
+    Containment,
+    /// Children in layers along their relationships (Sugiyama), top to bottom
+    Layered,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Size {
+    width: f64,
+    height: f64,
+}
+
+/// Lay out `graph` in place, giving every node a position and size. Nodes with a
+/// `parent_guid` in the graph are placed inside their parent, relative to its top
+/// left corner, and containers grow to fit. Nodes that already have a position,
+/// such as one saved in the user's ViewState, keep it. The result only depends on
+/// the graph, not on the order of its nodes.
+pub fn apply_layout(graph: &mut CanvasGraphDto, mode: LayoutMode) {
+    let mut order: Vec<usize> = (0..graph.nodes.len()).collect();
+    order.sort_by(|&a, &b| graph.nodes[a].GUID.cmp(&graph.nodes[b].GUID));
+    let index: HashMap<&str, usize> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.GUID.as_str(), i))
+        .collect();
+
+    // The parents on the nodes are normally resolved already; this also cuts the
+    // cycles of any that were set elsewhere, which would leave subtrees unreachable
+    let hierarchy = ContainmentHierarchy::resolve(
+        graph
+            .nodes
+            .iter()
+            .map(|node| (node.GUID.as_str(), node.parent_guid.as_deref())),
+        std::iter::empty(),
+    );
+    let parents: Vec<Option<usize>> = graph
+        .nodes
+        .iter()
+        .map(|node| index.get(hierarchy.parent_of(&node.GUID)?).copied())
+        .collect();
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); graph.nodes.len()];
+    let mut roots = Vec::new();
+    for &i in &order {
+        match parents[i] {
+            Some(parent) => children[parent].push(i),
+            None => roots.push(i),
+        }
+    }
+
+    let edges: Vec<(usize, usize)> = graph
+        .edges
+        .iter()
+        .filter(|edge| !CONTAINMENT_TYPES.contains(&edge.r#type.as_str()))
+        .filter_map(|edge| {
+            Some((
+                *index.get(edge.fromGUID.as_str())?,
+                *index.get(edge.toGUID.as_str())?,
+            ))
+        })
+        .collect();
+
+    let preferred: Vec<Size> = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let display = node.display.as_ref();
+            Size {
+                width: display.and_then(|d| d.width).unwrap_or(NODE_WIDTH),
+                height: display.and_then(|d| d.height).unwrap_or(NODE_HEIGHT),
+            }
+        })
+        .collect();
+
+    let mut engine = Engine {
+        mode,
+        parents: &parents,
+        children: &children,
+        edges: &edges,
+        sizes: preferred.clone(),
+        positions: vec![(0.0, 0.0); graph.nodes.len()],
+    };
+    for &i in &order {
+        if parents[i].is_none() {
+            engine.size_subtree(i, &preferred);
+        }
+    }
+    engine.arrange(&roots, None, (0.0, 0.0));
+
+    for (i, node) in graph.nodes.iter_mut().enumerate() {
+        let (x, y) = engine.positions[i];
+        node.position.get_or_insert(NodePosition { x, y, z: None });
+        let display = node.display.get_or_insert_with(NodeDisplay::default);
+        display.width = Some(engine.sizes[i].width);
+        display.height = Some(engine.sizes[i].height);
+    }
+}
+
+struct Engine<'a> {
+    mode: LayoutMode,
+    parents: &'a [Option<usize>],
+    children: &'a [Vec<usize>],
+    edges: &'a [(usize, usize)],
+    sizes: Vec<Size>,
+    positions: Vec<(f64, f64)>,
+}
+
+impl Engine<'_> {
+    /// Size containers bottom-up and place their children relative to them
+    fn size_subtree(&mut self, node: usize, preferred: &[Size]) {
+        let children = &self.children[node];
+        if children.is_empty() {
+            return;
+        }
+        for &child in children {
+            self.size_subtree(child, preferred);
+        }
+
+        let content = self.arrange(children, Some(node), (PADDING, HEADER));
+        self.sizes[node] = Size {
+            width: preferred[node].width.max(content.width + 2.0 * PADDING),
+            height: preferred[node]
+                .height
+                .max(content.height + HEADER + PADDING),
+        };
+    }
+
+    /// Place the sized `members`, children of `container`, starting at `origin`.
+    /// Returns the size they take up.
+    fn arrange(&mut self, members: &[usize], container: Option<usize>, origin: (f64, f64)) -> Size {
+        let sizes: Vec<Size> = members.iter().map(|&m| self.sizes[m]).collect();
+        let (offsets, content) = match self.mode {
+            LayoutMode::Grid => grid(&sizes),
+            LayoutMode::Containment => pack(&sizes),
+            LayoutMode::Layered => {
+                let edges = self.sibling_edges(members, container);
+                layered(&sizes, &edges)
+            }
+        };
+        for (&member, (x, y)) in members.iter().zip(offsets) {
+            self.positions[member] = (origin.0 + x, origin.1 + y);
+        }
+        content
+    }
+
+    /// Relationships between descendants of different members, lifted to the
+    /// members, as indices into `members`
+    fn sibling_edges(&self, members: &[usize], container: Option<usize>) -> Vec<(usize, usize)> {
+        let local: HashMap<usize, usize> =
+            members.iter().enumerate().map(|(i, &m)| (m, i)).collect();
+        let lift = |mut node: usize| -> Option<usize> {
+            let mut steps = 0;
+            while self.parents[node] != container {
+                node = self.parents[node]?;
+                steps += 1;
+                if steps > self.parents.len() {
+                    return None;
+                }
+            }
+            local.get(&node).copied()
+        };
+
+        let edges: BTreeSet<(usize, usize)> = self
+            .edges
+            .iter()
+            .filter_map(|&(from, to)| Some((lift(from)?, lift(to)?)))
+            .filter(|(from, to)| from != to)
+            .collect();
+        edges.into_iter().collect()
+    }
+}
+
+fn grid(sizes: &[Size]) -> (Vec<(f64, f64)>, Size) {
+    if sizes.is_empty() {
+        return (Vec::new(), Size::default());
+    }
+    let columns = (sizes.len() as f64).sqrt().ceil() as usize;
+    let cell_width = sizes.iter().map(|s| s.width).fold(0.0, f64::max);
+    let cell_height = sizes.iter().map(|s| s.height).fold(0.0, f64::max);
+
+    let offsets = (0..sizes.len())
+        .map(|i| {
+            let (row, column) = (i / columns, i % columns);
+            (
+                column as f64 * (cell_width + GAP),
+                row as f64 * (cell_height + GAP),
+            )
+        })
+        .collect();
+    let rows = sizes.len().div_ceil(columns);
+    let used_columns = columns.min(sizes.len());
+    let content = Size {
+        width: used_columns as f64 * (cell_width + GAP) - GAP,
+        height: rows as f64 * (cell_height + GAP) - GAP,
+    };
+    (offsets, content)
+}
+
+/// Shelf packing: tallest first, rows as wide as keeps the area near
+/// `TARGET_ASPECT`
+fn pack(sizes: &[Size]) -> (Vec<(f64, f64)>, Size) {
+    let area: f64 = sizes
+        .iter()
+        .map(|s| (s.width + GAP) * (s.height + GAP))
+        .sum();
+    let widest = sizes.iter().map(|s| s.width).fold(0.0, f64::max);
+    let row_limit = widest.max((area * TARGET_ASPECT).sqrt());
+
+    let mut by_height: Vec<usize> = (0..sizes.len()).collect();
+    by_height.sort_by(|&a, &b| sizes[b].height.total_cmp(&sizes[a].height));
+
+    let mut offsets = vec![(0.0, 0.0); sizes.len()];
+    let (mut x, mut y, mut row_height) = (0.0, 0.0, 0.0_f64);
+    let mut content = Size::default();
+    for i in by_height {
+        let size = sizes[i];
+        if x > 0.0 && x + size.width > row_limit {
+            x = 0.0;
+            y += row_height + GAP;
+            row_height = 0.0;
+        }
+        offsets[i] = (x, y);
+        content.width = content.width.max(x + size.width);
+        content.height = content.height.max(y + size.height);
+        x += size.width + GAP;
+        row_height = row_height.max(size.height);
+    }
+    (offsets, content)
+}
+
+/// Sugiyama-style layering: break cycles, assign longest-path layers, order each
+/// layer by barycenters, then stack the layers centred on the widest
+fn layered(sizes: &[Size], edges: &[(usize, usize)]) -> (Vec<(f64, f64)>, Size) {
+    let count = sizes.len();
+    let edges = acyclic(count, edges);
+
+    let mut successors = vec![Vec::new(); count];
+    let mut predecessors = vec![Vec::new(); count];
+    for &(from, to) in &edges {
+        successors[from].push(to);
+        predecessors[to].push(from);
+    }
+
+    // Longest path from the sources, in topological order
+    let mut layer = vec![0; count];
+    let mut remaining: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+    let mut ready: BTreeSet<usize> = (0..count).filter(|&v| remaining[v] == 0).collect();
+    while let Some(node) = ready.pop_first() {
+        for &next in &successors[node] {
+            layer[next] = layer[next].max(layer[node] + 1);
+            remaining[next] -= 1;
+            if remaining[next] == 0 {
+                ready.insert(next);
+            }
+        }
+    }
+
+    let depth = layer.iter().copied().max().map_or(0, |max| max + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); depth];
+    for node in 0..count {
+        layers[layer[node]].push(node);
+    }
+
+    let mut rank = vec![0.0; count];
+    let assign_ranks = |layers: &[Vec<usize>], rank: &mut [f64]| {
+        for members in layers {
+            for (i, &node) in members.iter().enumerate() {
+                rank[node] = i as f64;
+            }
+        }
+    };
+    assign_ranks(&layers, &mut rank);
+    for sweep in 0..ORDERING_SWEEPS {
+        let downward = sweep % 2 == 0;
+        let neighbours = if downward { &predecessors } else { &successors };
+        let sequence: Vec<usize> = if downward {
+            (1..depth).collect()
+        } else {
+            (0..depth.saturating_sub(1)).rev().collect()
+        };
+        for l in sequence {
+            let barycenter = |node: usize| match neighbours[node].len() {
+                0 => rank[node],
+                n => neighbours[node].iter().map(|&m| rank[m]).sum::<f64>() / n as f64,
+            };
+            let mut keyed: Vec<(f64, usize)> = layers[l]
+                .iter()
+                .map(|&node| (barycenter(node), node))
+                .collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0).then(rank[a.1].total_cmp(&rank[b.1])));
+            layers[l] = keyed.into_iter().map(|(_, node)| node).collect();
+            for (i, &node) in layers[l].iter().enumerate() {
+                rank[node] = i as f64;
+            }
+        }
+    }
+
+    let row_widths: Vec<f64> = layers
+        .iter()
+        .map(|members| members.iter().map(|&m| sizes[m].width + GAP).sum::<f64>() - GAP)
+        .collect();
+    let width = row_widths.iter().copied().fold(0.0, f64::max);
+
+    let mut offsets = vec![(0.0, 0.0); count];
+    let mut y = 0.0;
+    for (members, row_width) in layers.iter().zip(&row_widths) {
+        let mut x = (width - row_width) / 2.0;
+        let row_height = members.iter().map(|&m| sizes[m].height).fold(0.0, f64::max);
+        for &member in members {
+            offsets[member] = (x, y);
+            x += sizes[member].width + GAP;
+        }
+        y += row_height + GAP;
+    }
+
+    let height = if depth == 0 { 0.0 } else { y - GAP };
+    (offsets, Size { width, height })
+}
+
+/// Reverse the edges that close a cycle, found by depth-first search in node order
+fn acyclic(count: usize, edges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut successors = vec![Vec::new(); count];
+    for &(from, to) in edges {
+        successors[from].push(to);
+    }
+
+    // 0 unvisited, 1 on the stack, 2 done
+    let mut state = vec![0u8; count];
+    let mut back_edges = HashSet::new();
+    for start in 0..count {
+        if state[start] != 0 {
+            continue;
+        }
+        let mut stack = vec![(start, 0)];
+        state[start] = 1;
+        while let Some((node, next)) = stack.pop() {
+            match successors[node].get(next) {
+                Some(&to) => {
+                    stack.push((node, next + 1));
+                    match state[to] {
+                        0 => {
+                            state[to] = 1;
+                            stack.push((to, 0));
+                        }
+                        1 => {
+                            back_edges.insert((node, to));
+                        }
+                        _ => {}
+                    }
+                }
+                None => state[node] = 2,
+            }
+        }
+    }
+
+    edges
+        .iter()
+        .map(|&(from, to)| {
+            if back_edges.contains(&(from, to)) {
+                (to, from)
+            } else {
+                (from, to)
+            }
+        })
+        .filter(|(from, to)| from != to)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn position(graph: &CanvasGraphDto, guid: &str) -> (f64, f64) {
+        let node = graph.nodes.iter().find(|node| node.GUID == guid).unwrap();
+        let position = node.position.as_ref().unwrap();
+        (position.x, position.y)
+    }
+
+    fn size(graph: &CanvasGraphDto, guid: &str) -> (f64, f64) {
+        let node = graph.nodes.iter().find(|node| node.GUID == guid).unwrap();
+        let display = node.display.as_ref().unwrap();
+        (display.width.unwrap(), display.height.unwrap())
+    }
+
+    #[test]
+    fn grid_is_independent_of_node_order() {
+        let nodes = json!([{ "GUID": "c" }, { "GUID": "a" }, { "GUID": "d" }, { "GUID": "b" }]);
+        let mut shuffled = CanvasGraphDto::from_json(nodes, json!([]));
+        apply_layout(&mut shuffled, LayoutMode::Grid);
+
+        assert_eq!(position(&shuffled, "a"), (0.0, 0.0));
+        assert_eq!(position(&shuffled, "b"), (NODE_WIDTH + GAP, 0.0));
+        assert_eq!(position(&shuffled, "c"), (0.0, NODE_HEIGHT + GAP));
+        assert_eq!(
+            position(&shuffled, "d"),
+            (NODE_WIDTH + GAP, NODE_HEIGHT + GAP)
+        );
+    }
+
+    #[test]
+    fn keeps_saved_positions() {
+        let mut saved = CanvasGraphDto::from_json(
+            json!([{ "GUID": "a", "position": { "x": 500.0, "y": 7.0 } }, { "GUID": "b" }]),
+            json!([]),
+        );
+        apply_layout(&mut saved, LayoutMode::Grid);
+
+        assert_eq!(position(&saved, "a"), (500.0, 7.0));
+        assert_eq!(position(&saved, "b"), (NODE_WIDTH + GAP, 0.0));
+    }
+
+    #[test]
+    fn containers_grow_around_their_children() {
+        let mut packed = CanvasGraphDto::from_json(
+            json!([
+                { "GUID": "root" },
+                { "GUID": "a", "parent_guid": "root" },
+                { "GUID": "b", "parent_guid": "root", "display": { "width": 400.0, "height": 300.0 } },
+            ]),
+            json!([]),
+        );
+        apply_layout(&mut packed, LayoutMode::Containment);
+
+        let (width, height) = size(&packed, "root");
+        for child in ["a", "b"] {
+            let (x, y) = position(&packed, child);
+            let (w, h) = size(&packed, child);
+            assert!(x >= PADDING && y >= HEADER);
+            assert!(x + w <= width - PADDING + 1e-9 && y + h <= height - PADDING + 1e-9);
+        }
+        // The taller child goes first
+        assert_eq!(position(&packed, "b"), (PADDING, HEADER));
+    }
+
+    #[test]
+    fn parent_cycles_are_cut_at_the_smallest_guid() {
+        let mut cycle = CanvasGraphDto::from_json(
+            json!([
+                { "GUID": "a", "parent_guid": "b" },
+                { "GUID": "b", "parent_guid": "a" },
+            ]),
+            json!([]),
+        );
+        apply_layout(&mut cycle, LayoutMode::Grid);
+
+        assert_eq!(position(&cycle, "a"), (0.0, 0.0));
+        assert_eq!(position(&cycle, "b"), (PADDING, HEADER));
+    }
+
+    #[test]
+    fn layered_places_targets_below_sources() {
+        let mut chain = CanvasGraphDto::from_json(
+            json!([{ "GUID": "a" }, { "GUID": "b" }, { "GUID": "c" }]),
+            json!([
+                { "GUID": "r1", "fromGUID": "c", "toGUID": "b", "type": "CALLS" },
+                { "GUID": "r2", "fromGUID": "b", "toGUID": "a", "type": "CALLS" },
+                { "GUID": "r3", "fromGUID": "a", "toGUID": "c", "type": "CALLS" },
+            ]),
+        );
+        apply_layout(&mut chain, LayoutMode::Layered);
+
+        // The search starts at a and breaks the cycle at b -> a, leaving a -> c -> b
+        let (_, a) = position(&chain, "a");
+        let (_, c) = position(&chain, "c");
+        let (_, b) = position(&chain, "b");
+        assert!(a < c && c < b);
+    }
+}
@@ -1,2 +1,8 @@
 pub mod canvas;
+pub mod cursor;
+pub mod display;
+pub mod export;
+pub mod graph_response;
+pub mod layout;
+pub mod result_cache;
 pub mod dto;
@@ -0,0 +1,519 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use neo4rs::Query;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use crate::database::access::CypherAccess;
+use crate::database::neo4j_gateway::{
+    GatewayError, GatewayQueryResult, Neo4jGateway, QueryCounters, QueryMetrics, QuerySummary,
+    QueryType,
+};
+use crate::graph_events::{DeltaDispatcher, GraphChanges};
+
+use super::canvas::CanvasHarvester;
+
+const ENTRY_PREFIX: &str = "canvas_cache:entry:";
+/// Sets of the query IDs whose result carries a tag, see [`result_tags`]
+const TAG_PREFIX: &str = "canvas_cache:tag:";
+/// Carried by every entry, for changes that require a resync
+const ANY_TAG: &str = "any";
+
+/// Larger results are not worth the memory and the tag bookkeeping
+const MAX_CACHED_ROWS: usize = 5_000;
+/// Tag sets merged per SUNION when invalidating
+const SUNION_BATCH: usize = 500;
+
+/// A read-only query result as cached
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResult {
+    raw_response: Value,
+    /// Of the execution that filled the cache, so that hits answer identically
+    elapsed_ms: u64,
+    result_count: usize,
+    tags: BTreeSet<String>,
+}
+
+/// Results of read-only canvas queries by derived query ID, in Redis and, for the
+/// most recently used ones, in process. Entries expire after the TTL and are
+/// dropped earlier when the `graph:delta` feed reports a change to one of their
+/// nodes or relationships. Created or updated nodes also drop the entries holding
+/// nodes with one of their labels, and created or updated relationships those
+/// holding relationships of their type, since the query may match them now. Changes with
+/// deletions that could not be identified drop every entry. Results without any
+/// nodes or relationships are not cached, nothing would drop them.
+/// Changes missed while the feed was unavailable only expire with the TTL.
+pub struct ResultCache {
+    redis: ConnectionManager,
+    ttl: Duration,
+    local: Mutex<LocalCache>,
+    /// Bumped by every change; results read before a change are not stored
+    generation: AtomicU64,
+}
+
+impl ResultCache {
+    /// Keeps results for `ttl`, zero disables the cache, and the last `capacity`
+    /// of them in process as well. Follows the `graph:delta` feed.
+    pub async fn start(
+        redis_url: &str,
+        ttl: Duration,
+        capacity: usize,
+        dispatcher: Arc<DeltaDispatcher>,
+    ) -> Result<Arc<Self>, redis::RedisError> {
+        let client = redis::Client::open(redis_url)?;
+        let cache = Arc::new(Self {
+            redis: ConnectionManager::new(client).await?,
+            ttl,
+            local: Mutex::new(LocalCache::new(capacity)),
+            generation: AtomicU64::new(0),
+        });
+
+        if cache.enabled() {
+            tokio::spawn(cache.clone().watch(dispatcher));
+            info!(
+                "Canvas result cache enabled (ttl={:?}, local_entries={})",
+                cache.ttl, capacity
+            );
+        }
+        Ok(cache)
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// The result of a read-only query from the cache, otherwise executed and
+    /// cached. Cached results skip authorization, which every role passes for reads.
+    pub async fn fetch(
+        &self,
+        neo4j: &Neo4jGateway,
+        query_id: &str,
+        prepared: &Query,
+        parameters: &HashMap<String, Value>,
+        access: CypherAccess,
+    ) -> Result<GatewayQueryResult, GatewayError> {
+        if !self.enabled() {
+            return neo4j
+                .execute_prepared(query_id, prepared, parameters, access)
+                .await;
+        }
+
+        if let Some(cached) = self.lookup(query_id).await {
+            debug!(query_id, "Canvas result cache hit");
+            return Ok(cached.to_result());
+        }
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let result = neo4j
+            .execute_prepared(query_id, prepared, parameters, access)
+            .await?;
+        if result.summary.query_type == QueryType::Read
+            && result.metrics.result_count <= MAX_CACHED_ROWS
+        {
+            self.store(query_id, &result, generation).await;
+        }
+        Ok(result)
+    }
+
+    async fn lookup(&self, query_id: &str) -> Option<Arc<CachedResult>> {
+        if let Some(cached) = self.local.lock().unwrap().get(query_id) {
+            return Some(cached);
+        }
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let mut redis = self.redis.clone();
+        let json: Option<String> = match redis.get(format!("{ENTRY_PREFIX}{query_id}")).await {
+            Ok(json) => json,
+            Err(e) => {
+                warn!("Failed to read canvas result cache: {}", e);
+                return None;
+            }
+        };
+        let cached: CachedResult = match serde_json::from_str(&json?) {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!("Ignoring malformed canvas cache entry {}: {}", query_id, e);
+                return None;
+            }
+        };
+
+        let cached = Arc::new(cached);
+        // Redis does not say how much of the TTL is left; the local copy lives
+        // at most as long as the entry would
+        if self.generation.load(Ordering::Acquire) == generation {
+            self.local.lock().unwrap().insert(
+                query_id.to_string(),
+                cached.clone(),
+                Instant::now() + self.ttl,
+            );
+        }
+        Some(cached)
+    }
+
+    async fn store(&self, query_id: &str, result: &GatewayQueryResult, generation: u64) {
+        let mut tags = result_tags(&result.raw_response);
+        // Without nodes or relationships, such as an empty result, a count or other
+        // scalars, no change could ever invalidate the entry
+        if tags.is_empty() {
+            debug!(query_id, "Canvas result has no graph entities, not cached");
+            return;
+        }
+        tags.insert(ANY_TAG.to_string());
+        let cached = CachedResult {
+            raw_response: result.raw_response.clone(),
+            elapsed_ms: result.metrics.elapsed_ms,
+            result_count: result.metrics.result_count,
+            tags,
+        };
+        let json = match serde_json::to_string(&cached) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!("Failed to serialize canvas cache entry {}: {}", query_id, e);
+                return;
+            }
+        };
+
+        let ttl = self.ttl.as_secs();
+        let mut pipe = redis::pipe();
+        pipe.set_ex(format!("{ENTRY_PREFIX}{query_id}"), json, ttl)
+            .ignore();
+        for tag in &cached.tags {
+            let key = format!("{TAG_PREFIX}{tag}");
+            pipe.sadd(&key, query_id).ignore();
+            pipe.expire(&key, ttl as i64).ignore();
+        }
+
+        // A change that arrived while the query ran may not be in the result
+        if self.generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        let mut redis = self.redis.clone();
+        if let Err(e) = pipe.query_async::<()>(&mut redis).await {
+            warn!("Failed to store canvas cache entry {}: {}", query_id, e);
+            return;
+        }
+        // Or while it was being stored, before its tags were in place
+        if self.generation.load(Ordering::Acquire) != generation {
+            let _: Result<(), _> = redis.del(format!("{ENTRY_PREFIX}{query_id}")).await;
+            return;
+        }
+        self.local.lock().unwrap().insert(
+            query_id.to_string(),
+            Arc::new(cached),
+            Instant::now() + self.ttl,
+        );
+    }
+
+    /// Drop the entries a change may have made stale
+    pub async fn invalidate(&self, changes: &GraphChanges) {
+        let tags = change_tags(changes);
+        if tags.is_empty() {
+            return;
+        }
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.local.lock().unwrap().remove_tagged(&tags);
+
+        let tag_keys: Vec<String> = tags
+            .iter()
+            .map(|tag| format!("{TAG_PREFIX}{tag}"))
+            .collect();
+        let mut redis = self.redis.clone();
+        let mut query_ids: BTreeSet<String> = BTreeSet::new();
+        for batch in tag_keys.chunks(SUNION_BATCH) {
+            match redis.sunion::<_, Vec<String>>(batch).await {
+                Ok(ids) => query_ids.extend(ids),
+                Err(e) => {
+                    warn!("Failed to look up stale canvas cache entries: {}", e);
+                    return;
+                }
+            }
+        }
+        if query_ids.is_empty() {
+            return;
+        }
+
+        let mut pipe = redis::pipe();
+        for query_id in &query_ids {
+            pipe.del(format!("{ENTRY_PREFIX}{query_id}")).ignore();
+        }
+        for batch in tag_keys.chunks(SUNION_BATCH) {
+            pipe.del(batch).ignore();
+        }
+        match pipe.query_async::<()>(&mut redis).await {
+            Ok(()) => debug!("Dropped {} stale canvas cache entries", query_ids.len()),
+            Err(e) => warn!("Failed to drop stale canvas cache entries: {}", e),
+        }
+    }
+
+    async fn watch(self: Arc<Self>, dispatcher: Arc<DeltaDispatcher>) {
+        let cache = &*self;
+        // Whatever was missed can only be caught by the TTL; at least the entries
+        // of this process are not served any longer
+        dispatcher
+            .follow_feed(
+                "Canvas result cache",
+                |changes| async move { cache.invalidate(&changes).await },
+                || cache.forget_local(),
+            )
+            .await
+    }
+
+    fn forget_local(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.local.lock().unwrap().clear();
+    }
+}
+
+impl CachedResult {
+    fn to_result(&self) -> GatewayQueryResult {
+        GatewayQueryResult {
+            metrics: QueryMetrics {
+                elapsed_ms: self.elapsed_ms,
+                result_count: self.result_count,
+            },
+            raw_response: self.raw_response.clone(),
+            summary: QuerySummary {
+                query_type: QueryType::Read,
+                counters: QueryCounters::default(),
+                notifications: Vec::new(),
+                plan: None,
+            },
+        }
+    }
+}
+
+/// Least recently used entries of this process in front of Redis
+struct LocalCache {
+    capacity: usize,
+    entries: HashMap<String, LocalEntry>,
+    /// Query IDs by last use
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+struct LocalEntry {
+    result: Arc<CachedResult>,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+impl LocalCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, query_id: &str) -> Option<Arc<CachedResult>> {
+        let entry = self.entries.get_mut(query_id)?;
+        if entry.expires_at <= Instant::now() {
+            self.remove(query_id);
+            return None;
+        }
+        self.clock += 1;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = self.clock;
+        self.recency.insert(self.clock, query_id.to_string());
+        Some(entry.result.clone())
+    }
+
+    fn insert(&mut self, query_id: String, result: Arc<CachedResult>, expires_at: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(&query_id);
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, query_id.clone());
+        self.entries.insert(
+            query_id,
+            LocalEntry {
+                result,
+                expires_at,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    fn remove(&mut self, query_id: &str) {
+        if let Some(entry) = self.entries.remove(query_id) {
+            self.recency.remove(&entry.last_used);
+        }
+    }
+
+    fn remove_tagged(&mut self, tags: &BTreeSet<String>) {
+        let stale: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.result.tags.is_disjoint(tags))
+            .map(|(query_id, _)| query_id.clone())
+            .collect();
+        for query_id in stale {
+            self.remove(&query_id);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+/// GUIDs of the nodes and relationships in a result, their labels and types
+fn result_tags(raw_response: &Value) -> BTreeSet<String> {
+    let mut harvester = CanvasHarvester::default();
+    let mut tags = BTreeSet::new();
+    let rows = raw_response["results"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for row in rows {
+        let (nodes, edges) = harvester.harvest(row);
+        for node in nodes {
+            tags.extend(node.labels.iter().map(|label| format!("label:{label}")));
+            tags.insert(format!("guid:{}", node.GUID));
+        }
+        for edge in edges {
+            tags.insert(format!("type:{}", edge.r#type));
+            tags.insert(format!("guid:{}", edge.GUID));
+        }
+    }
+    tags
+}
+
+/// Tags of the entries a change may affect, see [`ResultCache`]
+fn change_tags(changes: &GraphChanges) -> BTreeSet<String> {
+    let mut tags = BTreeSet::new();
+    if changes.resync_required {
+        tags.insert(ANY_TAG.to_string());
+    }
+    for node in changes.nodes_created.iter().chain(&changes.nodes_updated) {
+        tags.insert(format!("guid:{}", node.GUID));
+        tags.extend(node.labels.iter().map(|label| format!("label:{label}")));
+    }
+    for rel in changes
+        .relationships_created
+        .iter()
+        .chain(&changes.relationships_updated)
+    {
+        tags.insert(format!("guid:{}", rel.GUID));
+        tags.insert(format!("type:{}", rel.r#type));
+    }
+    for guid in changes
+        .nodes_deleted
+        .iter()
+        .chain(&changes.relationships_deleted)
+    {
+        tags.insert(format!("guid:{guid}"));
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn cached(tags: &[&str]) -> Arc<CachedResult> {
+        Arc::new(CachedResult {
+            raw_response: json!({ "results": [] }),
+            elapsed_ms: 0,
+            result_count: 0,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let expires_at = Instant::now() + Duration::from_secs(60);
+        let mut local = LocalCache::new(2);
+        local.insert("a".to_string(), cached(&[]), expires_at);
+        local.insert("b".to_string(), cached(&[]), expires_at);
+        assert!(local.get("a").is_some());
+        local.insert("c".to_string(), cached(&[]), expires_at);
+
+        assert!(local.get("a").is_some());
+        assert!(local.get("b").is_none());
+        assert!(local.get("c").is_some());
+    }
+
+    #[test]
+    fn drops_expired_and_tagged_entries() {
+        let mut local = LocalCache::new(4);
+        local.insert("old".to_string(), cached(&[]), Instant::now());
+        assert!(local.get("old").is_none());
+
+        let expires_at = Instant::now() + Duration::from_secs(60);
+        local.insert(
+            "services".to_string(),
+            cached(&["label:Service", "guid:s1"]),
+            expires_at,
+        );
+        local.insert(
+            "teams".to_string(),
+            cached(&["label:Team", "guid:t1"]),
+            expires_at,
+        );
+        local.remove_tagged(&BTreeSet::from(["guid:s1".to_string()]));
+
+        assert!(local.get("services").is_none());
+        assert!(local.get("teams").is_some());
+    }
+
+    #[test]
+    fn tags_results_and_changes() {
+        let node = json!({ "labels": ["Service"], "properties": { "GUID": "s1" } });
+        let edge = json!({
+            "type": "CALLS",
+            "properties": { "GUID": "r1", "fromGUID": "s1", "toGUID": "s2" },
+        });
+        let tags = result_tags(&json!({ "results": [{ "n": node, "r": edge }] }));
+        assert_eq!(
+            tags.into_iter().collect::<Vec<_>>(),
+            vec!["guid:r1", "guid:s1", "label:Service", "type:CALLS"]
+        );
+
+        let changes: GraphChanges = serde_json::from_value(json!({
+            "timestamp": 0,
+            "nodesCreated": [{ "GUID": "s3", "labels": ["Service"] }],
+            "nodesDeleted": ["t1"],
+            "relationshipsUpdated": [{ "GUID": "r1", "fromGUID": "s1", "toGUID": "s2", "type": "CALLS" }],
+        }))
+        .unwrap();
+        assert_eq!(
+            change_tags(&changes).into_iter().collect::<Vec<_>>(),
+            vec![
+                "guid:r1",
+                "guid:s3",
+                "guid:t1",
+                "label:Service",
+                "type:CALLS"
+            ]
+        );
+
+        let changes: GraphChanges = serde_json::from_value(json!({
+            "timestamp": 0,
+            "resyncRequired": true,
+        }))
+        .unwrap();
+        assert_eq!(
+            change_tags(&changes).into_iter().collect::<Vec<_>>(),
+            vec![ANY_TAG]
+        );
+    }
+}
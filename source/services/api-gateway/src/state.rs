@@ -1,9 +1,12 @@
 use crate::config::Config;
 use crate::crypto::CryptoService;
 use crate::database::neo4j_gateway::Neo4jGateway;
+use crate::database::schema::SchemaCache;
+use crate::database::stored_queries::StoredQueryRegistry;
 use crate::email::EmailService;
-use crate::graph_events::GraphDeltaPublisher;
+use crate::graph_events::{DeltaDispatcher, GraphDeltaPublisher};
 use crate::logging::CentralLogger;
+use crate::runtime::result_cache::ResultCache;
 use crate::security_metrics::SecurityMonitor;
 use crate::websocket::UpdateChannel;
 use kalisi_core::auth::JwtAuth;
@@ -16,6 +19,9 @@ pub struct AppState {
     pub config: Arc<Config>,
     pub redis: MultiplexedConnection,
     pub neo4j: Arc<Neo4jGateway>,
+    pub stored_queries: Arc<StoredQueryRegistry>,
+    pub schema_cache: Arc<SchemaCache>,
+    pub result_cache: Arc<ResultCache>,
     pub jwt_auth: Arc<JwtAuth>,
     pub email_service: Arc<EmailService>,
     #[allow(dead_code)]
@@ -24,12 +30,20 @@ pub struct AppState {
     pub update_channel: UpdateChannel,
     pub logger: CentralLogger,
     pub graph_delta_publisher: Arc<Mutex<GraphDeltaPublisher>>,
+    pub graph_delta_dispatcher: Arc<DeltaDispatcher>,
 }
 
 impl AppState {
     pub async fn new(config: Config) -> anyhow::Result<Self> {
         let neo4j_gateway = Arc::new(Neo4jGateway::new(&config).await?);
 
+        // Load the stored query allow-list
+        let mut stored_queries =
+            StoredQueryRegistry::load_dir(std::path::Path::new(&config.stored_queries_dir))?;
+        if config.stored_queries_from_neo4j {
+            stored_queries.load_neo4j(&neo4j_gateway).await?;
+        }
+
         // Initialize Redis connection
         let redis_client = redis::Client::open(config.redis_url.clone())?;
         let redis = redis_client.get_multiplexed_async_connection().await?;
@@ -63,13 +77,32 @@ impl AppState {
 
         // Initialize graph delta publisher (feature-flagged via ENABLE_GRAPH_DELTA)
         let graph_delta_publisher = Arc::new(Mutex::new(
-            GraphDeltaPublisher::new(&config.redis_url).await?,
+            GraphDeltaPublisher::new(&config.redis_url, config.graph_deltas.stream_maxlen).await?,
         ));
 
+        // Shared reader of the per-ViewNode delta streams for WebSocket subscribers
+        let graph_delta_dispatcher = DeltaDispatcher::start(&config.redis_url).await?;
+
+        // Graph schema for autocompletion, dropped whenever the graph changes
+        let schema_cache = Arc::new(SchemaCache::new(config.schema_cache_ttl));
+        schema_cache.clone().watch(graph_delta_dispatcher.clone());
+
+        // Read-only canvas results, dropped when the graph changes under them
+        let result_cache = ResultCache::start(
+            &config.redis_url,
+            config.canvas_cache_ttl,
+            config.canvas_cache_local_entries,
+            graph_delta_dispatcher.clone(),
+        )
+        .await?;
+
         Ok(Self {
             config: config.clone(),
             redis,
             neo4j: neo4j_gateway,
+            stored_queries: Arc::new(stored_queries),
+            schema_cache,
+            result_cache,
             jwt_auth,
             email_service,
             crypto_service,
@@ -77,6 +110,7 @@ impl AppState {
             update_channel,
             logger,
             graph_delta_publisher,
+            graph_delta_dispatcher,
         })
     }
 
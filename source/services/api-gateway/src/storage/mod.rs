@@ -2,9 +2,11 @@ pub mod auth_event;
 pub mod otp;
 pub mod session;
 pub mod user;
+pub mod view_state;
 // pub mod encrypted_user;
 
 pub use otp::{OtpPurpose, OtpStorage};
 pub use session::SessionStorage;
 pub use user::UserStorage;
+pub use view_state::ViewStateStorage;
 // pub use encrypted_user::EncryptedUserStorage;
@@ -0,0 +1,139 @@
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::runtime::dto::NodePosition;
+
+/// Stores the state only if its version is still the one the client saw.
+/// Returns `{1, new version}` when saved, `{0, current version}` otherwise.
+const SAVE_SCRIPT: &str = r#"
+local version = tonumber(redis.call('HGET', KEYS[1], 'version') or '0')
+if version ~= tonumber(ARGV[1]) then
+    return {0, version}
+end
+redis.call('HSET', KEYS[1], 'version', version + 1, 'state', ARGV[2])
+return {1, version + 1}
+"#;
+
+/// What a user has made of a ViewNode: where they dragged nodes, which containers
+/// they collapsed and where they left the camera. The graph itself is not stored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ViewState {
+    #[serde(default)]
+    pub view_node_id: String,
+    /// Bumped by every save, 0 while nothing has been saved
+    #[serde(default)]
+    pub version: u64,
+    /// Node positions by GUID, relative to the parent like `CanvasNodeDto.position`
+    #[serde(default)]
+    pub positions: HashMap<String, NodePosition>,
+    /// GUIDs of collapsed containers
+    #[serde(default)]
+    pub collapsed: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub viewport: Option<Viewport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Viewport {
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+}
+
+/// ViewNode IDs end up in Redis keys, of saved view states as well as of delta
+/// streams. Keeping them to the characters GUIDs use keeps the keys inside their
+/// namespace.
+pub fn is_view_node_id(view_node_id: &str) -> bool {
+    !view_node_id.is_empty()
+        && view_node_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub enum SaveOutcome {
+    Saved(ViewState),
+    /// Someone saved in between; holds the state that was kept
+    Conflict(ViewState),
+}
+
+pub struct ViewStateStorage {
+    redis: redis::aio::MultiplexedConnection,
+}
+
+impl ViewStateStorage {
+    pub fn new(redis: redis::aio::MultiplexedConnection) -> Self {
+        Self { redis }
+    }
+
+    fn key(user_id: Uuid, view_node_id: &str) -> String {
+        format!("view_state:{}:{}", user_id, view_node_id)
+    }
+
+    /// The saved state, or an empty one at version 0
+    pub async fn load(&mut self, user_id: Uuid, view_node_id: &str) -> Result<ViewState> {
+        let key = Self::key(user_id, view_node_id);
+        let (version, state): (Option<u64>, Option<String>) = redis::cmd("HMGET")
+            .arg(&key)
+            .arg("version")
+            .arg("state")
+            .query_async(&mut self.redis)
+            .await?;
+
+        let mut view_state = match state {
+            Some(json_str) => serde_json::from_str(&json_str)?,
+            None => ViewState::default(),
+        };
+        view_state.view_node_id = view_node_id.to_string();
+        view_state.version = version.unwrap_or(0);
+        Ok(view_state)
+    }
+
+    /// Save `state` over version `state.version`
+    pub async fn save(&mut self, user_id: Uuid, mut state: ViewState) -> Result<SaveOutcome> {
+        let key = Self::key(user_id, &state.view_node_id);
+        let expected = state.version;
+        state.updated_at = Some(Utc::now());
+        let value = serde_json::to_string(&state)?;
+
+        let (saved, version): (u8, u64) = redis::Script::new(SAVE_SCRIPT)
+            .key(&key)
+            .arg(expected)
+            .arg(value)
+            .invoke_async(&mut self.redis)
+            .await?;
+
+        if saved == 1 {
+            state.version = version;
+            Ok(SaveOutcome::Saved(state))
+        } else {
+            let current = self.load(user_id, &state.view_node_id).await?;
+            Ok(SaveOutcome::Conflict(current))
+        }
+    }
+
+    pub async fn delete(&mut self, user_id: Uuid, view_node_id: &str) -> Result<()> {
+        let key = Self::key(user_id, view_node_id);
+        self.redis.del::<_, ()>(&key).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_node_ids_stay_inside_their_key_namespace() {
+        assert!(is_view_node_id("3f2b-view_1"));
+        for id in ["", "a:b", "a*", "../a", "a b"] {
+            assert!(!is_view_node_id(id), "{id}");
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::ws::{CloseFrame, Message, WebSocket},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::time::{interval_at, timeout, Duration, Instant, Interval, MissedTickBehavior};
+use tracing::{info, warn};
+
+use crate::middleware::auth::{extract_token, verify_session, AuthUser};
+use crate::storage::view_state::is_view_node_id;
+use crate::AppState;
+
+/// Close code sent when a socket fails to authenticate or its session ends
+pub const CLOSE_UNAUTHORIZED: u16 = 4401;
+
+/// Time a socket without credentials at the upgrade has to send its `auth` frame
+const AUTH_FRAME_TIMEOUT: Duration = Duration::from_secs(10);
+
+const VIEW_NODE_QUERY: &str = "MATCH (vn:ViewNode {GUID: $guid}) RETURN count(vn) AS views";
+
+/// Browsers cannot set headers on a WebSocket handshake, so the token may also be
+/// passed as `?token=`
+#[derive(Debug, Default, Deserialize)]
+pub struct WsAuthQuery {
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AuthFrame {
+    #[serde(rename = "type")]
+    kind: String,
+    token: String,
+}
+
+impl AuthFrame {
+    /// Token of a `{"type": "auth", "token": "..."}` frame
+    fn token(text: &str) -> Option<String> {
+        serde_json::from_str::<AuthFrame>(text)
+            .ok()
+            .filter(|frame| frame.kind == "auth")
+            .map(|frame| frame.token)
+    }
+}
+
+/// How a socket authenticates, decided at the upgrade
+pub enum PendingAuth {
+    Verified(WsSession),
+    /// No credentials came with the upgrade; the first frame has to carry them
+    FirstFrame,
+}
+
+impl PendingAuth {
+    /// Check the token of the upgrade request: the Authorization header, the
+    /// `token` cookie or the `token` query parameter. An invalid token rejects the
+    /// upgrade.
+    pub async fn from_request(
+        state: &AppState,
+        headers: &HeaderMap,
+        query: WsAuthQuery,
+    ) -> Result<Self, Response> {
+        match extract_token(headers).or(query.token) {
+            Some(token) => WsSession::verify(state, token)
+                .await
+                .map(PendingAuth::Verified)
+                .map_err(|message| (StatusCode::UNAUTHORIZED, message).into_response()),
+            None => Ok(PendingAuth::FirstFrame),
+        }
+    }
+
+    /// Finish authentication on the open socket, reading a
+    /// `{"type": "auth", "token": "..."}` frame if needed. Closes the socket when
+    /// that fails.
+    pub async fn complete(self, socket: &mut WebSocket, state: &AppState) -> Option<WsSession> {
+        let token = match self {
+            PendingAuth::Verified(session) => return Some(session),
+            PendingAuth::FirstFrame => timeout(AUTH_FRAME_TIMEOUT, socket.recv()).await,
+        };
+
+        let token = match token {
+            Ok(Some(Ok(Message::Text(text)))) => AuthFrame::token(&text),
+            _ => None,
+        };
+        let result = match token {
+            Some(token) => WsSession::verify(state, token).await,
+            None => Err("Authentication required"),
+        };
+
+        match result {
+            Ok(session) => {
+                let reply = json!({ "type": "authenticated", "userId": session.user.user_id });
+                let _ = socket.send(Message::Text(reply.to_string().into())).await;
+                Some(session)
+            }
+            Err(message) => {
+                close_unauthorized(socket, message).await;
+                None
+            }
+        }
+    }
+}
+
+/// The user behind an authenticated socket. The session is re-checked while the
+/// socket stays open, see [`revalidate_interval`].
+pub struct WsSession {
+    pub user: AuthUser,
+    token: String,
+}
+
+impl WsSession {
+    async fn verify(state: &AppState, token: String) -> Result<Self, &'static str> {
+        let claims = verify_session(state, &token).await?;
+        info!("WebSocket authenticated for {}", claims.email);
+        Ok(Self {
+            user: AuthUser::from(claims),
+            token,
+        })
+    }
+
+    /// Fails once the token has expired or the session was revoked by a logout
+    pub async fn revalidate(&self, state: &AppState) -> Result<(), &'static str> {
+        verify_session(state, &self.token).await.map(|_| ())
+    }
+
+    /// Whether the user may follow the deltas of `view_node_id`. ViewNodes are not
+    /// owned by users and every role may read them, so this only checks that the ID
+    /// is well formed and names an existing ViewNode.
+    pub async fn may_follow_view_node(&self, state: &AppState, view_node_id: &str) -> bool {
+        if !is_view_node_id(view_node_id) {
+            return false;
+        }
+
+        let parameters = HashMap::from([("guid".to_string(), json!(view_node_id))]);
+        match state
+            .neo4j
+            .execute(
+                "ws-view-node-scope",
+                VIEW_NODE_QUERY,
+                &parameters,
+                state.config.cypher_roles.access_for(&self.user.role),
+            )
+            .await
+        {
+            Ok(result) => result.raw_response["results"][0]["views"]
+                .as_i64()
+                .is_some_and(|views| views > 0),
+            Err(e) => {
+                warn!("Failed to look up ViewNode {}: {}", view_node_id, e);
+                false
+            }
+        }
+    }
+}
+
+/// Ticks whenever open sockets should re-check their session, every `period`
+pub fn revalidate_interval(period: Duration) -> Interval {
+    let mut interval = interval_at(Instant::now() + period, period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval
+}
+
+pub async fn close_unauthorized(socket: &mut WebSocket, reason: &str) {
+    let frame = CloseFrame {
+        code: CLOSE_UNAUTHORIZED,
+        reason: reason.into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_token_of_auth_frames() {
+        assert_eq!(
+            AuthFrame::token(r#"{"type": "auth", "token": "abc"}"#).as_deref(),
+            Some("abc")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_and_other_frames() {
+        for text in [
+            "",
+            "abc",
+            r#"{"type": "auth""#,
+            r#"{"type": "auth"}"#,
+            r#"{"type": "auth", "token": 42}"#,
+            r#"{"token": "abc"}"#,
+            r#"{"type": "subscribe", "token": "abc"}"#,
+            r#"{"type": "AUTH", "token": "abc"}"#,
+        ] {
+            assert_eq!(AuthFrame::token(text), None, "{text}");
+        }
+    }
+}
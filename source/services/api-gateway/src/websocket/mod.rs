@@ -0,0 +1,310 @@
+pub mod auth;
+mod protocol;
+mod subscriptions;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::HeaderMap,
+    response::Response,
+};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tokio::time::{interval, sleep_until, Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+use crate::logging::{LogCategory, LogLevel};
+use crate::AppState;
+
+use auth::{PendingAuth, WsAuthQuery, WsSession};
+use protocol::{ClientMessage, ErrorCode, ServerMessage, PROTOCOL_VERSION};
+pub use subscriptions::DeltaCoalescing;
+use subscriptions::{GraphSubscriptions, MAX_SUBSCRIPTIONS};
+
+/// WebSocket connection handler for real-time updates
+pub async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<WsAuthQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let pending = match PendingAuth::from_request(&state, &headers, query).await {
+        Ok(pending) => pending,
+        Err(rejection) => return rejection,
+    };
+
+    ws.on_upgrade(|mut socket| async move {
+        if let Some(session) = pending.complete(&mut socket, &state).await {
+            handle_socket(socket, state, session).await;
+        }
+    })
+}
+
+/// Handle individual WebSocket connection
+async fn handle_socket(mut socket: WebSocket, state: AppState, session: WsSession) {
+    // Create a broadcast receiver for updates
+    let mut update_rx = state.update_channel.subscribe();
+
+    // Graph delta subscriptions, one per followed ViewNode
+    let mut graph_subscriptions = GraphSubscriptions::new(state.config.delta_coalescing);
+
+    // Send initial security metrics
+    if let Ok(monitor) = state.security_monitor.try_read() {
+        let message = ServerMessage::SecurityUpdate {
+            data: monitor.get_dashboard_data().await,
+            timestamp: chrono::Utc::now(),
+        };
+        let _ = socket.send(Message::Text(message.to_text().into())).await;
+    }
+
+    // Set up periodic updates (every 10 seconds to reduce flooding)
+    let mut interval = interval(Duration::from_secs(10));
+
+    // Close the socket once the session expires or is revoked
+    let mut revalidate = auth::revalidate_interval(state.config.ws_session_check_interval);
+
+    'connection: loop {
+        let flush_at = graph_subscriptions.due_at();
+
+        tokio::select! {
+
+            // Handle incoming messages from client
+            Some(msg) = socket.recv() => {
+                match msg {
+                    Ok(Message::Text(text)) => {
+                        // Handle legacy plain text messages
+                        if text.as_str() == "ping" {
+                            let _ = socket.send(Message::Text("pong".into())).await;
+                            continue;
+                        }
+
+                        let replies = match ClientMessage::parse(&text) {
+                            Ok(message) => handle_client_message(message, &state, &session, &mut graph_subscriptions).await,
+                            Err(error) => {
+                                debug!("Rejecting WebSocket message: {:?}", error);
+                                vec![error]
+                            }
+                        };
+                        for reply in replies {
+                            if socket.send(Message::Text(reply.to_text().into())).await.is_err() {
+                                break 'connection;
+                            }
+                        }
+                    }
+                    Ok(Message::Close(_)) => {
+                        info!("WebSocket connection closed by client");
+
+                        // Log WebSocket close event
+                        let mut context = HashMap::new();
+                        context.insert("event".to_string(), serde_json::Value::String("client_close".to_string()));
+                        state.logger.info(
+                            LogCategory::WebSocket,
+                            "WebSocket connection closed by client",
+                            context
+                        ).await;
+                        break;
+                    }
+                    Ok(_) => {},
+                    Err(e) => {
+                        error!("WebSocket error: {}", e);
+
+                        // Log WebSocket error
+                        let mut error_context = HashMap::new();
+                        error_context.insert("error_type".to_string(), serde_json::Value::String("receive_error".to_string()));
+                        error_context.insert("error_message".to_string(), serde_json::Value::String(e.to_string()));
+
+                        state.logger.log_with_context(
+                            LogLevel::Error,
+                            LogCategory::WebSocket,
+                            &format!("WebSocket receive error: {}", e),
+                            error_context
+                        ).await;
+                        break;
+                    }
+                }
+            }
+
+            _ = revalidate.tick() => {
+                if let Err(reason) = session.revalidate(&state).await {
+                    warn!("Closing WebSocket of {}: {}", session.user.email, reason);
+                    auth::close_unauthorized(&mut socket, reason).await;
+                    break;
+                }
+            }
+
+            // Send periodic updates
+            _ = interval.tick() => {
+                if let Ok(monitor) = state.security_monitor.try_read() {
+                    let message = ServerMessage::SecurityUpdate {
+                        data: monitor.get_dashboard_data().await,
+                        timestamp: chrono::Utc::now(),
+                    };
+
+                    if let Err(e) = socket.send(Message::Text(message.to_text().into())).await {
+                        error!("Failed to send WebSocket message: {}", e);
+
+                        // Log WebSocket send error
+                        let mut context = HashMap::new();
+                        context.insert("error_type".to_string(), serde_json::Value::String("send_error".to_string()));
+                        context.insert("error_message".to_string(), serde_json::Value::String(e.to_string()));
+                        state.logger.error(
+                            LogCategory::WebSocket,
+                            &format!("Failed to send periodic update: {}", e),
+                            context
+                        ).await;
+                        break;
+                    }
+                }
+            }
+
+            // Handle broadcast updates (instant updates for events)
+            Ok(update) = update_rx.recv() => {
+                if let Err(e) = socket.send(Message::Text(update.into())).await {
+                    error!("Failed to send broadcast update: {}", e);
+
+                    // Log WebSocket broadcast error
+                    let mut context = HashMap::new();
+                    context.insert("error_type".to_string(), serde_json::Value::String("broadcast_error".to_string()));
+                    context.insert("error_message".to_string(), serde_json::Value::String(e.to_string()));
+                    state.logger.error(
+                        LogCategory::WebSocket,
+                        &format!("Failed to send broadcast update: {}", e),
+                        context
+                    ).await;
+                    break;
+                }
+            }
+
+            // Queue graph deltas of the subscribed ViewNodes
+            resync = graph_subscriptions.receive() => {
+                if let Some(resync) = resync {
+                    if socket.send(Message::Text(resync.to_text().into())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+
+            // Send the deltas merged within the coalescing window
+            _ = sleep_until(flush_at.unwrap_or_else(Instant::now)), if flush_at.is_some() => {
+                for delta in graph_subscriptions.take_due() {
+                    debug!("Sending coalesced graph delta to client");
+                    if let Err(e) = socket.send(Message::Text(delta.into())).await {
+                        error!("Failed to send graph delta: {}", e);
+                        break 'connection;
+                    }
+                }
+            }
+        }
+    }
+
+    info!("WebSocket connection closed");
+
+    // Log WebSocket disconnection
+    let mut context = HashMap::new();
+    context.insert(
+        "event".to_string(),
+        serde_json::Value::String("connection_closed".to_string()),
+    );
+    state
+        .logger
+        .info(
+            LogCategory::WebSocket,
+            "WebSocket connection closed",
+            context,
+        )
+        .await;
+}
+
+/// Act on one client message, returning the replies
+async fn handle_client_message(
+    message: ClientMessage,
+    state: &AppState,
+    session: &WsSession,
+    graph_subscriptions: &mut GraphSubscriptions,
+) -> Vec<ServerMessage> {
+    match message {
+        // Credentials already came with the upgrade
+        ClientMessage::Auth { .. } => Vec::new(),
+        ClientMessage::Hello { version } if version == PROTOCOL_VERSION => {
+            vec![ServerMessage::Welcome {
+                version: PROTOCOL_VERSION,
+                max_subscriptions: MAX_SUBSCRIPTIONS,
+            }]
+        }
+        ClientMessage::Hello { version } => vec![ServerMessage::error(
+            ErrorCode::UnsupportedVersion,
+            format!("protocol version {version} is not supported, use {PROTOCOL_VERSION}"),
+        )],
+        ClientMessage::Ping => vec![ServerMessage::Pong {
+            timestamp: chrono::Utc::now(),
+        }],
+        ClientMessage::ConsoleLog { level, message } => {
+            handle_console_log(level.as_deref(), &message);
+            Vec::new()
+        }
+        ClientMessage::SubscribeGraphChanges {
+            view_node_id,
+            last_stream_id,
+        } => {
+            info!("Client subscribing to graph changes for ViewNode: {}", view_node_id);
+            if !session.may_follow_view_node(state, &view_node_id).await {
+                return vec![ServerMessage::error(
+                    ErrorCode::Forbidden,
+                    format!("not permitted to follow ViewNode {view_node_id}"),
+                )];
+            }
+            graph_subscriptions
+                .subscribe(
+                    &state.graph_delta_dispatcher,
+                    &view_node_id,
+                    last_stream_id.as_deref(),
+                )
+                .await
+        }
+        ClientMessage::UnsubscribeGraphChanges { view_node_id } => {
+            info!("Client unsubscribing from graph changes for ViewNode: {}", view_node_id);
+            vec![graph_subscriptions.unsubscribe(&view_node_id)]
+        }
+    }
+}
+
+/// Handle console log messages from browser
+fn handle_console_log(level: Option<&str>, message: &str) {
+    // Write to stderr which goes to gateway-debug.log
+    match level {
+        Some(level) => eprintln!("[BROWSER:{}] {}", level, message),
+        None => eprintln!("[BROWSER] {}", message),
+    }
+}
+
+/// Broadcast channel for instant updates
+#[derive(Clone)]
+pub struct UpdateChannel {
+    tx: broadcast::Sender<String>,
+}
+
+impl UpdateChannel {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(100);
+        Self { tx }
+    }
+
+    #[allow(dead_code)]
+    pub fn send_update(&self, update: serde_json::Value) {
+        if let Ok(text) = serde_json::to_string(&update) {
+            let _ = self.tx.send(text);
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for UpdateChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version of the message schema below. Clients announce theirs with `hello`;
+/// clients that skip the handshake are assumed to speak version 1.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Messages a client sends on `/ws`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Credentials for sockets that did not authenticate at the upgrade, only
+    /// accepted as the first frame
+    Auth {
+        #[allow(dead_code)]
+        token: String,
+    },
+    Hello {
+        version: u32,
+    },
+    Ping,
+    /// Browser console output forwarded to the gateway log
+    ConsoleLog {
+        #[serde(default)]
+        level: Option<String>,
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    SubscribeGraphChanges {
+        view_node_id: String,
+        /// Stream ID of the last delta the client received, to resume after it
+        #[serde(default)]
+        last_stream_id: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UnsubscribeGraphChanges {
+        view_node_id: String,
+    },
+}
+
+impl ClientMessage {
+    /// Parse a text frame, telling malformed JSON apart from messages that do not
+    /// fit the schema
+    pub fn parse(text: &str) -> Result<Self, ServerMessage> {
+        let value: Value = serde_json::from_str(text)
+            .map_err(|e| ServerMessage::error(ErrorCode::InvalidJson, e.to_string()))?;
+        serde_json::from_value(value)
+            .map_err(|e| ServerMessage::error(ErrorCode::InvalidMessage, e.to_string()))
+    }
+}
+
+/// Messages the gateway sends on `/ws`. Graph deltas are sent as serialized
+/// [`GraphDelta`](crate::graph_events::GraphDelta)s with type `graph_delta`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    #[serde(rename_all = "camelCase")]
+    Welcome {
+        version: u32,
+        /// ViewNodes a socket may follow at the same time
+        max_subscriptions: usize,
+    },
+    Pong {
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+    SecurityUpdate {
+        data: Value,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+    #[serde(rename_all = "camelCase")]
+    GraphSubscriptionAck {
+        view_node_id: String,
+        /// Missed deltas are replayed after the last-seen stream ID
+        resumed: bool,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+    #[serde(rename_all = "camelCase")]
+    GraphUnsubscribed {
+        view_node_id: String,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+    /// Deltas were missed and the view has to be reloaded
+    #[serde(rename_all = "camelCase")]
+    GraphResyncRequired {
+        view_node_id: String,
+        reason: ResyncReason,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+    #[serde(rename_all = "camelCase")]
+    GraphSubscriptionError {
+        view_node_id: String,
+        error: String,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+    /// The client sent something the gateway could not act on
+    Error { code: ErrorCode, message: String },
+}
+
+impl ServerMessage {
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        ServerMessage::Error {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn resync(view_node_id: &str, reason: ResyncReason) -> Self {
+        ServerMessage::GraphResyncRequired {
+            view_node_id: view_node_id.to_string(),
+            reason,
+            timestamp: chrono::Utc::now(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        serde_json::to_string(self).expect("server messages serialize")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidJson,
+    InvalidMessage,
+    UnsupportedVersion,
+    NotSubscribed,
+    TooManySubscriptions,
+    /// The user may not follow the requested ViewNode
+    Forbidden,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResyncReason {
+    /// The last-seen delta is no longer in the stream
+    Trimmed,
+    /// More deltas were pending than the client may queue
+    Overflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_client_messages() {
+        let message = ClientMessage::parse(
+            r#"{"type": "subscribe_graph_changes", "viewNodeId": "view-1", "lastStreamId": "1-0"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            message,
+            ClientMessage::SubscribeGraphChanges { view_node_id, last_stream_id }
+                if view_node_id == "view-1" && last_stream_id.as_deref() == Some("1-0")
+        ));
+
+        // Extra fields, as sent by the console logger, are ignored
+        let message = ClientMessage::parse(
+            r#"{"type": "console_log", "level": "warn", "message": "x", "url": "/app"}"#,
+        )
+        .unwrap();
+        assert!(matches!(message, ClientMessage::ConsoleLog { .. }));
+    }
+
+    #[test]
+    fn reports_bad_input() {
+        let code = |text: &str| match ClientMessage::parse(text) {
+            Err(ServerMessage::Error { code, .. }) => code,
+            other => panic!("expected an error frame, got {other:?}"),
+        };
+
+        assert_eq!(code("{not json"), ErrorCode::InvalidJson);
+        assert_eq!(code(r#"{"type": "shout"}"#), ErrorCode::InvalidMessage);
+        assert_eq!(
+            code(r#"{"type": "unsubscribe_graph_changes"}"#),
+            ErrorCode::InvalidMessage
+        );
+    }
+
+    #[test]
+    fn serializes_server_messages() {
+        let json: Value = serde_json::from_str(
+            &ServerMessage::resync("view-1", ResyncReason::Overflow).to_text(),
+        )
+        .unwrap();
+
+        assert_eq!(json["type"], "graph_resync_required");
+        assert_eq!(json["viewNodeId"], "view-1");
+        assert_eq!(json["reason"], "overflow");
+    }
+}
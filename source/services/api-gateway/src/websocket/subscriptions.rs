@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use crate::graph_events::{DeltaDispatcher, DeltaMessage, DeltaSubscription, GraphDelta};
+
+use super::protocol::{ErrorCode, ResyncReason, ServerMessage};
+
+/// ViewNodes one socket may follow at the same time
+pub const MAX_SUBSCRIPTIONS: usize = 16;
+
+/// Deltas of all subscriptions queued ahead of the socket loop
+const FORWARD_BUFFER: usize = 64;
+
+enum DeltaEvent {
+    Delta(DeltaMessage),
+    /// The subscription fell behind and deltas were dropped
+    Lagged(u64),
+}
+
+struct Forwarded {
+    view_node_id: String,
+    generation: u64,
+    event: DeltaEvent,
+}
+
+struct ViewSubscription {
+    /// Tells deltas of this subscription apart from those of a replaced one
+    generation: u64,
+    task: JoinHandle<()>,
+    coalescer: DeltaCoalescer,
+}
+
+impl Drop for ViewSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// The graph delta subscriptions of one socket. Each subscription is read by a
+/// task of its own, which forwards into a channel shared by the socket.
+pub struct GraphSubscriptions {
+    coalescing: DeltaCoalescing,
+    views: HashMap<String, ViewSubscription>,
+    tx: mpsc::Sender<Forwarded>,
+    rx: mpsc::Receiver<Forwarded>,
+    next_generation: u64,
+}
+
+impl GraphSubscriptions {
+    pub fn new(coalescing: DeltaCoalescing) -> Self {
+        let (tx, rx) = mpsc::channel(FORWARD_BUFFER);
+        Self {
+            coalescing,
+            views: HashMap::new(),
+            tx,
+            rx,
+            next_generation: 0,
+        }
+    }
+
+    /// Subscribe to `view_node_id`, replacing an earlier subscription to it.
+    /// Returns the messages to send to the client.
+    pub async fn subscribe(
+        &mut self,
+        dispatcher: &DeltaDispatcher,
+        view_node_id: &str,
+        last_stream_id: Option<&str>,
+    ) -> Vec<ServerMessage> {
+        if !self.views.contains_key(view_node_id) && self.views.len() >= MAX_SUBSCRIPTIONS {
+            return vec![ServerMessage::error(
+                ErrorCode::TooManySubscriptions,
+                format!("at most {MAX_SUBSCRIPTIONS} ViewNodes can be followed per connection"),
+            )];
+        }
+
+        let mut subscription = match dispatcher.subscribe(view_node_id, last_stream_id).await {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                return vec![ServerMessage::GraphSubscriptionError {
+                    view_node_id: view_node_id.to_string(),
+                    error: e.to_string(),
+                    timestamp: chrono::Utc::now(),
+                }]
+            }
+        };
+
+        let mut messages = vec![ServerMessage::GraphSubscriptionAck {
+            view_node_id: view_node_id.to_string(),
+            resumed: last_stream_id.is_some() && !subscription.resync_required,
+            timestamp: chrono::Utc::now(),
+        }];
+        if subscription.resync_required {
+            messages.push(ServerMessage::resync(view_node_id, ResyncReason::Trimmed));
+        }
+
+        // Deltas published while the client was away
+        let mut coalescer = DeltaCoalescer::new(self.coalescing);
+        let replay = std::mem::take(&mut subscription.replay);
+        if !replay.iter().all(|delta| coalescer.push(delta)) {
+            messages.push(ServerMessage::resync(view_node_id, ResyncReason::Overflow));
+        }
+
+        self.next_generation += 1;
+        let generation = self.next_generation;
+        let task = tokio::spawn(forward(
+            view_node_id.to_string(),
+            generation,
+            subscription,
+            self.tx.clone(),
+        ));
+        self.views.insert(
+            view_node_id.to_string(),
+            ViewSubscription {
+                generation,
+                task,
+                coalescer,
+            },
+        );
+
+        messages
+    }
+
+    pub fn unsubscribe(&mut self, view_node_id: &str) -> ServerMessage {
+        match self.views.remove(view_node_id) {
+            Some(_) => ServerMessage::GraphUnsubscribed {
+                view_node_id: view_node_id.to_string(),
+                timestamp: chrono::Utc::now(),
+            },
+            None => ServerMessage::error(
+                ErrorCode::NotSubscribed,
+                format!("not subscribed to ViewNode {view_node_id}"),
+            ),
+        }
+    }
+
+    /// Wait for the next delta of any subscription and queue it. Returns a resync
+    /// request when the client has to reload a view instead.
+    pub async fn receive(&mut self) -> Option<ServerMessage> {
+        let forwarded = self.rx.recv().await?;
+        let view = self
+            .views
+            .get_mut(&forwarded.view_node_id)
+            .filter(|view| view.generation == forwarded.generation)?;
+
+        let overflow = match forwarded.event {
+            DeltaEvent::Delta(delta) => !view.coalescer.push(&delta),
+            DeltaEvent::Lagged(skipped) => {
+                warn!("Client fell {} deltas behind", skipped);
+                view.coalescer.clear();
+                true
+            }
+        };
+
+        // Dropped deltas cannot be recovered, the client reloads the view
+        overflow.then(|| {
+            warn!(
+                "Graph delta queue of ViewNode {} overflowed, requesting snapshot",
+                forwarded.view_node_id
+            );
+            ServerMessage::resync(&forwarded.view_node_id, ResyncReason::Overflow)
+        })
+    }
+
+    /// When the first coalesced delta is due, if any is pending
+    pub fn due_at(&self) -> Option<Instant> {
+        self.views
+            .values()
+            .filter_map(|view| view.coalescer.due_at())
+            .min()
+    }
+
+    /// Serialized deltas whose coalescing window has passed
+    pub fn take_due(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        self.views
+            .values_mut()
+            .filter(|view| view.coalescer.due_at().is_some_and(|due_at| due_at <= now))
+            .filter_map(|view| view.coalescer.take())
+            .collect()
+    }
+}
+
+async fn forward(
+    view_node_id: String,
+    generation: u64,
+    mut subscription: DeltaSubscription,
+    tx: mpsc::Sender<Forwarded>,
+) {
+    loop {
+        let event = match subscription.recv().await {
+            Ok(delta) => DeltaEvent::Delta(delta),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => DeltaEvent::Lagged(skipped),
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let forwarded = Forwarded {
+            view_node_id: view_node_id.clone(),
+            generation,
+            event,
+        };
+        if tx.send(forwarded).await.is_err() {
+            break;
+        }
+    }
+    debug!(
+        "Stopped forwarding graph deltas of ViewNode {}",
+        view_node_id
+    );
+}
+
+/// How the graph deltas a client receives are merged, see [`DeltaCoalescer`]
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct DeltaCoalescing {
+    /// Deltas arriving within this window are sent as one message
+    pub window: Duration,
+    /// Entities a client may have pending before it is sent a snapshot request instead
+    pub max_pending: usize,
+}
+
+impl Default for DeltaCoalescing {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(100),
+            max_pending: 5_000,
+        }
+    }
+}
+
+/// Merges the graph deltas a client receives within one window into a single
+/// message, so that bulk writes do not flood the browser
+struct DeltaCoalescer {
+    window: Duration,
+    max_pending: usize,
+    pending: Option<PendingDelta>,
+}
+
+struct PendingDelta {
+    delta: GraphDelta,
+    /// Entry of the last merged delta, for the client to resume after
+    stream_id: String,
+    flush_at: Instant,
+}
+
+impl DeltaCoalescer {
+    fn new(coalescing: DeltaCoalescing) -> Self {
+        Self {
+            window: coalescing.window,
+            max_pending: coalescing.max_pending,
+            pending: None,
+        }
+    }
+
+    /// Merge a delta into the pending one. Returns false when that exceeds
+    /// `max_pending`, in which case the pending deltas are dropped.
+    fn push(&mut self, message: &DeltaMessage) -> bool {
+        let delta: GraphDelta = match serde_json::from_str(&message.payload) {
+            Ok(delta) => delta,
+            Err(e) => {
+                warn!(
+                    "Skipping malformed graph delta {}: {}",
+                    message.stream_id, e
+                );
+                return true;
+            }
+        };
+
+        let pending = match self.pending.as_mut() {
+            Some(pending) => {
+                pending.delta.merge(delta);
+                pending.stream_id = message.stream_id.clone();
+                pending
+            }
+            None => self.pending.insert(PendingDelta {
+                delta,
+                stream_id: message.stream_id.clone(),
+                flush_at: Instant::now() + self.window,
+            }),
+        };
+
+        if pending.delta.len() > self.max_pending {
+            self.pending = None;
+            return false;
+        }
+        true
+    }
+
+    fn due_at(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|pending| pending.flush_at)
+    }
+
+    /// The merged delta, unless the deltas cancelled each other out
+    fn take(&mut self) -> Option<String> {
+        let pending = self.pending.take()?;
+        if pending.delta.is_empty() {
+            return None;
+        }
+
+        let mut delta = serde_json::to_value(&pending.delta).ok()?;
+        delta["streamId"] = serde_json::Value::String(pending.stream_id);
+        Some(delta.to_string())
+    }
+
+    fn clear(&mut self) {
+        self.pending = None;
+    }
+}